@@ -0,0 +1,65 @@
+use std::sync::{Condvar, Mutex};
+
+/// State of the barrier, protected by its lock.
+struct BarrierState {
+    /// Number of workers waiting for the current generation.
+    count: usize,
+    /// Generation of the barrier, incremented each time all the workers arrived.
+    generation: usize,
+    /// Indicates if the barrier has been broken.
+    broken: bool,
+}
+
+/// Synchronization barrier between workers, like `std::sync::Barrier`, except that it can be
+/// broken so that a worker which panicked does not leave the other ones waiting forever.
+pub struct SyncBarrier {
+    state: Mutex<BarrierState>,
+    cvar: Condvar,
+    n_workers: usize,
+}
+
+impl SyncBarrier {
+    /// Creates a new barrier for `n_workers` workers.
+    pub fn new(n_workers: usize) -> Self {
+        SyncBarrier {
+            state: Mutex::new(BarrierState { count: 0, generation: 0, broken: false }),
+            cvar: Condvar::new(),
+            n_workers,
+        }
+    }
+
+    /// Blocks until all the workers called `wait`. Returns `Some(true)` for a single worker, the
+    /// leader, and `Some(false)` for the others. Returns `None` if the barrier is broken.
+    pub fn wait(&self) -> Option<bool> {
+        let mut state = self.state.lock().unwrap();
+        if state.broken {
+            return None;
+        }
+
+        let generation = state.generation;
+        state.count += 1;
+        if state.count < self.n_workers {
+            while generation == state.generation && !state.broken {
+                state = self.cvar.wait(state).unwrap();
+            }
+            if state.broken { None } else { Some(false) }
+        } else {
+            state.count = 0;
+            state.generation += 1;
+            self.cvar.notify_all();
+            Some(true)
+        }
+    }
+
+    /// Breaks the barrier: the waiting workers are released, and the next calls to `wait` return
+    /// immediately.
+    pub fn break_barrier(&self) {
+        self.state.lock().unwrap().broken = true;
+        self.cvar.notify_all();
+    }
+
+    /// Returns true if the barrier has been broken.
+    pub fn is_broken(&self) -> bool {
+        self.state.lock().unwrap().broken
+    }
+}
@@ -0,0 +1,58 @@
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// A source of time for the runtime.
+/// Time-based processes read the clock of the runtime instead of the system time, so that they
+/// can be executed deterministically with a `VirtualClock`.
+pub trait Clock: Send + Sync {
+    /// Returns the time elapsed since the creation of the clock.
+    fn now(&self) -> Duration;
+
+    /// Waits until `duration` has elapsed.
+    fn advance(&self, duration: Duration);
+}
+
+/// Clock following the system time. Advancing it puts the calling thread to sleep.
+pub struct RealClock {
+    start: Instant,
+}
+
+impl RealClock {
+    /// Creates a new `RealClock`, starting now.
+    pub fn new() -> Self {
+        RealClock { start: Instant::now() }
+    }
+}
+
+impl Clock for RealClock {
+    fn now(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn advance(&self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// Clock whose time only moves when it is advanced. Advancing it returns immediately.
+pub struct VirtualClock {
+    now: Mutex<Duration>,
+}
+
+impl VirtualClock {
+    /// Creates a new `VirtualClock`, starting at time zero.
+    pub fn new() -> Self {
+        VirtualClock { now: Mutex::new(Duration::from_secs(0)) }
+    }
+}
+
+impl Clock for VirtualClock {
+    fn now(&self) -> Duration {
+        *self.now.lock().unwrap()
+    }
+
+    fn advance(&self, duration: Duration) {
+        *self.now.lock().unwrap() += duration;
+    }
+}
@@ -1,3 +1,5 @@
+mod barrier;
+pub mod clock;
 mod continuation;
 pub mod process;
 pub mod signal;
@@ -5,15 +7,24 @@ pub mod signal;
 extern crate coco;
 extern crate itertools;
 
-use self::continuation::Continuation;
-use self::process::Process;
+use self::barrier::SyncBarrier;
+use self::clock::{Clock, RealClock};
+pub use self::continuation::Continuation;
+use self::process::{Process, ProcessMut};
+use self::signal::SAwaitIn;
+use self::signal::signal_runtime::ValueRuntime;
 
 use self::coco::deque::{self, Worker, Stealer};
 
-use std::sync::{Arc, Barrier};
-use std::sync::atomic::{AtomicIsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::sync::atomic::{self, AtomicBool, AtomicIsize, AtomicUsize, Ordering};
+use std::any::Any;
+use std::hint;
 use std::mem;
+use std::panic::{self, AssertUnwindSafe};
 use std::thread;
+use std::fmt;
+use std::time::{Duration, Instant};
 
 type JobStealer = Stealer<Box<Continuation<()>>>;
 
@@ -33,8 +44,108 @@ pub struct SharedData {
     n_local_working: AtomicIsize,
     /// Number of workers that, at end of instant, will have work to do on next instant.
     n_global_working: AtomicIsize,
-     /// Synchronization barrier between workers.
-    sync_barrier: Barrier,
+     /// Synchronization barrier between workers. It is broken when a worker panics.
+    sync_barrier: SyncBarrier,
+    /// Number of the current instant, shared by all the workers.
+    instant: AtomicUsize,
+    /// Indicates if the workers stopped because they reached the maximum number of iterations,
+    /// while some work remained.
+    iteration_limit_reached: AtomicBool,
+    /// Indicates if the workers stopped because the execution was cancelled.
+    cancelled: AtomicBool,
+    /// Indicates if the workers stopped because none of them had work left for the next instant.
+    /// If the process has not returned by then, it is deadlocked.
+    out_of_work: AtomicBool,
+    /// Number of workers waiting for some work to steal.
+    n_sleeping: AtomicIsize,
+    /// Lock protecting the sleep of the workers.
+    sleep_lock: Mutex<()>,
+    /// Condition variable notified when some work can be stolen, or when the instant is over.
+    work_available: Condvar,
+    /// Continuations waiting for a time on the clock of the runtime, with this time.
+    timers: Mutex<Vec<(Duration, Box<Continuation<()>>)>>,
+    /// Indicates if some continuations wait for a time at the end of the instant.
+    timers_pending: AtomicBool,
+    /// Number of pending continuations of all the workers: current instant, next instant and end
+    /// of instant.
+    queue_len: AtomicUsize,
+    /// Maximum of `queue_len` observed during the execution.
+    peak_queue_len: AtomicUsize,
+}
+
+impl SharedData {
+    /// Creates the data shared between `n_workers` workers, given the stealing ends of their
+    /// work-stealing queues.
+    pub fn new(runtimes_jobs: Vec<JobStealer>, n_workers: usize) -> Self {
+        SharedData {
+            runtimes_jobs,
+            n_local_working: AtomicIsize::new(n_workers as isize),
+            n_global_working: AtomicIsize::new(0),
+            sync_barrier: SyncBarrier::new(n_workers),
+            instant: AtomicUsize::new(0),
+            iteration_limit_reached: AtomicBool::new(false),
+            cancelled: AtomicBool::new(false),
+            out_of_work: AtomicBool::new(false),
+            n_sleeping: AtomicIsize::new(0),
+            sleep_lock: Mutex::new(()),
+            work_available: Condvar::new(),
+            timers: Mutex::new(vec!()),
+            timers_pending: AtomicBool::new(false),
+            queue_len: AtomicUsize::new(0),
+            peak_queue_len: AtomicUsize::new(0),
+        }
+    }
+
+    /// Blocks the calling worker until some work can be stolen or nobody is working anymore, or
+    /// until `timeout` has elapsed. May return spuriously.
+    fn wait_for_work(&self, timeout: Option<Duration>) {
+        let guard = self.sleep_lock.lock().unwrap();
+        self.n_sleeping.fetch_add(1, Ordering::SeqCst);
+        atomic::fence(Ordering::SeqCst);
+
+        // The condition is checked again now that notifiers know we may sleep, so that no wakeup
+        // can be lost.
+        let nothing_to_steal = self.runtimes_jobs.iter().all(|job| job.len() == 0);
+        if self.n_local_working.load(Ordering::SeqCst) > 0 && nothing_to_steal
+            && !self.sync_barrier.is_broken()
+        {
+            match timeout {
+                Some(timeout) => {
+                    let _guard = self.work_available.wait_timeout(guard, timeout).unwrap();
+                },
+                None => {
+                    let _guard = self.work_available.wait(guard).unwrap();
+                },
+            }
+        }
+
+        self.n_sleeping.fetch_add(-1, Ordering::SeqCst);
+    }
+
+    /// Wakes up the workers waiting for some work.
+    fn notify_workers(&self) {
+        atomic::fence(Ordering::SeqCst);
+        if self.n_sleeping.load(Ordering::SeqCst) > 0 {
+            let _guard = self.sleep_lock.lock().unwrap();
+            self.work_available.notify_all();
+        }
+    }
+
+    /// Releases the other workers after a worker panicked: they stop at their next
+    /// synchronization.
+    fn worker_panicked(&self) {
+        self.sync_barrier.break_barrier();
+        let _guard = self.sleep_lock.lock().unwrap();
+        self.work_available.notify_all();
+    }
+
+    /// Decrements the number of workers working in the instant, and wakes up the waiting workers
+    /// if nobody is working anymore.
+    fn stop_working(&self) {
+        if self.n_local_working.fetch_add(-1, Ordering::SeqCst) == 1 {
+            self.notify_workers();
+        }
+    }
 }
 
 impl ParallelRuntime {
@@ -47,12 +158,7 @@ impl ParallelRuntime {
             (0..n_workers).map(|_| deque::new()).unzip();
 
         // Shared data structure between workers.
-        let shared_data = SharedData {
-            runtimes_jobs: stealer_job_cur_instant,
-            n_local_working: AtomicIsize::new(n_workers as isize),
-            n_global_working: AtomicIsize::new(0),
-            sync_barrier: Barrier::new(n_workers),
-        };
+        let shared_data = SharedData::new(stealer_job_cur_instant, n_workers);
 
         // Instantiation of ParallelRuntime.
         let mut r = ParallelRuntime {
@@ -63,6 +169,7 @@ impl ParallelRuntime {
         // Creation of workers.
         while let Some(cur_instant_worker) = worker_job_cur_instant.pop() {
             let mut runtime = Runtime::new(r.shared_data.clone(), cur_instant_worker);
+            runtime.worker_id = r.runtimes.len();
             r.runtimes.push(runtime);
         };
 
@@ -72,7 +179,13 @@ impl ParallelRuntime {
     /// Start the runtime with a given job.
     /// `max_iters` is the maximum number of iterations that should be done. If it's -1 then there's
     /// no limit.
-    pub fn execute(&mut self, job: Box<Continuation<()>>, max_iters: i32) {
+    /// Returns an error if some worker panicked. In that case the other workers are stopped, and
+    /// the runtime cannot be used anymore.
+    pub fn execute(&mut self, job: Box<Continuation<()>>, max_iters: i32) -> Result<(), ExecError> {
+        self.shared_data.iteration_limit_reached.store(false, Ordering::SeqCst);
+        self.shared_data.cancelled.store(false, Ordering::SeqCst);
+        self.shared_data.out_of_work.store(false, Ordering::SeqCst);
+
         // Give the job to an arbitrarily chosen worker.
         self.runtimes[0].on_current_instant(job);
 
@@ -83,9 +196,15 @@ impl ParallelRuntime {
             let mut b = thread::Builder::new();
             b = b.name("RRS Worker".to_string());
 
+            let shared_data = self.shared_data.clone();
             let worker_continuation = move || {
                 // Thread main loop.
-                runtime.work(max_iters);
+                let result = panic::catch_unwind(AssertUnwindSafe(|| runtime.work(max_iters)));
+                if let Err(payload) = result {
+                    // The other workers must not wait for this one anymore.
+                    shared_data.worker_panicked();
+                    panic::resume_unwind(payload);
+                }
                 runtime
             };
             let handle = b.spawn(worker_continuation).unwrap();
@@ -93,10 +212,333 @@ impl ParallelRuntime {
         }
 
         // Wait for work to be done.
+        let mut panic_message = None;
         while let Some(x) = join_handles.pop() {
-            self.runtimes.push(x.join().unwrap());
+            match x.join() {
+                Ok(runtime) => self.runtimes.push(runtime),
+                Err(payload) => {
+                    panic_message.get_or_insert_with(|| describe_panic(&payload));
+                },
+            }
         };
+
+        match panic_message {
+            Some(message) => Err(ExecError::WorkerPanicked(message)),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns true if the last execution stopped because it reached the maximum number of
+    /// iterations while some work remained.
+    pub fn iteration_limit_reached(&self) -> bool {
+        self.shared_data.iteration_limit_reached.load(Ordering::SeqCst)
+    }
+
+    /// Returns true if the last execution stopped because it was cancelled.
+    pub fn cancelled(&self) -> bool {
+        self.shared_data.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Returns true if the last execution stopped because no worker had work left, i.e. every
+    /// remaining continuation waits for something that will never happen.
+    pub fn out_of_work(&self) -> bool {
+        self.shared_data.out_of_work.load(Ordering::SeqCst)
+    }
+
+    /// Sets the token that cancels the execution. The workers check it at the end of each instant.
+    pub fn set_cancellation(&mut self, token: Option<CancellationToken>) {
+        for runtime in self.runtimes.iter_mut() {
+            runtime.set_cancellation(token.clone());
+        }
+    }
+
+    /// Sets the maximum number of pending continuations each worker accepts through
+    /// `Runtime::try_on_current_instant`. `None` removes the limit.
+    pub fn set_queue_cap(&mut self, cap: Option<usize>) {
+        for runtime in self.runtimes.iter_mut() {
+            runtime.set_queue_cap(cap);
+        }
+    }
+
+    /// Sets the maximum number of jobs a worker steals at once from another worker.
+    pub fn set_steal_batch(&mut self, steal_batch: usize) {
+        for runtime in self.runtimes.iter_mut() {
+            runtime.set_steal_batch(steal_batch);
+        }
+    }
+
+    /// Sets how an idle worker waits before looking again for some work to steal.
+    pub fn set_backoff<B>(&mut self, backoff: B) where B: Into<Backoff> {
+        let backoff = backoff.into();
+        for runtime in self.runtimes.iter_mut() {
+            runtime.set_backoff(backoff);
+        }
+    }
+
+    /// Sets the clock read by the time-based processes of all the workers.
+    pub fn set_clock(&mut self, clock: Arc<Clock>) {
+        for runtime in self.runtimes.iter_mut() {
+            runtime.set_clock(clock.clone());
+        }
+    }
+
+    /// Returns the statistics of the runtime, gathered over all the workers.
+    /// The peak queue length is the largest number of continuations pending at once in all the
+    /// workers, and the number of instants is the one of the worker which went the furthest. The
+    /// other counters are summed.
+    pub fn stats(&self) -> RuntimeStats {
+        let peak_queue_len = self.shared_data.peak_queue_len.load(Ordering::SeqCst);
+        self.runtimes.iter().map(|r| r.stats()).fold(RuntimeStats::default(), |acc, s| {
+            RuntimeStats {
+                peak_queue_len,
+                jobs_run: acc.jobs_run + s.jobs_run,
+                jobs_stolen: acc.jobs_stolen + s.jobs_stolen,
+                instants: acc.instants.max(s.instants),
+                idle_time: acc.idle_time + s.idle_time,
+            }
+        })
+    }
+}
+
+/// Sequential runtime structure.
+/// All the continuations are executed on the calling thread, without any stealing or
+/// synchronization between workers, which makes the execution deterministic.
+pub struct SequentialRuntime {
+    /// The only worker.
+    runtime: Runtime,
+}
+
+impl SequentialRuntime {
+    /// Creates a new `SequentialRuntime`.
+    pub fn new() -> Self {
+        // The worker does not share its queue: nobody can steal its jobs.
+        let (worker, _) = deque::new();
+        let shared_data = SharedData::new(vec!(), 1);
+
+        SequentialRuntime {
+            runtime: Runtime::new(Arc::new(shared_data), worker),
+        }
+    }
+
+    /// Start the runtime with a given job, on the calling thread.
+    /// `max_iters` is the maximum number of iterations that should be done. If it's -1 then there's
+    /// no limit.
+    pub fn execute(&mut self, job: Box<Continuation<()>>, max_iters: i32) {
+        self.runtime.on_current_instant(job);
+        self.runtime.work(max_iters);
+    }
+
+    /// Sets the maximum number of pending continuations accepted through
+    /// `Runtime::try_on_current_instant`. `None` removes the limit.
+    pub fn set_queue_cap(&mut self, cap: Option<usize>) {
+        self.runtime.set_queue_cap(cap);
+    }
+
+    /// Sets the clock read by the time-based processes.
+    pub fn set_clock(&mut self, clock: Arc<Clock>) {
+        self.runtime.set_clock(clock);
+    }
+
+    /// Returns the statistics of the runtime.
+    pub fn stats(&self) -> RuntimeStats {
+        self.runtime.stats()
+    }
+}
+
+/// Returns the message of a panic, given its payload.
+fn describe_panic(payload: &Box<Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<Any>".to_string()
+    }
+}
+
+/// Reasons why the execution of a process did not return a value.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ExecError {
+    /// Nothing was left to do, but the process did not return.
+    Deadlock,
+    /// The maximum number of iterations was reached before the process returned.
+    IterationLimit,
+    /// A worker panicked during the execution, with the given message.
+    WorkerPanicked(String),
+    /// The execution was cancelled before the process returned.
+    Cancelled,
+}
+
+impl fmt::Display for ExecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ExecError::Deadlock => write!(f, "Deadlock detected!"),
+            ExecError::IterationLimit => write!(f, "Iteration limit reached."),
+            ExecError::WorkerPanicked(ref message) => write!(f, "A worker panicked: {}", message),
+            ExecError::Cancelled => write!(f, "Execution cancelled."),
+        }
+    }
+}
+
+/// Token used to stop a running execution from outside. Clones of a token share the same state.
+#[derive(Clone, Debug, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Creates a new token, which is not cancelled.
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    /// Cancels the executions using this token. They stop at the end of the current instant.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns true if the token has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::SeqCst)
+    }
+}
+
+/// Configuration of a parallel execution.
+#[derive(Clone, Debug)]
+pub struct RuntimeConfig {
+    /// Number of workers. At least one worker is created.
+    pub workers: usize,
+    /// Maximum number of instants. If it's -1 then there's no limit.
+    pub max_iters: i32,
+    /// How an idle worker waits before looking again for some work.
+    pub backoff: Backoff,
+    /// Token that cancels the execution. `None` makes the execution impossible to cancel.
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl RuntimeConfig {
+    /// Creates the default configuration: one worker per logical CPU, no limit on the number of
+    /// instants, and idle workers waiting until they are notified.
+    pub fn new() -> Self {
+        RuntimeConfig {
+            workers: available_workers(),
+            max_iters: -1,
+            backoff: Backoff::Wait,
+            cancellation: None,
+        }
     }
+
+    /// Sets the number of workers.
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers;
+        self
+    }
+
+    /// Sets the maximum number of instants.
+    pub fn max_iters(mut self, max_iters: i32) -> Self {
+        self.max_iters = max_iters;
+        self
+    }
+
+    /// Sets how an idle worker waits before looking again for some work. A maximum waiting time
+    /// `Some(duration)` is equivalent to `Backoff::Sleep(duration)`, and `None` to `Backoff::Wait`.
+    pub fn backoff<B>(mut self, backoff: B) -> Self where B: Into<Backoff> {
+        self.backoff = backoff.into();
+        self
+    }
+
+    /// Sets the token that cancels the execution.
+    pub fn cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        RuntimeConfig::new()
+    }
+}
+
+/// Strategy followed by an idle worker, while other workers are still executing the instant.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Backoff {
+    /// Sleeps until some work can be stolen or the instant is over.
+    Wait,
+    /// Sleeps at most the given duration, or until some work can be stolen or the instant is
+    /// over.
+    Sleep(Duration),
+    /// Yields to the other threads.
+    Yield,
+    /// Busy waits, which gives the lowest latency but keeps a CPU busy.
+    Spin,
+    /// Spins, then yields, then sleeps for a duration doubling from `min` up to `max`, as the
+    /// worker stays idle. Suits both short instants and long waits.
+    Exponential { min: Duration, max: Duration },
+}
+
+/// Number of idle rounds of `Backoff::Exponential` spent spinning.
+const BACKOFF_SPIN_ROUNDS: u32 = 4;
+/// Number of idle rounds of `Backoff::Exponential` spent yielding, once done spinning.
+const BACKOFF_YIELD_ROUNDS: u32 = 4;
+
+impl Backoff {
+    /// Waits for the `round`-th time in a row, starting from 0, for some work in `manager`.
+    fn idle(&self, manager: &SharedData, round: u32) {
+        match *self {
+            Backoff::Wait => manager.wait_for_work(None),
+            Backoff::Sleep(duration) => manager.wait_for_work(Some(duration)),
+            Backoff::Yield => thread::yield_now(),
+            Backoff::Spin => hint::spin_loop(),
+            Backoff::Exponential { min, max } => {
+                if round < BACKOFF_SPIN_ROUNDS {
+                    hint::spin_loop();
+                } else if round < BACKOFF_SPIN_ROUNDS + BACKOFF_YIELD_ROUNDS {
+                    thread::yield_now();
+                } else {
+                    let exponent = (round - BACKOFF_SPIN_ROUNDS - BACKOFF_YIELD_ROUNDS).min(16);
+                    let duration = min.checked_mul(1 << exponent).map_or(max, |d| d.min(max));
+                    manager.wait_for_work(Some(duration));
+                }
+            },
+        }
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Backoff::Wait
+    }
+}
+
+impl From<Option<Duration>> for Backoff {
+    fn from(backoff: Option<Duration>) -> Self {
+        match backoff {
+            Some(duration) => Backoff::Sleep(duration),
+            None => Backoff::Wait,
+        }
+    }
+}
+
+/// Returns the number of logical CPUs, or 1 if it cannot be determined.
+pub fn available_workers() -> usize {
+    thread::available_parallelism().map(|n| n.get()).unwrap_or(1).max(1)
+}
+
+/// Statistics collected by a runtime during its execution.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RuntimeStats {
+    /// Maximum number of pending continuations (current instant, next instant and end of
+    /// instant) observed at once.
+    pub peak_queue_len: usize,
+    /// Number of continuations executed.
+    pub jobs_run: usize,
+    /// Number of continuations stolen from other workers.
+    pub jobs_stolen: usize,
+    /// Number of instants completed.
+    pub instants: u64,
+    /// Time spent waiting for some work to steal.
+    pub idle_time: Duration,
 }
 
 /// Runtime for executing reactive continuations.
@@ -110,10 +552,26 @@ pub struct Runtime {
     end_of_instant: Vec<Box<Continuation<()>>>,
     /// Pointer to the shared data between workers.
     manager:        Arc<SharedData>,
+    /// Maximum number of pending continuations accepted by `try_on_current_instant`.
+    queue_cap:      Option<usize>,
+    /// Statistics of the runtime.
+    stats:          RuntimeStats,
+    /// Abort flags of the `do_until` blocks enclosing the continuation being executed.
+    abort_flags:    Vec<Arc<AtomicBool>>,
+    /// Catching points of the `catch_unwind` processes enclosing the continuation being executed.
+    catch_points:   Vec<Arc<CatchPoint>>,
+    /// Maximum number of jobs stolen at once from another worker.
+    steal_batch:    usize,
+    /// How the worker waits when idle, before looking again for some work.
+    backoff:        Backoff,
+    /// Clock read by the time-based processes.
+    clock:          Arc<Clock>,
+    /// Token that cancels the execution.
+    cancellation:   Option<CancellationToken>,
+    /// Index of the worker among the workers of its parallel runtime.
+    worker_id:      usize,
 }
 
-use std::time;
-
 impl Runtime {
     /// Creates a new `Runtime`.
     pub fn new(manager: Arc<SharedData>,
@@ -123,55 +581,217 @@ impl Runtime {
             next_instant: vec!(),
             end_of_instant: vec!(),
             manager,
+            queue_cap: None,
+            stats: RuntimeStats::default(),
+            abort_flags: vec!(),
+            catch_points: vec!(),
+            steal_batch: 1,
+            backoff: Backoff::Wait,
+            clock: Arc::new(RealClock::new()),
+            cancellation: None,
+            worker_id: 0,
+        }
+    }
+
+    /// Returns the statistics of the runtime.
+    pub fn stats(&self) -> RuntimeStats {
+        self.stats
+    }
+
+    /// Returns the index of the worker executing the runtime, between 0 and the number of workers
+    /// of its parallel runtime (excluded).
+    pub fn worker_id(&self) -> usize {
+        self.worker_id
+    }
+
+    /// Returns the number of the current instant, starting from 0.
+    /// Continuations executed at the end of an instant still see the number of this instant.
+    pub fn current_instant(&self) -> u64 {
+        self.manager.instant.load(Ordering::SeqCst) as u64
+    }
+
+    /// Sets the maximum number of pending continuations accepted by `try_on_current_instant`.
+    /// `None` removes the limit.
+    pub fn set_queue_cap(&mut self, cap: Option<usize>) {
+        self.queue_cap = cap;
+    }
+
+    /// Sets the maximum number of jobs stolen at once from another worker. Once a job has been
+    /// stolen, up to `steal_batch - 1` other jobs are moved from the same worker to the local
+    /// queue, which amortizes the cost of stealing many small jobs.
+    pub fn set_steal_batch(&mut self, steal_batch: usize) {
+        self.steal_batch = steal_batch.max(1);
+    }
+
+    /// Sets how the worker waits when idle, before looking again for some work to steal.
+    pub fn set_backoff<B>(&mut self, backoff: B) where B: Into<Backoff> {
+        self.backoff = backoff.into();
+    }
+
+    /// Sets the clock read by the time-based processes.
+    pub fn set_clock(&mut self, clock: Arc<Clock>) {
+        self.clock = clock;
+    }
+
+    /// Returns the clock read by the time-based processes.
+    pub fn clock(&self) -> &Clock {
+        &*self.clock
+    }
+
+    /// Sets the token that cancels the execution.
+    pub fn set_cancellation(&mut self, token: Option<CancellationToken>) {
+        self.cancellation = token;
+    }
+
+    /// Returns the number of pending continuations of the runtime.
+    pub fn queue_len(&self) -> usize {
+        self.cur_instant.len() + self.next_instant.len() + self.end_of_instant.len()
+    }
+
+    /// Returns true if the number of pending continuations exceeds the queue cap.
+    pub fn is_saturated(&self) -> bool {
+        match self.queue_cap {
+            Some(cap) => self.queue_len() >= cap,
+            None => false,
+        }
+    }
+
+    /// Registers a continuation to execute on the current instant, unless the runtime is
+    /// saturated. In that case, the continuation is given back so that the producer can try
+    /// again once the queue has drained.
+    pub fn try_on_current_instant(&mut self, c: Box<Continuation<()>>)
+        -> Result<(), Box<Continuation<()>>>
+    {
+        if self.is_saturated() {
+            Err(c)
+        } else {
+            self.on_current_instant(c);
+            Ok(())
+        }
+    }
+
+    /// Schedules a continuation on the current instant. Together with `schedule_next` and
+    /// `schedule_end`, this lets processes be implemented outside of the engine.
+    ///
+    /// Like every continuation registered by the runtime, `c` is guarded by the enclosing
+    /// `do_until` blocks: it is dropped without being called if one of them has been aborted.
+    pub fn schedule_current(&mut self, c: Box<Continuation<()>>) {
+        self.on_current_instant(c);
+    }
+
+    /// Schedules a continuation on the next instant.
+    pub fn schedule_next(&mut self, c: Box<Continuation<()>>) {
+        self.on_next_instant(c);
+    }
+
+    /// Schedules a continuation at the end of the current instant, once all the continuations
+    /// of the instant have been executed and the signals have been released.
+    ///
+    /// Runtime calls made by `c` behave as if they where executed during the next instant:
+    /// continuations it schedules with `schedule_current` run on the next instant, and those it
+    /// schedules with `schedule_next` on the one after. It still sees the number of the instant
+    /// which just ended in `current_instant`.
+    pub fn schedule_end(&mut self, c: Box<Continuation<()>>) {
+        self.on_end_of_instant(c);
+    }
+
+    /// Counts a continuation about to be added to the queues, and updates the peak queue length of
+    /// all the workers. It must be called before the continuation is pushed: once pushed, it may be
+    /// stolen and run by another worker, which uncounts it.
+    fn count_job(&mut self) {
+        let total = self.manager.queue_len.fetch_add(1, Ordering::Relaxed) + 1;
+        self.manager.peak_queue_len.fetch_max(total, Ordering::Relaxed);
+    }
+
+    /// Updates the peak queue length of the worker.
+    fn update_peak_queue_len(&mut self) {
+        let len = self.queue_len();
+        if len > self.stats.peak_queue_len {
+            self.stats.peak_queue_len = len;
         }
     }
 
+    /// Runs a continuation taken from the queues.
+    fn run_job(&mut self, c: Box<Continuation<()>>) {
+        self.manager.queue_len.fetch_sub(1, Ordering::Relaxed);
+        self.stats.jobs_run += 1;
+        c.call_box(self, ());
+    }
+
     /// Worker loop that executes at most `max_iter` instants.
     /// If `max_iter` is -1 there is no limit.
     pub fn work(&mut self, max_iter: i32) {
         let mut n_iter = 0;
 
         loop {
-            // Execution count check.
+            // Execution count check. All the workers stop at the same iteration, since each of
+            // them counts the instants it runs, and the other exits below are decided from the
+            // shared state fixed before the last barrier: all the workers cross the same barriers.
             n_iter += 1;
             if max_iter != -1 && n_iter > max_iter {
+                // The previous instant ended with some work to do.
+                self.manager.iteration_limit_reached.store(true, Ordering::SeqCst);
                 break;
             }
 
             // Step 1.
             // Do all the local work.
             while let Some(c) = self.cur_instant.pop() {
-                c.call_box(self, ());
+                self.run_job(c);
             }
             // Decrement the number of working threads when work is done.
-            self.manager.n_local_working.fetch_add(-1, Ordering::Relaxed);
+            self.manager.stop_working();
 
             // While someone is working (and might add something on his queue)
-            while self.manager.n_local_working.load(Ordering::Relaxed) > 0 {
+            let mut idle_rounds = 0;
+            while self.manager.n_local_working.load(Ordering::SeqCst) > 0
+                && !self.manager.sync_barrier.is_broken()
+            {
                 let mut stolen = false;
 
                 // Try to steal work and unroll all local work then.
-                while let Some(c) = self.manager.runtimes_jobs.iter().filter_map(|job| {
-                    job.steal()
+                let manager = self.manager.clone();
+                while let Some((c, victim)) = manager.runtimes_jobs.iter().filter_map(|job| {
+                    job.steal().map(|c| (c, job))
                 }).next() {
                     stolen = true;
-                    self.manager.n_local_working.fetch_add(1, Ordering::Relaxed);
-                    c.call_box(self, ());
+                    idle_rounds = 0;
+                    self.manager.n_local_working.fetch_add(1, Ordering::SeqCst);
+                    self.stats.jobs_stolen += 1;
+
+                    // Take a batch of jobs from the same worker.
+                    for _ in 1..self.steal_batch {
+                        match victim.steal() {
+                            Some(c) => {
+                                self.stats.jobs_stolen += 1;
+                                self.cur_instant.push(c);
+                            },
+                            None => break,
+                        }
+                    }
+
+                    self.run_job(c);
                     while let Some(c) = self.cur_instant.pop() {
-                        c.call_box(self, ());
+                        self.run_job(c);
                     }
-                    self.manager.n_local_working.fetch_add(-1, Ordering::Relaxed);
+                    self.manager.stop_working();
                 }
 
-                // Nothing was stolen but someone is still working, try to steal later on.
+                // Nothing was stolen but someone is still working, wait for some work to steal.
                 if !stolen {
-                    thread::sleep(time::Duration::from_micros(1));
+                    let start = Instant::now();
+                    self.backoff.idle(&self.manager, idle_rounds);
+                    self.stats.idle_time += start.elapsed();
+                    idle_rounds += 1;
                 }
             }
 
             // Synchronization barrier, and reset global working threads counter.
-            if self.manager.sync_barrier.wait().is_leader() {
-                self.manager.n_global_working.store(0, Ordering::Relaxed);
+            // All the synchronizations stop the worker if another one panicked.
+            match self.manager.sync_barrier.wait() {
+                Some(true) => self.manager.n_global_working.store(0, Ordering::Relaxed),
+                Some(false) => (),
+                None => break,
             }
 
             // Step 2.
@@ -184,10 +804,26 @@ impl Runtime {
 
             // Do all the local work.
             while let Some(c) = end_of_instant.pop() {
-                c.call_box(self, ());
+                self.run_job(c);
             }
 
-            self.manager.sync_barrier.wait();
+            // Synchronization barrier, and move to the next instant. Nobody executes continuations
+            // until the next barrier, so all the workers agree on the instant number.
+            let leader = match self.manager.sync_barrier.wait() {
+                Some(leader) => leader,
+                None => break,
+            };
+            self.stats.instants += 1;
+            if leader {
+                self.manager.instant.fetch_add(1, Ordering::SeqCst);
+                let timers_pending = !self.manager.timers.lock().unwrap().is_empty();
+                self.manager.timers_pending.store(timers_pending, Ordering::SeqCst);
+                // The token is checked by a single worker, so that all the workers stop at the
+                // same instant.
+                if self.cancellation.as_ref().map_or(false, |token| token.is_cancelled()) {
+                    self.manager.cancelled.store(true, Ordering::SeqCst);
+                }
+            }
 
             // Check if the worker will have work to do later;
             let local_work_to_do = self.end_of_instant.len() > 0 || self.next_instant.len() > 0 || self.cur_instant.len() > 0;
@@ -196,52 +832,284 @@ impl Runtime {
             if local_work_to_do {
                 self.manager.n_global_working.fetch_add(1, Ordering::Relaxed);
             }
-            self.manager.n_local_working.fetch_add(1, Ordering::Relaxed);
-            self.manager.sync_barrier.wait();
+            self.manager.n_local_working.fetch_add(1, Ordering::SeqCst);
+            let leader = match self.manager.sync_barrier.wait() {
+                Some(leader) => leader,
+                None => break,
+            };
 
             let work_to_do = self.manager.n_global_working.load(Ordering::Relaxed) > 0;
+            let timers_pending = self.manager.timers_pending.load(Ordering::SeqCst);
 
-            if !work_to_do {
+            if !work_to_do && !timers_pending {
+                // Nothing can wake up the remaining continuations anymore.
+                self.manager.out_of_work.store(true, Ordering::SeqCst);
                 break;
             }
+            if self.manager.cancelled.load(Ordering::SeqCst) {
+                break;
+            }
+            if leader && timers_pending {
+                // The other workers cannot end the instant before the leader stops working.
+                self.release_timers(!work_to_do);
+            }
 
         };
     }
 
+    /// Moves the continuations whose time has come on the current instant. If `wait` is true,
+    /// first waits on the clock until the earliest of them can be executed.
+    fn release_timers(&mut self, wait: bool) {
+        if wait {
+            let earliest = self.manager.timers.lock().unwrap().iter().map(|&(t, _)| t).min();
+            if let Some(earliest) = earliest {
+                let now = self.clock.now();
+                if earliest > now {
+                    self.clock.advance(earliest - now);
+                }
+            }
+        }
+
+        let now = self.clock.now();
+        let timers = mem::replace(&mut *self.manager.timers.lock().unwrap(), vec!());
+        let (ready, pending): (Vec<_>, Vec<_>) = timers.into_iter().partition(|&(t, _)| t <= now);
+        self.manager.timers.lock().unwrap().extend(pending);
+        for (_, c) in ready {
+            self.on_current_instant(c);
+        }
+    }
+
+    /// Wraps `c` so that it becomes inert if one of the `do_until` blocks enclosing the current
+    /// continuation is aborted before `c` is called, and so that its panics are caught by the
+    /// `catch_unwind` processes enclosing the current continuation.
+    fn guard<C>(&self, c: C) -> Guarded<C> {
+        Guarded {
+            abort_flags: self.abort_flags.clone(),
+            catch_points: self.catch_points.clone(),
+            continuation: c,
+        }
+    }
+
+    /// Same as `guard`, but avoids the wrapping when no `do_until` block nor `catch_unwind` process
+    /// encloses the current continuation.
+    fn guard_box(&self, c: Box<Continuation<()>>) -> Box<Continuation<()>> {
+        if self.abort_flags.is_empty() && self.catch_points.is_empty() {
+            c
+        } else {
+            Box::new(self.guard(move |r: &mut Runtime, ()| c.call_box(r, ())))
+        }
+    }
+
+    /// Calls `f` with `abort_flags` as enclosing `do_until` blocks, then restores the current ones.
+    fn with_abort_flags<F>(&mut self, abort_flags: Vec<Arc<AtomicBool>>, f: F)
+        where F: FnOnce(&mut Runtime)
+    {
+        let saved = mem::replace(&mut self.abort_flags, abort_flags);
+        f(self);
+        self.abort_flags = saved;
+    }
+
+    /// Calls `f` with `abort_flags` as enclosing `do_until` blocks and `catch_points` as enclosing
+    /// `catch_unwind` processes, then restores the current ones. A panic raised by `f` is handed to
+    /// the innermost `catch_unwind` process, if there is one.
+    fn within<F>(&mut self, abort_flags: Vec<Arc<AtomicBool>>, catch_points: Vec<Arc<CatchPoint>>,
+                 f: F)
+        where F: FnOnce(&mut Runtime)
+    {
+        let saved_flags = mem::replace(&mut self.abort_flags, abort_flags);
+        let saved_points = mem::replace(&mut self.catch_points, catch_points);
+        match self.catch_points.last().cloned() {
+            None => f(self),
+            Some(catch_point) => {
+                if let Err(payload) = panic::catch_unwind(AssertUnwindSafe(|| f(self))) {
+                    catch_point.catch(self, payload);
+                }
+            }
+        }
+        self.abort_flags = saved_flags;
+        self.catch_points = saved_points;
+    }
+
+    /// Calls `f` outside of any `do_until` block or `catch_unwind` process. Used to register the
+    /// internal continuations of signals, which must not be discarded when the process that
+    /// triggered them is aborted.
+    fn unguarded<F>(&mut self, f: F) where F: FnOnce(&mut Runtime) {
+        self.within(vec!(), vec!(), f);
+    }
+
     /// Registers a continuation to execute on the current instant.
     fn on_current_instant(&mut self, c: Box<Continuation<()>>) {
+        let c = self.guard_box(c);
+        self.count_job();
         self.cur_instant.push(c);
+        self.update_peak_queue_len();
+        // Some idle worker may steal the continuation.
+        self.manager.notify_workers();
     }
 
     /// Registers a continuation to execute at the next instant.
     fn on_next_instant(&mut self, c: Box<Continuation<()>>) {
+        let c = self.guard_box(c);
+        self.count_job();
         self.next_instant.push(c);
+        self.update_peak_queue_len();
+    }
+
+    /// Registers a continuation to execute at the first instant starting once `time` has been
+    /// reached on the clock of the runtime. The workers are not blocked in the meantime.
+    fn on_time(&mut self, time: Duration, c: Box<Continuation<()>>) {
+        let c = self.guard_box(c);
+        self.manager.timers.lock().unwrap().push((time, c));
     }
 
     /// Registers a continuation to execute at the end of the instant. Runtime calls for `c`
     /// behave as if they where executed during the next instant.
     fn on_end_of_instant(&mut self, c: Box<Continuation<()>>) {
+        let c = self.guard_box(c);
+        self.count_job();
         self.end_of_instant.push(c);
+        self.update_peak_queue_len();
+    }
+}
+
+/// A continuation which is discarded if one of its enclosing `do_until` blocks has been aborted.
+/// Otherwise, it is called within these blocks, and within its enclosing `catch_unwind` processes.
+struct Guarded<C> {
+    abort_flags: Vec<Arc<AtomicBool>>,
+    catch_points: Vec<Arc<CatchPoint>>,
+    continuation: C,
+}
+
+impl<C, V> Continuation<V> for Guarded<C> where C: Continuation<V>, V: 'static {
+    fn call(self, runtime: &mut Runtime, value: V) {
+        if self.abort_flags.iter().any(|flag| flag.load(Ordering::SeqCst)) {
+            // The continuation is dropped.
+            return;
+        }
+        let continuation = self.continuation;
+        runtime.within(self.abort_flags, self.catch_points, move |r: &mut Runtime| {
+            continuation.call(r, value);
+        });
+    }
+
+    fn call_box(self: Box<Self>, runtime: &mut Runtime, value: V) {
+        (*self).call(runtime, value);
     }
 }
 
-use std::sync::{Mutex};
+/// Catching point of a `catch_unwind` process, to which the panics of the continuations executed
+/// within the process are handed.
+struct CatchPoint {
+    /// Abort flag of the process, raised by a panic to discard the remaining continuations of the
+    /// process.
+    aborted: Arc<AtomicBool>,
+    /// Called with the payload of the panic.
+    handler: Box<Fn(&mut Runtime, Box<Any + Send>) + Send + Sync>,
+}
+
+impl CatchPoint {
+    fn catch(&self, runtime: &mut Runtime, payload: Box<Any + Send>) {
+        self.aborted.store(true, Ordering::SeqCst);
+        (self.handler)(runtime, payload);
+    }
+}
 
 /// Executes `process` in parallel runtime.
 pub fn execute_process<P>(process: P) -> P::Value where P:Process, P::Value: Send {
-    match execute_process_steps(process, 6, -1) {
-        Some(x) => x,
-        None => panic!("Deadlock detected!"),
+    match try_execute_process(process) {
+        Ok(x) => x,
+        Err(e) => panic!("{}", e),
     }
 }
 
 /// Executes `max_iters` steps of `process` on `n_workers` in a parallel runtime.
 pub fn execute_process_steps<P>(process: P, n_workers: usize, max_iters: i32) -> Option<P::Value> where P:Process, P::Value: Send {
-    // let mut r = Runtime::new(1);
+    try_execute_process_steps(process, n_workers, max_iters).ok()
+}
+
+/// Executes `max_iters` steps of `process` on `n_workers` in a parallel runtime, and returns the
+/// statistics of the runtime along with the value of the process.
+pub fn execute_process_steps_with_stats<P>(process: P, n_workers: usize, max_iters: i32) -> (Option<P::Value>, RuntimeStats) where P:Process, P::Value: Send {
+    let (result, stats) = run_with_config(process, RuntimeConfig::new().workers(n_workers).max_iters(max_iters));
+    (result.ok(), stats)
+}
+
+/// Executes `process` in parallel runtime. Returns an error describing why the process did not
+/// return a value.
+pub fn try_execute_process<P>(process: P) -> Result<P::Value, ExecError> where P:Process, P::Value: Send {
+    try_execute_process_steps(process, 6, -1)
+}
+
+/// Executes `max_iters` steps of `process` on `n_workers` in a parallel runtime. Returns an error
+/// describing why the process did not return a value.
+pub fn try_execute_process_steps<P>(process: P, n_workers: usize, max_iters: i32) -> Result<P::Value, ExecError> where P:Process, P::Value: Send {
+    execute_with_config(process, RuntimeConfig::new().workers(n_workers).max_iters(max_iters))
+}
+
+/// Executes `process` in parallel runtime, with one worker per logical CPU.
+pub fn execute_process_auto<P>(process: P) -> P::Value where P:Process, P::Value: Send {
+    match execute_with_config(process, RuntimeConfig::new()) {
+        Ok(x) => x,
+        Err(e) => panic!("{}", e),
+    }
+}
+
+/// Executes `process` in a parallel runtime configured by `config`. Returns an error describing
+/// why the process did not return a value.
+pub fn execute_with_config<P>(process: P, config: RuntimeConfig) -> Result<P::Value, ExecError> where P:Process, P::Value: Send {
+    run_with_config(process, config).0
+}
+
+/// Same as `execute_with_config`, but also returns the statistics of the runtime.
+fn run_with_config<P>(process: P, config: RuntimeConfig) -> (Result<P::Value, ExecError>, RuntimeStats) where P:Process, P::Value: Send {
+    let result: Arc<Mutex<Option<P::Value>>> = Arc::new(Mutex::new(None));
+    let result2 = result.clone();
+
+    let mut r = ParallelRuntime::new(config.workers.max(1));
+    r.set_backoff(config.backoff);
+    r.set_cancellation(config.cancellation);
+
+    let todo = Box::new(move |mut runtime: &mut Runtime, ()| {
+        process.call(&mut runtime, move |_: &mut Runtime, value: P::Value| {
+            *result2.lock().unwrap() = Some(value);
+        });
+    });
+
+    if let Err(e) = r.execute(todo, config.max_iters) {
+        return (Err(e), r.stats());
+    }
+
+    let value = match Arc::try_unwrap(result) {
+        Ok(x) => x.into_inner().unwrap(),
+        _ => None,
+    };
+
+    // A process which keeps working is never reported as deadlocked: the workers only run out of
+    // work when nothing remains to be executed in the next instants.
+    let result = match value {
+        Some(x) => Ok(x),
+        None if r.out_of_work() => Err(ExecError::Deadlock),
+        None if r.cancelled() => Err(ExecError::Cancelled),
+        None if r.iteration_limit_reached() => Err(ExecError::IterationLimit),
+        None => unreachable!("The workers stopped for no reason."),
+    };
+    (result, r.stats())
+}
+
+/// Executes `process` in sequential runtime, on the calling thread.
+pub fn execute_process_sequential<P>(process: P) -> P::Value where P:Process, P::Value: Send {
+    match execute_process_sequential_steps(process, -1) {
+        Some(x) => x,
+        None => panic!("Deadlock detected!"),
+    }
+}
+
+/// Executes `max_iters` steps of `process` in a sequential runtime, on the calling thread.
+pub fn execute_process_sequential_steps<P>(process: P, max_iters: i32) -> Option<P::Value> where P:Process, P::Value: Send {
     let result: Arc<Mutex<Option<P::Value>>> = Arc::new(Mutex::new(None));
     let result2 = result.clone();
 
-    let mut r = ParallelRuntime::new(n_workers);
+    let mut r = SequentialRuntime::new();
 
     let todo = Box::new(move |mut runtime: &mut Runtime, ()| {
         process.call(&mut runtime, move |_: &mut Runtime, value: P::Value| {
@@ -257,6 +1125,25 @@ pub fn execute_process_steps<P>(process: P, n_workers: usize, max_iters: i32) ->
     }
 }
 
+/// Executes `program` for `instants` instants in a sequential runtime, and returns all the values
+/// delivered by `signal` during this run, in order.
+pub fn observe_signal<S, P>(signal: &S, program: P, instants: i32) -> Vec<<S::VR as ValueRuntime>::V2>
+    where S: SAwaitIn + 'static, P: Process, P::Value: Send, <S::VR as ValueRuntime>::V2: 'static
+{
+    let values = Arc::new(Mutex::new(vec!()));
+    let values2 = values.clone();
+
+    let collect = move |v| {
+        values2.lock().unwrap().push(v);
+    };
+    let observer = signal.await_in().map(collect).loop_inf();
+
+    execute_process_sequential_steps(observer.join(program), instants);
+
+    let mut values = values.lock().unwrap();
+    mem::replace(&mut *values, vec!())
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -268,12 +1155,13 @@ mod tests {
     use engine;
     use engine::signal::*;
 
-    use std::sync::atomic::AtomicIsize;
-    use std::sync::Barrier;
     use engine::continuation::Continuation;
-    use engine::SharedData;
+    use engine::{SharedData, ParallelRuntime, SequentialRuntime, RuntimeConfig, CancellationToken, Backoff};
+    use engine::clock::{Clock, VirtualClock};
+    use self::test::Bencher;
     use self::coco::deque;
     use std::sync::{Arc, Mutex};
+    use std::{thread, time};
 
     #[test]
     fn test_continuation_pause() {
@@ -288,12 +1176,7 @@ mod tests {
         let (worker, _) = deque::new();
 
         // Shared data structure between workers.
-        let shared_data = SharedData {
-            runtimes_jobs: vec!(),
-            n_local_working: AtomicIsize::new(1),
-            n_global_working: AtomicIsize::new(0),
-            sync_barrier: Barrier::new(1),
-        };
+        let shared_data = SharedData::new(vec!(), 1);
 
         let shared_data = Arc::new(shared_data);
 
@@ -327,12 +1210,7 @@ mod tests {
         let (worker, _) = deque::new();
 
         // Shared data structure between workers.
-        let shared_data = SharedData {
-            runtimes_jobs: vec!(),
-            n_local_working: AtomicIsize::new(1),
-            n_global_working: AtomicIsize::new(0),
-            sync_barrier: Barrier::new(1),
-        };
+        let shared_data = SharedData::new(vec!(), 1);
 
         let shared_data = Arc::new(shared_data);
 
@@ -359,6 +1237,16 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_instant() {
+        let p = process::instant().join(value(()).pause().pause().then(process::instant()));
+        assert_eq!(engine::execute_process(p), (0, 2));
+
+        // All the workers agree on the instant number.
+        let ps = (0..100).map(|_| value(()).pause().then(process::instant())).collect();
+        assert_eq!(engine::execute_process(multi_join(ps)), vec![1; 100]);
+    }
+
     #[test]
     fn test_flatten() {
         let p = value(42);
@@ -443,6 +1331,340 @@ mod tests {
         assert_eq!((Some(42), None), engine::execute_process(faster.join(slower)));
     }
 
+    /// Runs a `multi_join` of `n` processes incrementing a counter, and returns the counter.
+    fn run_multijoin_steal_batch(n: usize, n_workers: usize, steal_batch: usize) -> usize {
+        let counter = Arc::new(Mutex::new(0));
+        let mut processes = vec!();
+
+        for _ in 0..n {
+            let counter_clone = counter.clone();
+
+            let add_cont = move |_| {
+                let mut value = counter_clone.lock().unwrap();
+                *value = *value + 1;
+            };
+
+            processes.push(value(()).map(add_cont));
+        }
+
+        let mut r = ParallelRuntime::new(n_workers);
+        r.set_steal_batch(steal_batch);
+        let p = multi_join(processes);
+        let todo = Box::new(move |runtime: &mut engine::Runtime, ()| {
+            p.call(runtime, |_: &mut engine::Runtime, _| {});
+        });
+        r.execute(todo, -1).unwrap();
+
+        let count = *counter.lock().unwrap();
+        count
+    }
+
+    #[test]
+    fn test_continuation_seq() {
+        let result = Arc::new(Mutex::new(None));
+        let result2 = result.clone();
+        let store = move |_: &mut engine::Runtime, v: i32| {
+            *result2.lock().unwrap() = Some(v);
+        };
+        let double = store.seq(|_: &mut engine::Runtime, v: i32| v * 2);
+        let c = double.pause().seq(|_: &mut engine::Runtime, v: i32| v + 1);
+
+        // The composed continuation is boxed as a job, so it goes through `call_box`.
+        let job = (move |runtime: &mut engine::Runtime, instant: u64| {
+            value(3 + instant as i32).call(runtime, c);
+        }).seq(|runtime: &mut engine::Runtime, ()| runtime.current_instant());
+        SequentialRuntime::new().execute(Box::new(job), -1);
+        assert_eq!(*result.lock().unwrap(), Some(8));
+    }
+
+    /// Calls `n` chains of three steps composed with `seq`.
+    fn run_continuation_seq(n: usize) -> i32 {
+        let total = Arc::new(Mutex::new(0));
+        let total2 = total.clone();
+        let job = move |runtime: &mut engine::Runtime, ()| {
+            for i in 0..n {
+                let total = total2.clone();
+                let store = move |_: &mut engine::Runtime, v: i32| *total.lock().unwrap() += v;
+                let c = store.seq(|_: &mut engine::Runtime, v: i32| v * 2)
+                    .seq(|_: &mut engine::Runtime, v: i32| v + 1);
+                c.call(runtime, i as i32);
+            }
+        };
+        SequentialRuntime::new().execute(Box::new(job), -1);
+        let total = *total.lock().unwrap();
+        total
+    }
+
+    /// Calls `n` chains of the same three steps, each one boxed.
+    fn run_continuation_boxed(n: usize) -> i32 {
+        let total = Arc::new(Mutex::new(0));
+        let total2 = total.clone();
+        let job = move |runtime: &mut engine::Runtime, ()| {
+            for i in 0..n {
+                let total = total2.clone();
+                let store: Box<Continuation<i32>> = Box::new(move |_: &mut engine::Runtime, v: i32| {
+                    *total.lock().unwrap() += v;
+                });
+                let double: Box<Continuation<i32>> = Box::new(move |r: &mut engine::Runtime, v: i32| {
+                    store.call_box(r, v * 2);
+                });
+                let c: Box<Continuation<i32>> = Box::new(move |r: &mut engine::Runtime, v: i32| {
+                    double.call_box(r, v + 1);
+                });
+                c.call_box(runtime, i as i32);
+            }
+        };
+        SequentialRuntime::new().execute(Box::new(job), -1);
+        let total = *total.lock().unwrap();
+        total
+    }
+
+    #[test]
+    fn test_continuation_seq_boxed() {
+        assert_eq!(run_continuation_seq(100), run_continuation_boxed(100));
+    }
+
+    #[bench]
+    fn bench_continuation_seq(b: &mut Bencher) {
+        b.iter(|| run_continuation_seq(10000));
+    }
+
+    #[bench]
+    fn bench_continuation_boxed(b: &mut Bencher) {
+        b.iter(|| run_continuation_boxed(10000));
+    }
+
+    #[bench]
+    fn bench_multijoin(b: &mut Bencher) {
+        b.iter(|| run_multijoin_steal_batch(10000, engine::available_workers(), 1));
+    }
+
+    #[test]
+    fn test_steal_batch() {
+        for &steal_batch in [1, 2, 16, 1000].iter() {
+            assert_eq!(run_multijoin_steal_batch(10000, 4, steal_batch), 10000);
+        }
+    }
+
+    #[bench]
+    fn bench_multijoin_steal_batch_1(b: &mut Bencher) {
+        b.iter(|| run_multijoin_steal_batch(10000, 4, 1));
+    }
+
+    #[bench]
+    fn bench_multijoin_steal_batch_16(b: &mut Bencher) {
+        b.iter(|| run_multijoin_steal_batch(10000, 4, 16));
+    }
+
+    #[test]
+    fn test_runtime_config() {
+        let p = || value(()).pause().pause().then(process::instant());
+
+        assert!(engine::available_workers() >= 1);
+        assert_eq!(engine::execute_process_auto(p()), 2);
+
+        // A single worker, whose barrier has size 1.
+        let config = RuntimeConfig::new().workers(1);
+        assert_eq!(engine::execute_with_config(p(), config), Ok(2));
+
+        // No worker is clamped to one worker.
+        let config = RuntimeConfig::new().workers(0).backoff(Some(time::Duration::from_millis(1)));
+        assert_eq!(engine::execute_with_config(p(), config), Ok(2));
+
+        let config = RuntimeConfig::new().workers(3).max_iters(2);
+        assert_eq!(engine::execute_with_config(p(), config), Err(engine::ExecError::IterationLimit));
+    }
+
+    #[test]
+    fn test_backoff() {
+        let backoffs = vec!(
+            Backoff::Wait,
+            Backoff::Sleep(time::Duration::from_millis(1)),
+            Backoff::Yield,
+            Backoff::Spin,
+            Backoff::Exponential {
+                min: time::Duration::from_micros(10),
+                max: time::Duration::from_millis(1),
+            },
+        );
+
+        for backoff in backoffs {
+            // Some work to steal at each instant, and idle workers in between.
+            let counter = Arc::new(Mutex::new(0));
+            let processes: Vec<_> = (0..100).map(|_| {
+                let counter = counter.clone();
+                value(()).pause().map(move |()| *counter.lock().unwrap() += 1).pause()
+            }).collect();
+
+            let config = RuntimeConfig::new().workers(4).backoff(backoff);
+            assert_eq!(engine::execute_with_config(multi_join(processes), config).map(|v| v.len()), Ok(100));
+            assert_eq!(*counter.lock().unwrap(), 100);
+        }
+        assert_eq!(Backoff::from(None), Backoff::Wait);
+    }
+
+    #[test]
+    fn test_cancellation() {
+        let token = CancellationToken::new();
+        let token2 = token.clone();
+        let last_instant = Arc::new(Mutex::new(0));
+        let last_instant2 = last_instant.clone();
+
+        // The process cancels its own execution at instant 5, and would loop forever otherwise.
+        let step = move |instant: u64| {
+            *last_instant2.lock().unwrap() = instant;
+            if instant == 5 {
+                token2.cancel();
+            }
+        };
+        let p = process::instant().map(step).pause().loop_inf();
+
+        let config = RuntimeConfig::new().workers(3).cancellation(token.clone());
+        assert_eq!(engine::execute_with_config(p, config), Err(engine::ExecError::Cancelled));
+        assert!(token.is_cancelled());
+        assert_eq!(*last_instant.lock().unwrap(), 5);
+    }
+
+    #[test]
+    fn test_iteration_limit_stress() {
+        // All the workers stop at the same iteration, whichever reaches the limit first: none of
+        // them is left waiting at a barrier, whether the process ends before the limit or not.
+        for max_iters in 1..40 {
+            let ps = (0..32).map(|i| value(i).delay(10)).collect();
+            let result = engine::try_execute_process_steps(multi_join(ps), 8, max_iters);
+            if max_iters > 10 {
+                assert_eq!(result, Ok((0..32).collect()));
+            } else {
+                assert_eq!(result, Err(engine::ExecError::IterationLimit));
+            }
+        }
+    }
+
+    #[test]
+    fn test_multijoin_order() {
+        // Processes finish in different instants, but values keep the original order.
+        let processes: Vec<_> = (0..300).map(|i| {
+            let p = value(i);
+            match i % 3 {
+                0 => p.boxed(),
+                1 => p.pause().boxed(),
+                _ => p.pause().pause().boxed(),
+            }
+        }).collect();
+
+        assert_eq!(engine::execute_process(multi_join(processes)), (0..300).collect::<Vec<_>>());
+        assert_eq!(engine::execute_process(multi_join(Vec::<process::Value<i32>>::new())), vec!());
+    }
+
+    #[test]
+    fn test_multijoin_iter() {
+        let p = process::multi_join_iter((0..100).map(|i| value(i)));
+        assert_eq!(engine::execute_process(p), (0..100).collect::<Vec<_>>());
+
+        // Iterators without an exact size are accepted too.
+        let p = process::multi_join_iter((0..100).filter(|i| i % 2 == 0).map(|i| value(i).pause()));
+        assert_eq!(engine::execute_process(p), (0..50).map(|i| 2 * i).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_multijoin_indexed() {
+        let processes: Vec<_> = (0..100).map(|i| value(i)).collect();
+        let mut values = engine::execute_process(process::multi_join_indexed(processes));
+        values.sort();
+        assert_eq!(values, (0..100).map(|i| (i, i)).collect::<Vec<_>>());
+
+        let processes: Vec<_> = (0..100).map(|i| value(i).pause()).collect();
+        let sum = process::multi_join_collect_with(processes, 0, |acc, _, v| acc + v);
+        assert_eq!(engine::execute_process(sum), 4950);
+    }
+
+    #[test]
+    fn test_dynamic_multi_join() {
+        let instant = |r: &mut engine::Runtime, ()| r.current_instant();
+        let (join, handle) = process::dynamic_multi_join(vec!(value(()).pause().map_rt(instant).boxed()));
+
+        // A process added at instant 0 is launched at instant 1, and adds another one itself.
+        let handle2 = handle.clone();
+        let add_more = move |r: &mut engine::Runtime, ()| {
+            handle2.add(r, value(()).pause().map_rt(instant).boxed());
+            r.current_instant()
+        };
+        let adder = value(()).map_rt(move |r: &mut engine::Runtime, ()| {
+            handle.add(r, value(()).map_rt(add_more).boxed())
+        });
+
+        // The join waits for all the processes, and returns their values in order of addition.
+        let program = join.join(adder).map(|(values, ())| values);
+        assert_eq!(engine::execute_process(program), vec![1, 1, 3]);
+
+        // Without added processes, it behaves like `multi_join`.
+        let (join, _) = process::dynamic_multi_join(vec!(value(1), value(2)));
+        assert_eq!(engine::execute_process_steps(join, 4, -1), Some(vec![1, 2]));
+    }
+
+    #[test]
+    fn test_dynamic_multi_join_deadlock() {
+        // An added process waits for a signal which is never emitted: the join does not keep the
+        // runtime busy, so the deadlock is detected.
+        let s = puresignal::new();
+        let (join, handle) = process::dynamic_multi_join(vec!(value(()).boxed()));
+        let s2 = s.clone();
+        let adder = value(()).map_rt(move |r: &mut engine::Runtime, ()| {
+            handle.add(r, s2.await_immediate().boxed());
+        });
+        assert_eq!(engine::try_execute_process(join.join(adder)), Err(engine::ExecError::Deadlock));
+    }
+
+    #[test]
+    fn test_flatten_result() {
+        let inner = value(()).pause().map(|()| -> Result<i32, String> { Ok(5) });
+        let outer = value(()).map(move |()| -> Result<_, String> { Ok(inner) });
+        assert_eq!(engine::execute_process(outer.flatten_result()), Ok(5));
+
+        // The inner process is not executed when the outer one fails.
+        let executed = Arc::new(Mutex::new(false));
+        let executed2 = executed.clone();
+        let inner = value(()).map(move |()| -> Result<i32, String> {
+            *executed2.lock().unwrap() = true;
+            Ok(5)
+        });
+        fn fail<Q>(_inner: Q) -> Result<Q, String> {
+            Err("failed".to_string())
+        }
+        let outer = value(()).map(move |()| fail(inner));
+        assert_eq!(engine::execute_process(outer.flatten_result()), Err("failed".to_string()));
+        assert!(!*executed.lock().unwrap());
+    }
+
+    #[test]
+    fn test_par_map() {
+        let inputs: Vec<usize> = (0..100).collect();
+        let squares = engine::execute_process(process::par_map(inputs, |i| value(i * i)));
+        assert_eq!(squares, (0..100).map(|i| i * i).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_discard() {
+        let counter = Arc::new(Mutex::new(0));
+        let counter2 = counter.clone();
+        let incr = move |()| {
+            let mut counter = counter2.lock().unwrap();
+            *counter += 1;
+            *counter
+        };
+
+        let p = value(()).map(incr).discard();
+        let () = engine::execute_process(p);
+        assert_eq!(*counter.lock().unwrap(), 1);
+
+        // As a `ProcessMut`, the side effects occur at each iteration.
+        let counter3 = counter.clone();
+        let incr = move |()| { *counter3.lock().unwrap() += 10; 42 };
+        let p = value(()).map(incr).discard().take(3);
+        assert_eq!(engine::execute_process(p), vec!((), (), ()));
+        assert_eq!(*counter.lock().unwrap(), 31);
+    }
+
     #[test]
     fn test_multijoin() {
         let counter = Arc::new(Mutex::new(0));
@@ -465,6 +1687,168 @@ mod tests {
         assert_eq!(n, *counter.lock().unwrap());
     }
 
+    #[test]
+    fn test_reduce_signal() {
+        let n = 10000;
+        let s = reduce_signal::new(0, |a: i32, b: i32| a + b);
+
+        let emitters = multi_join((0..n).map(|_| s.emit(value(1))).collect());
+        let (total, _) = engine::execute_process(s.await_in().join(emitters));
+        assert_eq!(n, total);
+
+        // The partial sums are reset at each instant.
+        let p = s.emit(value(2)).pause().then(s.emit(value(3))).join(s.await_in().then(s.await_in()));
+        assert_eq!(engine::execute_process(p).1, 3);
+    }
+
+    #[test]
+    fn test_sequential() {
+        let program = || {
+            let s = puresignal::new();
+            let emitter = value(()).pause().emit(&s).then(value(3));
+            // Tests the signal at the first instant, where it is absent.
+            let tester1 = s.present(value(true), value(false));
+            // Tests the signal at the second instant, where it is present.
+            let tester2 = value(()).pause().then(s.present(value(true), value(false)));
+            emitter.join(tester1.join(tester2))
+        };
+
+        assert_eq!(engine::execute_process_sequential(program()), (3, (false, true)));
+        assert_eq!(engine::execute_process_sequential(program()), engine::execute_process(program()));
+
+        for i in 0..10 {
+            let program = value(i).pause().pause().map(|x| 3*x+2);
+            assert_eq!(engine::execute_process_sequential(program), 3*i+2);
+        }
+    }
+
+    #[test]
+    fn test_deadlock() {
+        // The process waits for a signal that is never emitted, while another one keeps running
+        // for a few instants.
+        let s = puresignal::new();
+        let busy = value(()).pause().loop_inf().timeout(10);
+        let p = s.await().join(busy);
+        assert_eq!(engine::try_execute_process(p), Err(engine::ExecError::Deadlock));
+
+        // A program running forever is stopped by the iteration limit, and is not deadlocked.
+        let p = value(()).pause().loop_inf();
+        assert_eq!(engine::try_execute_process_steps(p, 2, 50), Err(engine::ExecError::IterationLimit));
+    }
+
+    #[test]
+    fn test_exec_error() {
+        assert_eq!(engine::try_execute_process(value(42).pause()), Ok(42));
+
+        // Nobody emits the signal.
+        let s = puresignal::new();
+        assert_eq!(engine::try_execute_process(s.await_immediate()), Err(engine::ExecError::Deadlock));
+
+        let p = value(42).pause().pause();
+        assert_eq!(engine::try_execute_process_steps(p, 2, 2), Err(engine::ExecError::IterationLimit));
+
+        // The process finishes exactly on the last allowed instant.
+        let p = value(42).pause().pause();
+        assert_eq!(engine::try_execute_process_steps(p, 2, 3), Ok(42));
+
+        let c: fn(()) -> () = |_| panic!("Expected panic.");
+        let p = value(()).map(c);
+        let error = engine::ExecError::WorkerPanicked("Expected panic.".to_string());
+        assert_eq!(engine::try_execute_process_steps(p, 1, -1), Err(error));
+    }
+
+    #[test]
+    fn test_worker_panic_releases_workers() {
+        // One branch panics at the third instant, while the other one would loop forever.
+        let c: fn(()) -> () = |_| panic!("Panic in a branch.");
+        let p = value(()).pause().pause().map(c).join(value(()).pause().loop_inf());
+
+        let error = engine::ExecError::WorkerPanicked("Panic in a branch.".to_string());
+        assert_eq!(engine::try_execute_process_steps(p, 4, -1), Err(error));
+    }
+
+    #[test]
+    fn test_peak_queue_len() {
+        let counter = Arc::new(Mutex::new(0));
+        let mut processes = vec!();
+
+        let n = 1000;
+
+        for _ in 0..n {
+            let counter_clone = counter.clone();
+
+            let add_cont = move |_| {
+                let mut value = counter_clone.lock().unwrap();
+                *value = *value + 1;
+            };
+
+            processes.push(value(()).map(add_cont));
+        }
+
+        // With a single worker, `multi_join` enqueues all the processes at once.
+        let program = multi_join(processes);
+        let job = Box::new(move |runtime: &mut engine::Runtime, ()| {
+            program.call(runtime, |_: &mut engine::Runtime, _| {});
+        });
+
+        let mut r = engine::ParallelRuntime::new(1);
+        r.execute(job, -1).unwrap();
+
+        assert_eq!(n, *counter.lock().unwrap());
+        assert_eq!(n, r.stats().peak_queue_len);
+    }
+
+    #[test]
+    fn test_peak_queue_len_workers() {
+        // Each process pauses on the worker executing it: at the end of the first instant, the
+        // pauses of all the processes are pending at once, spread over the workers.
+        let n = 1000;
+        let processes = (0..n).map(|_| value(()).pause()).collect();
+        let program = multi_join(processes);
+        let job = Box::new(move |runtime: &mut engine::Runtime, ()| {
+            program.call(runtime, |_: &mut engine::Runtime, _| {});
+        });
+
+        let mut r = engine::ParallelRuntime::new(4);
+        r.execute(job, -1).unwrap();
+
+        let stats = r.stats();
+        assert!(stats.peak_queue_len >= n);
+        assert!(stats.peak_queue_len <= n + 1);
+    }
+
+    #[test]
+    fn test_stats() {
+        let (value, stats) = engine::execute_process_steps_with_stats(
+            value(42).pause().pause(), 4, -1);
+
+        assert_eq!(Some(42), value);
+        assert_eq!(3, stats.instants);
+        assert!(stats.jobs_run >= 3);
+    }
+
+    #[test]
+    fn test_queue_cap() {
+        let (worker, _) = deque::new();
+
+        // Shared data structure between workers.
+        let shared_data = SharedData::new(vec!(), 1);
+
+        let mut runtime = engine::Runtime::new(Arc::new(shared_data), worker);
+        runtime.set_queue_cap(Some(2));
+
+        let nop = |_: &mut engine::Runtime, ()| {};
+        assert!(runtime.try_on_current_instant(Box::new(nop)).is_ok());
+        assert!(runtime.try_on_current_instant(Box::new(nop)).is_ok());
+        // The queue is full: the continuation is refused.
+        assert!(runtime.try_on_current_instant(Box::new(nop)).is_err());
+
+        // Once the queue has drained, the runtime accepts continuations again.
+        runtime.work(1);
+        assert!(runtime.try_on_current_instant(Box::new(nop)).is_ok());
+        assert_eq!(2, runtime.stats().peak_queue_len);
+    }
+
     #[test]
     fn test_loop_while() {
         let n = 16;
@@ -520,6 +1904,284 @@ mod tests {
         assert_eq!((m * (m + 1), m * m), engine::execute_process(program));
     }
 
+    #[test]
+    fn test_and_then_mut() {
+        // Returns a process counting its executions.
+        let counter = || {
+            let mut count = 0;
+            let incr = move |()| { count += 1; count };
+            value(()).map(incr)
+        };
+        // Stops after three iterations.
+        let three = || {
+            let mut k = 0;
+            move |count| { k += 1; if k > 3 { None } else { Some(count) } }
+        };
+        let push = |mut counts: Vec<i32>, count| { counts.push(count); counts };
+
+        // The counter is created again at each iteration.
+        let fresh = move |()| counter();
+        let p = value(()).pause().and_then(fresh).map(three()).fold(vec!(), push);
+        assert_eq!(engine::execute_process(p), vec!(1, 1, 1));
+
+        // The counter is kept between iterations.
+        let kept = move |(), c: Option<_>| c.unwrap_or_else(counter);
+        let p = value(()).pause().and_then_mut(kept).map(three()).fold(vec!(), push);
+        assert_eq!(engine::execute_process(p), vec!(1, 2, 3));
+    }
+
+    #[test]
+    fn test_race() {
+        let forever = value(()).pause().loop_inf().then_return(0);
+        assert_eq!(engine::execute_process(value(1).race(forever)), 1);
+
+        // The loser counts during the even instants, and is aborted as soon as the winner
+        // terminates, during the fourth instant: it only counted during the first and third ones.
+        let counter = Arc::new(Mutex::new(0));
+        let counter2 = counter.clone();
+        let count = move |()| *counter2.lock().unwrap() += 1;
+        let forever = value(()).map(count).pause().pause().loop_inf().then_return(0);
+        let winner = value(()).pause().pause().pause().then_return(2);
+        assert_eq!(engine::execute_process(forever.race(winner)), 2);
+        assert_eq!(*counter.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_then_return() {
+        // The original value is dropped when the process returns.
+        let dropped = Arc::new(Mutex::new(0));
+        struct DropCounter(Arc<Mutex<i32>>);
+        impl Drop for DropCounter {
+            fn drop(&mut self) {
+                *self.0.lock().unwrap() += 1;
+            }
+        }
+
+        let p = value(DropCounter(dropped.clone())).pause().then_return("done");
+        assert_eq!(engine::execute_process(p), "done");
+        assert_eq!(*dropped.lock().unwrap(), 1);
+
+        // The value is returned at each iteration of a loop.
+        let mut k = 0;
+        let three = move |v: &'static str| { k += 1; if k > 3 { None } else { Some(v) } };
+        let push = |mut values: Vec<&'static str>, v| { values.push(v); values };
+        let dropped2 = dropped.clone();
+        let counter = move |()| DropCounter(dropped2.clone());
+        let p = value(()).map(counter).pause().then_return("again").map(three).fold(vec!(), push);
+        assert_eq!(engine::execute_process(p), vec!("again", "again", "again"));
+        assert_eq!(*dropped.lock().unwrap(), 5);
+    }
+
+    #[test]
+    fn test_fold() {
+        let n = 16;
+        let mut reward = n;
+
+        // Decrementer, returns the decremented value until it reaches 0.
+        let decrementer = move |_| {
+            let v = reward;
+            reward -= 1;
+            if v <= 0 { None } else { Some(v) }
+        };
+        let sum = |acc, v| acc + v;
+
+        let program = value(()).pause().map(decrementer).fold(0, sum);
+        assert_eq!(n * (n + 1) / 2, engine::execute_process(program));
+    }
+
+    #[test]
+    fn test_take() {
+        let mut n = -1;
+        let counter = move |()| { n += 1; n };
+        let program = value(()).map(counter).take(5);
+        assert_eq!(engine::execute_process(program), vec![0, 1, 2, 3, 4]);
+
+        // The body may pause, and the loop can itself be repeated.
+        let mut n = -1;
+        let counter = move |()| { n += 1; n };
+        let program = value(()).pause().map(counter).take(2).take(2);
+        assert_eq!(engine::execute_process(program), vec![vec![0, 1], vec![2, 3]]);
+        assert_eq!(engine::execute_process(value(0).take(0)), vec![]);
+    }
+
+    #[test]
+    fn test_until() {
+        let mut n = 0;
+        let counter = move |()| { n += 1; n };
+        let program = value(()).pause().map(counter).until(|n: &i32| *n < 10);
+        assert_eq!(engine::execute_process(program), 10);
+
+        // The loop can itself be repeated, and resumes with the state of its body.
+        let mut n = 0;
+        let counter = move |()| { n += 1; n };
+        let program = value(()).map(counter).until(|n: &i32| n % 3 != 0).take(2);
+        assert_eq!(engine::execute_process(program), vec![3, 6]);
+    }
+
+    #[test]
+    fn test_flat_map() {
+        // The function keeps its running sum across iterations, and each produced process emits
+        // it, then returns it at the next instant.
+        let s = value_signal::new(0, |v, acc| v + acc);
+        let s2 = s.clone();
+        let mut sum = 0;
+        let step = move |n: i32| {
+            sum += n;
+            value(sum).emit(&s2).then(value(sum).pause())
+        };
+        let mut n = 0;
+        let counter = move |()| { n += 1; n };
+        let program = value(()).map(counter).flat_map(step).take(4);
+        let watcher = s.await_in().take(4);
+        assert_eq!(engine::execute_process(program.join(watcher)),
+                   (vec![1, 3, 6, 10], vec![1, 3, 6, 10]));
+    }
+
+    #[test]
+    fn test_retry() {
+        // A process which fails twice, then succeeds.
+        let attempts = |n_failures: usize| {
+            let mut n = 0;
+            move |()| {
+                n += 1;
+                if n > n_failures { Ok(n) } else { Err(n) }
+            }
+        };
+        let program = value(()).map(attempts(2)).retry(5);
+        assert_eq!(engine::execute_process(program), Ok(3));
+
+        // When the attempts are exhausted, the last error is returned.
+        let program = value(()).map(attempts(2)).retry(2);
+        assert_eq!(engine::execute_process(program), Err(2));
+
+        // The attempts are counted again at each execution of the loop.
+        let program = value(()).pause().map(attempts(3)).retry(2).take(2);
+        assert_eq!(engine::execute_process(program), vec![Err(2), Ok(4)]);
+    }
+
+    #[test]
+    fn test_branch() {
+        let program = |b: bool| value(b).branch(value(42), value("no".to_string()));
+        assert_eq!(engine::execute_process(program(true)), process::Either::Left(42));
+        assert_eq!(engine::execute_process(program(false)),
+                   process::Either::Right("no".to_string()));
+
+        // The branches keep their state across iterations.
+        let mut b = false;
+        let toggle = move |()| { b = !b; b };
+        let mut n = 0;
+        let counter = move |()| { n += 1; n };
+        let program = value(()).map(toggle)
+            .branch(value(()).map(counter), process::clone_value("odd"))
+            .take(3);
+        assert_eq!(engine::execute_process(program),
+                   vec![process::Either::Left(1), process::Either::Right("odd"),
+                        process::Either::Left(2)]);
+    }
+
+    #[test]
+    fn test_clone_value() {
+        let seed = process::clone_value("seed".to_string());
+        let program = seed.pause().take(3);
+        assert_eq!(engine::execute_process(program), vec!["seed".to_string(); 3]);
+    }
+
+    #[test]
+    fn test_zip_with() {
+        let p = value(2).zip_with(value(3).pause(), |a, b| a + b);
+        assert_eq!(engine::execute_process(p), 5);
+
+        // The function is kept between two iterations.
+        let mut calls = 0;
+        let count = move |a: i32, b: i32| { calls += 1; a * b + calls };
+        let p = value(2).zip_with(value(3).pause(), count).take(3);
+        assert_eq!(engine::execute_process(p), vec![7, 8, 9]);
+    }
+
+    #[test]
+    fn test_delay() {
+        let instant = |r: &mut engine::Runtime, v| (v, r.current_instant());
+        assert_eq!(engine::execute_process(value(7).delay(3).map_rt(instant)), (7, 3));
+        assert_eq!(engine::execute_process(value(7).delay(0).map_rt(instant)), (7, 0));
+
+        // The delayed value arrives at the same instant as with three pauses.
+        let paused = value(()).pause().pause().pause().map_rt(|r: &mut engine::Runtime, ()| r.current_instant());
+        let delayed = value(7).delay(3).map_rt(instant);
+        assert_eq!(engine::execute_process(delayed.join(paused)), ((7, 3), 3));
+
+        // Each iteration of a loop is delayed.
+        let mut n = 0;
+        let counter = move |()| { n += 1; n };
+        let program = value(()).map(counter).delay(2).map_rt(instant).take(3);
+        assert_eq!(engine::execute_process(program), vec![(1, 2), (2, 4), (3, 6)]);
+    }
+
+    #[test]
+    fn test_map_rt() {
+        let p = value(10).pause().pause().map_rt(|r: &mut engine::Runtime, v| r.current_instant() + v);
+        assert_eq!(engine::execute_process(p), 12);
+
+        let instant = |r: &mut engine::Runtime, ()| r.current_instant();
+        let p = value(()).pause().map_rt(instant).take(3);
+        assert_eq!(engine::execute_process(p), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_catch_unwind() {
+        let fail = |v: i32| -> i32 { if v == 0 { panic!("Expected panic.") } else { v } };
+
+        let p = value(0).map(fail).catch_unwind().map(|r| r.unwrap_err().downcast_ref::<&str>().cloned());
+        assert_eq!(engine::execute_process(p), Some("Expected panic."));
+
+        // The other processes are not disturbed.
+        let p = value(0).map(fail).catch_unwind().join(value(2).pause().map(fail).catch_unwind());
+        let (r1, r2) = engine::execute_process(p);
+        assert!(r1.is_err());
+        assert_eq!(r2.ok(), Some(2));
+    }
+
+    #[test]
+    fn test_catch_unwind_later_instant() {
+        let fail = |v: i32| -> i32 { if v == 0 { panic!("Expected panic.") } else { v } };
+        let count = Arc::new(Mutex::new(0));
+        let count2 = count.clone();
+        let incr = move |v: i32| {
+            *count2.lock().unwrap() += 1;
+            v
+        };
+
+        // The panic happens at the second instant, and discards the rest of the other branch.
+        let p = value(0).pause().map(fail).join(value(1).pause().pause().map(incr))
+            .catch_unwind()
+            .map(|r| r.unwrap_err().downcast_ref::<&str>().cloned());
+        let p = p.join(value(2).pause().pause().pause());
+        assert_eq!(engine::execute_process(p), (Some("Expected panic."), 2));
+        assert_eq!(*count.lock().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_then_loop() {
+        let setup_count = Arc::new(Mutex::new(0));
+        let body_count = Arc::new(Mutex::new(0));
+        let setup_count2 = setup_count.clone();
+        let body_count2 = body_count.clone();
+
+        let incr_setup = move |_| {
+            *setup_count2.lock().unwrap() += 1;
+        };
+        let incr_body = move |_| {
+            *body_count2.lock().unwrap() += 1;
+        };
+
+        let setup = value(()).map(incr_setup);
+        let body = value(()).map(incr_body).pause();
+
+        // The loop never ends, so no value is returned.
+        assert_eq!(engine::execute_process_sequential_steps(setup.then_loop(body), 10), None);
+        assert_eq!(*setup_count.lock().unwrap(), 1);
+        assert_eq!(*body_count.lock().unwrap(), 10);
+    }
+
     #[test]
     fn test_while_perf() {
         let mut x = 1000;
@@ -535,6 +2197,53 @@ mod tests {
         assert_eq!(42, engine::execute_process(p.pause().loop_while()));
     }
 
+    #[test]
+    fn test_await_immediate_never_emitted() {
+        let s = puresignal::new();
+        let stop = puresignal::new();
+
+        // A continuation waiting for `s` is registered once, and stays there while `s` is absent.
+        let waiter = stop.do_until(s.await_immediate().pause().loop_inf());
+
+        let max_waiting = Arc::new(Mutex::new(0));
+        let max_waiting2 = max_waiting.clone();
+        let s2 = s.clone();
+        let check = move |instant: u64| {
+            let mut max_waiting = max_waiting2.lock().unwrap();
+            *max_waiting = usize::max(*max_waiting, s2.runtime().waiting_count());
+            if instant == 1000 { LoopStatus::Exit(()) } else { LoopStatus::Continue }
+        };
+        let checker = stop.emit(process::instant().map(check).pause().loop_while());
+
+        let (aborted, ()) = engine::execute_process(waiter.join(checker));
+        assert_eq!(aborted, None);
+        assert_eq!(*max_waiting.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_do_until_loop_drops_abortion() {
+        let stop = puresignal::new();
+        let stop2 = stop.clone();
+
+        // Each iteration runs a block which completes normally, and leaves its abortion waiting
+        // for `stop`, which is never emitted. These registrations must not accumulate.
+        let max_waiting = Arc::new(Mutex::new(0));
+        let max_waiting2 = max_waiting.clone();
+        let mut iterations = 0;
+        let block = move |()| {
+            let mut max_waiting = max_waiting2.lock().unwrap();
+            *max_waiting = usize::max(*max_waiting, stop2.runtime().waiting_count());
+            iterations += 1;
+            let status = if iterations == 1000 { LoopStatus::Exit(()) } else { LoopStatus::Continue };
+            stop2.do_until(value(status).pause())
+        };
+        let completed = |status: Option<LoopStatus<()>>| status.unwrap();
+        let p = value(()).map(block).flatten().map(completed).loop_while();
+
+        engine::execute_process(p);
+        assert!(*max_waiting.lock().unwrap() < 100);
+    }
+
     #[test]
     fn test_pure_signal() {
         let s = puresignal::new();
@@ -585,8 +2294,676 @@ mod tests {
         assert_eq!(engine::execute_process_steps(p, 4, 1000), None);
     }
 
+    #[test]
+    fn test_map_result_into_signal() {
+        let ok = value_signal::new(vec!(), |v: i32, mut acc: Vec<i32>| {
+            acc.push(v);
+            acc
+        });
+        let err = value_signal::new(vec!(), |e: String, mut acc: Vec<String>| {
+            acc.push(e);
+            acc
+        });
+
+        let check = |v: i32| {
+            if v % 2 == 0 { Ok(v) } else { Err(format!("odd {}", v)) }
+        };
+        let stages: Vec<_> = (0..6).map(|i| {
+            value(i).map_result_into_signal(check, &ok, &err)
+        }).collect();
+
+        let p = multi_join(stages).join(ok.await_in().join(err.await_in()));
+        let (results, (mut oks, mut errs)) = engine::execute_process(p);
+        oks.sort();
+        errs.sort();
+
+        assert_eq!(results, vec!(Ok(0), Err("odd 1".to_string()), Ok(2),
+                                 Err("odd 3".to_string()), Ok(4), Err("odd 5".to_string())));
+        assert_eq!(oks, vec!(0, 2, 4));
+        assert_eq!(errs, vec!("odd 1".to_string(), "odd 3".to_string(), "odd 5".to_string()));
+
+        // Over several instants, the values of each instant land in the signals of this instant
+        // only, and the results of all the instants are forwarded.
+        let oks = Arc::new(Mutex::new(vec!()));
+        let errs = Arc::new(Mutex::new(vec!()));
+        let (oks2, errs2) = (oks.clone(), errs.clone());
+        let collect_ok = move |v| oks2.lock().unwrap().push(v);
+        let collect_err = move |e| errs2.lock().unwrap().push(e);
+        let ok_observer = ok.await_in().map(collect_ok).loop_inf();
+        let err_observer = err.await_in().map(collect_err).loop_inf();
+
+        let mut n = 0;
+        let next = move |()| { n += 1; n - 1 };
+        let results = Arc::new(Mutex::new(vec!()));
+        let results2 = results.clone();
+        let record = move |r| {
+            let mut results = results2.lock().unwrap();
+            results.push(r);
+            if results.len() < 6 { LoopStatus::Continue } else { LoopStatus::Exit(()) }
+        };
+        let stage = value(()).map(next).map_result_into_signal(check, &ok, &err).map(record)
+            .pause().loop_while();
+
+        let p = stage.join(ok_observer.join(err_observer));
+        assert_eq!(engine::execute_process_sequential_steps(p, 7), None);
+        assert_eq!(*results.lock().unwrap(), vec!(Ok(0), Err("odd 1".to_string()), Ok(2),
+                                                  Err("odd 3".to_string()), Ok(4),
+                                                  Err("odd 5".to_string())));
+        assert_eq!(*oks.lock().unwrap(), vec!(vec!(0), vec!(2), vec!(4)));
+        assert_eq!(*errs.lock().unwrap(), vec!(vec!("odd 1".to_string()), vec!("odd 3".to_string()),
+                                               vec!("odd 5".to_string())));
+    }
+
+    #[test]
+    fn test_do_until() {
+        let s = puresignal::new();
+        let counter = Arc::new(Mutex::new(0));
+        let counter2 = counter.clone();
+
+        let incr = move |_| {
+            *counter2.lock().unwrap() += 1;
+        };
+        let body = value(()).map(incr).pause().loop_inf();
+        let emitter = value(()).pause().pause().pause().then(s.emit(value(())));
+        let p = s.do_until(body).join(emitter);
+
+        // The loop runs during instants 0 to 3, and is aborted at the end of instant 3.
+        assert_eq!(engine::execute_process(p), (None, ()));
+        assert_eq!(*counter.lock().unwrap(), 4);
+
+        let s = puresignal::new();
+        assert_eq!(engine::execute_process(s.do_until(value(42))), Some(42));
+    }
+
+    #[test]
+    fn test_latch_signal() {
+        let s = latch_signal::new(1);
+
+        // The initial value can be read before any emission.
+        assert_eq!(engine::execute_process(s.read_latest()), 1);
+
+        let p1 = s.emit(value(5))
+            .then(value(()).pause().pause())
+            .then(s.read_latest());
+        let p2 = s.await_in();
+
+        // The value is not reset after the instant of emission.
+        assert_eq!(engine::execute_process(p1.join(p2)), (5, 5));
+        assert_eq!(engine::execute_process(s.read_latest()), 5);
+    }
+
+    #[test]
+    fn test_select_map() {
+        let s1 = puresignal::new();
+        let s2 = puresignal::new();
+
+        let arms = vec!(
+            process::select_arm(&s1, |v: i32| v + 1),
+            process::select_arm(&s2, |v: i32| v * 2),
+        );
+        let p = value(10).select_map(arms).join(value(()).pause().then(s2.emit(value(()))));
+
+        assert_eq!(engine::execute_process(p), (20, ()));
+
+        // When both signals are emitted in the same instant, the first arm is selected, at the
+        // next instant.
+        let arms = vec!(
+            process::select_arm(&s1, |v: i32| v + 1),
+            process::select_arm(&s2, |v: i32| v * 2),
+        );
+        let instant = |r: &mut engine::Runtime, v: i32| (v, r.current_instant());
+        let emitter = s2.emit(value(())).then(s1.emit(value(())));
+        let p = value(10).select_map(arms).map_rt(instant).join(emitter);
+        assert_eq!(engine::execute_process(p), ((11, 1), ()));
+    }
+
+    #[test]
+    fn test_select_map_drops_other_arms() {
+        let s1 = puresignal::new();
+        let s2 = puresignal::new();
+        let s2_waiting = s2.clone();
+        let stop = puresignal::new();
+
+        // Only the first signal is emitted, so each selection leaves a registration on the second
+        // one, which must not accumulate.
+        let emitter = stop.do_until(value(()).emit(&s1).pause().loop_inf());
+        let max_waiting = Arc::new(Mutex::new(0));
+        let max_waiting2 = max_waiting.clone();
+        let mut iterations = 0;
+        let select = move |()| {
+            let mut max_waiting = max_waiting2.lock().unwrap();
+            *max_waiting = usize::max(*max_waiting, s2_waiting.runtime().waiting_count());
+            iterations += 1;
+            let status = |n: usize| if n == 1000 { LoopStatus::Exit(()) } else { LoopStatus::Continue };
+            let arms = vec!(
+                process::select_arm(&s1, status),
+                process::select_arm(&s2, |_: usize| LoopStatus::Exit(())),
+            );
+            value(iterations).select_map(arms)
+        };
+        let selector = stop.emit(value(()).map(select).flatten().loop_while());
+
+        let (aborted, ()) = engine::execute_process(emitter.join(selector));
+        assert_eq!(aborted, None);
+        assert!(*max_waiting.lock().unwrap() < 100);
+    }
+
+    #[test]
+    fn test_collect_signal() {
+        let s = collect_signal::new();
+
+        let p1 = s.emit(value(1)).then(s.emit(value(2))).then(s.emit(value(3)));
+        let p2 = s.await_in();
+
+        // Values are delivered in emission order, and reset at the end of the instant.
+        let p3 = value(()).pause().then(s.emit(value(4))).then(s.await_in());
+
+        assert_eq!(engine::execute_process(p1.join(p2).join(p3)), (((), vec!(1, 2, 3)), vec!(4)));
+    }
+
+    #[test]
+    fn test_is_present() {
+        let s = puresignal::new();
+
+        // The signal is emitted at instants 0 and 2.
+        let emitter = s.emit(value(())).then(value(()).pause().pause()).then(s.emit(value(())));
+
+        let mut statuses = vec!();
+        let record = move |present: bool| {
+            statuses.push(present);
+            if statuses.len() == 4 {
+                LoopStatus::Exit(statuses.clone())
+            } else {
+                LoopStatus::Continue
+            }
+        };
+        // Both branches resume at the next instant.
+        let watcher = s.is_present().then_else(value(true).pause(), value(false)).map(record).loop_while();
+
+        assert_eq!(engine::execute_process(watcher.join(emitter)), (vec!(true, false, true, false), ()));
+    }
+
+    #[test]
+    fn test_puresignal_any() {
+        let (a, b) = (puresignal::new(), puresignal::new());
+        let a_or_b = puresignal::any(&a, &b);
+
+        // `a` is emitted at instant 0, `b` at instant 2, and both at instant 3.
+        let emitter = a.emit(value(())).then(value(()).pause().pause()).then(b.emit(value(())))
+            .pause().then(a.emit(value(())).join(b.emit(value(()))));
+
+        let mut statuses = vec!();
+        let record = move |present: bool| {
+            statuses.push(present);
+            if statuses.len() == 5 {
+                LoopStatus::Exit(statuses.clone())
+            } else {
+                LoopStatus::Continue
+            }
+        };
+        let watcher = a_or_b.is_present().then_else(value(true).pause(), value(false)).map(record).loop_while();
+
+        let (statuses, _) = engine::execute_process(watcher.join(emitter));
+        assert_eq!(statuses, vec!(true, false, true, true, false));
+    }
+
+    #[test]
+    fn test_emit_if() {
+        let s = value_signal::new(0, |v1: i32, v2: i32| v1 + v2);
+
+        // The values 1, 2 and 3 are computed at instants 0, 1 and 2, only 2 is emitted.
+        let is_even = |v: &i32| v % 2 == 0;
+        let mut n = 0;
+        let incr = move |()| { n += 1; n };
+        let mut k = 0;
+        let stop = move |()| { k += 1; if k == 3 { LoopStatus::Exit(()) } else { LoopStatus::Continue } };
+        let emitter = s.emit_if(value(()).map(incr), is_even).pause().map(stop).loop_while();
+
+        let mut statuses = vec!();
+        let record = move |present: bool| {
+            statuses.push(present);
+            if statuses.len() == 3 {
+                LoopStatus::Exit(statuses.clone())
+            } else {
+                LoopStatus::Continue
+            }
+        };
+        let watcher = s.is_present().then_else(value(true).pause(), value(false)).map(record).loop_while();
+
+        assert_eq!(engine::execute_process(watcher.join(emitter)), (vec!(false, true, false), ()));
+    }
+
+    #[test]
+    fn test_present_with_default() {
+        let s = puresignal::new();
+
+        // The signal is only emitted at instant 1.
+        let emitter = value(()).pause().emit(&s);
+
+        let mut values = vec!();
+        let record = move |v: i32| {
+            values.push(v);
+            if values.len() == 3 {
+                LoopStatus::Exit(values.clone())
+            } else {
+                LoopStatus::Continue
+            }
+        };
+        // Both branches last one instant, so each iteration tests a new instant.
+        let watcher = s.present_with_default(value(1).pause(), 0).map(record).loop_while();
+
+        assert_eq!(engine::execute_process(watcher.join(emitter)), (vec!(0, 1, 0), ()));
+    }
+
+    #[test]
+    fn test_await_n() {
+        let s = puresignal::new();
+
+        // The signal is emitted at every instant, from instant 0 to 4.
+        let emitter = value(()).emit(&s).pause().take(5);
+        let waiter = s.await_n(3).map_rt(|r: &mut engine::Runtime, ()| r.current_instant());
+        assert_eq!(engine::execute_process(waiter.join(emitter)).0, 2);
+
+        // Without emission, awaiting 0 emissions returns immediately.
+        let waiter = s.await_n(0).map_rt(|r: &mut engine::Runtime, ()| r.current_instant());
+        assert_eq!(engine::execute_process(waiter), 0);
+    }
+
+    #[test]
+    fn test_present_value() {
+        let s = puresignal::new();
+
+        // The signal is only emitted at instant 1.
+        let emitter = value(()).pause().emit(&s);
+
+        let mut values = vec!();
+        let record = move |v: (bool, i32)| {
+            values.push(v);
+            if values.len() == 3 {
+                LoopStatus::Exit(values.clone())
+            } else {
+                LoopStatus::Continue
+            }
+        };
+        // Both branches last one instant, so each iteration tests a new instant.
+        let watcher = s.present_value(value(1).pause(), value(2)).map(record).loop_while();
+
+        assert_eq!(engine::execute_process(watcher.join(emitter)),
+                   (vec!((false, 2), (true, 1), (false, 2)), ()));
+    }
+
+    #[test]
+    fn test_await_in_dyn() {
+        let s1 = value_signal::new(0, |v1: i32, v2: i32| v1 + v2);
+        let s2 = value_signal::new(0, |v1: i32, v2: i32| v1 + v2);
+        let mapped = s2.map_in(|v| v * 10);
+
+        // Different kinds of signals, all yielding an `i32`.
+        let signals: Vec<Box<AwaitInDyn<Value=i32>>> = vec!(Box::new(s1.clone()), Box::new(mapped));
+        let awaits = multi_join(signals.iter().map(|s| s.await_in_dyn()).collect());
+
+        let p = awaits.join(s1.emit(value(5)).join(s2.emit(value(3))));
+        assert_eq!(engine::execute_process(p).0, vec!(5, 30));
+    }
+
+    #[test]
+    fn test_signal_reset() {
+        // A process waiting for a signal which is never emitted leaves its continuation behind.
+        let s = puresignal::new();
+        let woken = Arc::new(Mutex::new(0));
+        let woken2 = woken.clone();
+        let waiter = s.await_immediate().map(move |()| *woken2.lock().unwrap() += 1);
+        assert_eq!(engine::try_execute_process(waiter), Err(engine::ExecError::Deadlock));
+
+        // Once reset, the next execution does not wake it up, and starts with an absent signal.
+        s.reset();
+        let p = s.is_present().join(value(()).pause().emit(&s));
+        assert_eq!(engine::execute_process(p), (false, ()));
+        assert_eq!(*woken.lock().unwrap(), 0);
+
+        // The value of the signal is restored too.
+        let latch = latch_signal::new(0);
+        engine::execute_process(latch.emit(value(5)));
+        assert_eq!(engine::execute_process(latch.read_latest()), 5);
+        latch.reset();
+        assert_eq!(engine::execute_process(latch.read_latest()), 0);
+    }
+
+    #[test]
+    fn test_at_end_of_instant() {
+        let s = value_signal::new(0, |v1: i32, v2: i32| v1 + v2);
+
+        // The value 5 is emitted during the first instant, and 3 during the second one. The reader
+        // waits for the value of the signal from the end of the first instant, once the signal
+        // has been reset: it gets the value gathered from the default one in the second instant.
+        // The sequential runtime runs the end of instant continuations in the reverse order of
+        // their registration, so the reset registered by the emission runs before the reader.
+        let reader = s.await_in().at_end_of_instant();
+        let writer = value(5).emit(&s).pause().then(value(3).emit(&s));
+
+        assert_eq!(engine::execute_process_sequential(reader.join(writer)), (3, ()));
+    }
+
+    #[test]
+    fn test_boxed() {
+        let p: process::BoxedProcess<i32> = value(1).map(|x| x + 1).pause().map(|x| x * 3).boxed();
+        let p = p.pause().boxed();
+        assert_eq!(engine::execute_process(p), 6);
+    }
+
+    #[test]
+    fn test_observe_signal() {
+        let s = value_signal::new(0, |v1, v2| v1 + v2);
+
+        let program = s.emit(value(1)).pause()
+            .then(s.emit(value(2))).pause()
+            .then(s.emit(value(3))).pause()
+            .then(s.emit(value(4)).join(s.emit(value(5))));
+
+        assert_eq!(engine::observe_signal(&s, program, 6), vec!(1, 2, 3, 9));
+    }
+
+    #[test]
+    fn test_sleep() {
+        let start = time::Instant::now();
+        let p = value(()).sleep(time::Duration::from_millis(50)).then(value(1));
+        assert_eq!(engine::execute_process(p), 1);
+        assert!(start.elapsed() >= time::Duration::from_millis(50));
+
+        // The other processes keep running while a process sleeps: the clock advances by 10ms at
+        // the end of each instant, so a sleep of 35ms started at instant 0 ends at instant 4.
+        let clock = Arc::new(VirtualClock::new());
+        let tick_clock = clock.clone();
+        let mut ticks = 0;
+        let tick = move |()| {
+            tick_clock.advance(time::Duration::from_millis(10));
+            ticks += 1;
+            if ticks == 10 {
+                LoopStatus::Exit(())
+            } else {
+                LoopStatus::Continue
+            }
+        };
+        let ticker = value(()).map(tick).at_end_of_instant().loop_while();
+        let sleeper = value(()).sleep(time::Duration::from_millis(35))
+            .map_rt(|r: &mut engine::Runtime, ()| r.current_instant());
+
+        let mut r = SequentialRuntime::new();
+        r.set_clock(clock.clone());
+        let result = Arc::new(Mutex::new(None));
+        let result2 = result.clone();
+        let p = sleeper.join(ticker);
+        r.execute(Box::new(move |runtime: &mut engine::Runtime, ()| {
+            p.call(runtime, move |_: &mut engine::Runtime, v| *result2.lock().unwrap() = Some(v));
+        }), -1);
+        assert_eq!(*result.lock().unwrap(), Some((4, ())));
+        // Sleeping did not advance the clock itself.
+        assert_eq!(clock.now(), time::Duration::from_millis(100));
+
+        // When nothing else can be done, the runtime waits for the clock.
+        let clock = Arc::new(VirtualClock::new());
+        let mut r = SequentialRuntime::new();
+        r.set_clock(clock.clone());
+        let p = value(()).sleep(time::Duration::from_millis(35));
+        r.execute(Box::new(move |runtime: &mut engine::Runtime, ()| {
+            p.call(runtime, |_: &mut engine::Runtime, ()| {});
+        }), -1);
+        assert_eq!(clock.now(), time::Duration::from_millis(35));
+    }
+
+    #[test]
+    fn test_throttle() {
+        let clock = Arc::new(VirtualClock::new());
+        let period = time::Duration::from_millis(10);
+        let times = Arc::new(Mutex::new(vec!()));
+        let times2 = times.clone();
+
+        // Each iteration does 3ms of work, and is throttled to one iteration every 10ms.
+        let work_clock = clock.clone();
+        let work = move |()| work_clock.advance(time::Duration::from_millis(3));
+        let record_clock = clock.clone();
+        let mut count = 0;
+        let record = move |()| {
+            times2.lock().unwrap().push(record_clock.now());
+            count += 1;
+            if count == 5 {
+                LoopStatus::Exit(())
+            } else {
+                LoopStatus::Continue
+            }
+        };
+        let body = value(()).map(work).throttle(period).map(record).pause().loop_while();
+
+        let mut r = SequentialRuntime::new();
+        r.set_clock(clock.clone());
+        r.execute(Box::new(move |runtime: &mut engine::Runtime, ()| {
+            body.call(runtime, |_: &mut engine::Runtime, ()| {});
+        }), -1);
+
+        let expected: Vec<_> = (1..6).map(|i| period * i).collect();
+        assert_eq!(*times.lock().unwrap(), expected);
+        assert_eq!(clock.now(), period * 5);
+    }
+
+    #[test]
+    fn test_timeout() {
+        let p = || value(42).pause().pause();
+        assert_eq!(engine::execute_process(p().timeout(2)), Some(42));
+        assert_eq!(engine::execute_process(p().timeout(1)), None);
+        assert_eq!(engine::execute_process(value(42).timeout(0)), Some(42));
+        assert_eq!(engine::execute_process(value(42).pause().timeout(0)), None);
+
+        // On timeout, the process is aborted: it ran during instants 0 to 3.
+        let counter = Arc::new(Mutex::new(0));
+        let counter2 = counter.clone();
+        let count = move |()| *counter2.lock().unwrap() += 1;
+        let p = value(()).map(count).pause().loop_inf().timeout(3);
+        assert_eq!(engine::execute_process(p), None);
+        assert_eq!(*counter.lock().unwrap(), 4);
+
+        // Once the process has returned, the timeout does not keep the runtime busy.
+        let (v, stats) = engine::execute_process_steps_with_stats(value(1).timeout(1000), 2, -1);
+        assert_eq!(v, Some(Some(1)));
+        assert!(stats.instants <= 2);
+    }
+
+    #[test]
+    fn test_inspect() {
+        let seen = Arc::new(Mutex::new(vec!()));
+        let seen2 = seen.clone();
+        let record = move |v: &i32| seen2.lock().unwrap().push(*v);
+        assert_eq!(engine::execute_process(value(42).inspect(record.clone()).pause()), 42);
+        assert_eq!(*seen.lock().unwrap(), vec!(42));
+
+        // The mutable process is re-threaded at each iteration.
+        let mut n = 3;
+        let decrementer = move |()| { n -= 1; n };
+        let exit = |n: i32| if n == 0 { LoopStatus::Exit(n) } else { LoopStatus::Continue };
+        let p = value(()).pause().map(decrementer).inspect(record).map(exit).loop_while();
+        assert_eq!(engine::execute_process(p), 0);
+        assert_eq!(*seen.lock().unwrap(), vec!(42, 2, 1, 0));
+    }
+
+    #[test]
+    fn test_on_complete() {
+        let count = Arc::new(Mutex::new(0));
+        let count2 = count.clone();
+        let cleanup = move || *count2.lock().unwrap() += 1;
+        let p = value(42).pause().on_complete(cleanup.clone());
+        assert_eq!(engine::execute_process(p), 42);
+        assert_eq!(*count.lock().unwrap(), 1);
+
+        // The hook of a mutable process fires once per iteration.
+        let p = value(()).pause().on_complete(cleanup).take(3);
+        assert_eq!(engine::execute_process(p).len(), 3);
+        assert_eq!(*count.lock().unwrap(), 4);
+    }
+
+    #[test]
+    fn test_map_in() {
+        let s = value_signal::new(0, |v1: i32, v2: i32| v1 + v2);
+        let mapped = s.map_in(|v: i32| format!("value {}", v));
+
+        // Both views are present at the same instants.
+        let emitter = s.emit(value(1)).pause().then(s.emit(value(2)).join(s.emit(value(3))));
+        let receiver = mapped.await_in().join(mapped.await_in())
+            .then(mapped.is_present())
+            .then(mapped.await_in());
+
+        let (_, v) = engine::execute_process(emitter.join(receiver));
+        assert_eq!(v, "value 5");
+
+        let v = engine::observe_signal(&mapped, s.emit(value(4)), 3);
+        assert_eq!(v, vec!("value 4".to_string()));
+    }
+
+    #[test]
+    fn test_await_any() {
+        let signals = vec!(puresignal::new(), puresignal::new(), puresignal::new());
+
+        // The second signal is emitted at the second instant.
+        let emitter = value(()).pause().then(value(()).emit(&signals[1]));
+        let receiver = await_any(&signals).map_rt(|r: &mut engine::Runtime, i| (i, r.current_instant()));
+        let (_, (i, instant)) = engine::execute_process(emitter.join(receiver));
+        assert_eq!((i, instant), (1, 2));
+
+        // When several signals are emitted in the same instant, the lowest index wins.
+        let emitter = value(()).emit(&signals[2]).join(value(()).emit(&signals[1]));
+        let (_, i) = engine::execute_process(emitter.join(await_any(&signals)));
+        assert_eq!(i, 1);
+    }
+
+    #[test]
+    fn test_await_any_loop_drops_losers() {
+        let signals = vec!(puresignal::new(), puresignal::new());
+        let b = signals[1].clone();
+        let stop = puresignal::new();
+
+        // Only the first signal is emitted, so each iteration leaves a losing registration on
+        // the second one, which must not accumulate.
+        let emitter = stop.do_until(value(()).emit(&signals[0]).pause().loop_inf());
+
+        let max_waiting = Arc::new(Mutex::new(0));
+        let max_waiting2 = max_waiting.clone();
+        let mut iterations = 0;
+        let check = move |i: usize| {
+            assert_eq!(i, 0);
+            let mut max_waiting = max_waiting2.lock().unwrap();
+            *max_waiting = usize::max(*max_waiting, b.runtime().waiting_count());
+            iterations += 1;
+            if iterations == 1000 { LoopStatus::Exit(()) } else { LoopStatus::Continue }
+        };
+        let receiver = stop.emit(await_any(&signals).map(check).loop_while());
+
+        let (aborted, ()) = engine::execute_process(emitter.join(receiver));
+        assert_eq!(aborted, None);
+        assert!(*max_waiting.lock().unwrap() < 100);
+    }
+
+    #[test]
+    fn test_scan() {
+        let s = value_signal::new(0, |v1: i32, v2: i32| v1 + v2);
+
+        // The values 1, 2 and 3 are emitted in three successive instants.
+        let emitter = s.emit(value(1)).pause().then(s.emit(value(2))).pause().then(s.emit(value(3)));
+        let running_sum = s.scan(0, |acc, v| acc + v).take(3);
+
+        let (_, sums) = engine::execute_process(emitter.join(running_sum));
+        assert_eq!(sums, vec!(1, 3, 6));
+    }
+
+    #[test]
+    fn test_present_mut_branches() {
+        let s = puresignal::new();
+
+        // The signal is emitted at instants 0, 1 and 3.
+        let emitted = |instant: u64| instant != 2 && instant != 4;
+        let emitter = process::instant().map(emitted).then_else(value(()).emit(&s), value(()))
+            .pause().take(5);
+
+        // Each branch counts its own executions: the absent one is not advanced while the present
+        // one is executed, and conversely.
+        let mut present_count = 0;
+        let present_step = move |()| { present_count += 1; ("present", present_count) };
+        let mut absent_count = 0;
+        let absent_step = move |()| { absent_count += 1; ("absent", absent_count) };
+        let receiver = s.present(value(()).map(present_step).pause(), value(()).map(absent_step))
+            .take(5);
+
+        let (_, steps) = engine::execute_process(emitter.join(receiver));
+        assert_eq!(steps, vec!(("present", 1), ("present", 2), ("absent", 1), ("present", 3),
+                               ("absent", 2)));
+    }
+
+    #[test]
+    fn test_counter_signal() {
+        let s = counter_signal::new();
+
+        // Five emissions in the first instant, two in the second one.
+        let emissions = (0..5).map(|_| value(()).emit(&s)).collect();
+        let emitter = process::multi_join(emissions)
+            .pause()
+            .then(value(()).emit(&s).join(value(()).emit(&s)));
+        let receiver = s.await_in().join(value(()).pause().then(s.await_in()));
+
+        let (_, counts) = engine::execute_process(emitter.join(receiver));
+        assert_eq!(counts, (5, 2));
+    }
+
+    #[test]
+    fn test_history_recorder() {
+        use engine::signal::history_recorder::HistoryRecorder;
+
+        let s = value_signal::new(0, |v1: i32, v2: i32| v1 + v2);
+        let recorder = HistoryRecorder::new(&s, 3);
+
+        // The values 1 to 5 are emitted through the recorder, one per instant.
+        let mut n = 0;
+        let incr = move |()| { n += 1; n };
+        let mut k = 0;
+        let stop = move |()| { k += 1; if k == 5 { LoopStatus::Exit(()) } else { LoopStatus::Continue } };
+        let emitter = value(()).map(incr).emit(&recorder).pause().map(stop).loop_while();
+
+        // The original signal receives the values emitted through the recorder.
+        let receiver = s.await_in().join(recorder.await_in());
+        let ((), (v1, v2)) = engine::execute_process(emitter.join(receiver));
+        assert_eq!((v1, v2), (1, 1));
+        assert_eq!(recorder.history(), vec!((2, 3), (3, 4), (4, 5)));
+
+        recorder.reset();
+        assert_eq!(recorder.history(), vec!());
+    }
+
+    #[test]
+    fn test_bounded_mpsc_signal() {
+        let overflowed = Arc::new(Mutex::new(vec!()));
+        let overflowed2 = overflowed.clone();
+        let (s, r) = bounded_mpsc_signal::with_cap(|v1: i32, v2: i32| v1 + v2, 3, move |v: i32| {
+            overflowed2.lock().unwrap().push(v);
+        });
+
+        // The values 1 to 5 are emitted during the first instant.
+        let mut n = 0;
+        let incr = move |()| { n += 1; n };
+        let mut k = 0;
+        let stop = move |()| { k += 1; if k == 5 { LoopStatus::Exit(()) } else { LoopStatus::Continue } };
+        let s2 = s.clone();
+        let count = move |()| s2.emission_count();
+        let emitter = s.emit(value(()).map(incr)).map(stop).loop_while()
+            .then(value(()).map(count.clone()))
+            .pause()
+            .join(value(()).pause().map(count));
+
+        let (counts, sum) = engine::execute_process(emitter.join(r.await_in()));
+        // The counter is reset at the end of the instant.
+        assert_eq!(counts, (5, 0));
+        assert_eq!(sum, 1 + 2 + 3);
+        assert_eq!(*overflowed.lock().unwrap(), vec!(4, 5));
+    }
+
     #[test]
     fn test_mpsc_signal() {
+        #[derive(Clone)]
         pub struct TestStruct {
             content: i32,
         }
@@ -635,6 +3012,28 @@ mod tests {
         assert_eq!(engine::execute_process(p), (11, 10));
     }
 
+    #[test]
+    fn test_mpsc_await_one_immediate() {
+        use engine::signal::history_recorder::HistoryRecorder;
+
+        let (s, r) = mpsc_signal::new(|v: i32, sum: i32| sum + v);
+
+        // Nothing is emitted in the first instant, then two values are emitted in the second one.
+        // The readers waiting for the signal get the value gathered when the first emission made it
+        // present, while a reader started after both emissions gets their sum.
+        let emitter = value(()).pause().then(s.emit(value(1))).then(s.emit(value(2)))
+            .then(r.await_one_immediate());
+        let instant = |r: &mut engine::Runtime, sum: i32| (r.current_instant(), sum);
+        let reader = r.await_one_immediate().map_rt(instant);
+        // The receiver can also be read through generic views of signals.
+        let viewer = HistoryRecorder::new(&r, 1).await_one_immediate();
+
+        let (sum, (read, viewed)) = engine::execute_process(emitter.join(reader.join(viewer)));
+        assert_eq!(sum, 1 + 2);
+        assert_eq!(read, (1, 1));
+        assert_eq!(viewed, 1);
+    }
+
     #[test]
     fn test_spmc_signal() {
 
@@ -678,6 +3077,88 @@ mod tests {
         assert_eq!(engine::execute_process(p), (10, (20, 20)));
     }
 
+    #[test]
+    fn test_spmc_signal_strict() {
+        // Emitting twice in an instant keeps the last value by default.
+        let (sender, receiver) = spmc_signal::new();
+        let mut n = 0;
+        let counter = move |()| { n += 1; n };
+        let p = sender.emit(value(()).map(counter)).take(2).join(receiver.await_in());
+        assert_eq!(engine::execute_process(p).1, 2);
+
+        // A strict signal panics instead, but can be emitted at each instant.
+        let (sender, receiver) = spmc_signal::new_strict();
+        let p = sender.emit(value(1)).pause().take(2).join(receiver.await_in());
+        assert_eq!(engine::execute_process(p).1, 1);
+
+        let (sender, _) = spmc_signal::new_strict();
+        let p = sender.emit(value(1)).take(2);
+        let error = engine::ExecError::WorkerPanicked("SPMC signal emitted twice in the same instant.".to_string());
+        assert_eq!(engine::try_execute_process_steps(p, 1, -1).map(|_| ()), Err(error));
+    }
+
+    #[test]
+    fn test_spsc_signal() {
+        // Neither Clone nor Copy.
+        pub struct TestStruct {
+            content: Vec<i32>,
+        }
+
+        let (sender, receiver) = spsc_signal::new();
+        let p1 = sender.emit(value(()).map(|()| TestStruct { content: vec!(1, 2, 3) }));
+        let p2 = receiver.await_in().map(|v: TestStruct| v.content);
+
+        assert_eq!(engine::execute_process(p1.join(p2)), ((), vec!(1, 2, 3)));
+    }
+
+
+    #[test]
+    fn test_external_signal() {
+        let (feeder, signal) = external_signal::new();
+
+        // A background thread pushes three values.
+        let handle = thread::spawn(move || {
+            for i in 1..4 {
+                thread::sleep(time::Duration::from_millis(5));
+                feeder.push(i);
+            }
+        });
+
+        let mut collected = vec!();
+        let collect = move |values: Vec<i32>| {
+            collected.extend(values);
+            if collected.len() >= 3 {
+                LoopStatus::Exit(collected.clone())
+            } else {
+                LoopStatus::Continue
+            }
+        };
+
+        let p = signal.await_in().map(collect).loop_while();
+        assert_eq!(engine::execute_process(p), vec![1, 2, 3]);
+        handle.join().unwrap();
+    }
+
+    #[test]
+    fn test_external_emitter() {
+        use engine::signal::external_signal::ExternalEmitter;
+
+        let s = value_signal::new(0, |v1: i32, v2: i32| v1 + v2);
+        let emitter = ExternalEmitter::new(&s);
+
+        // A background thread emits the signal once the reactive program runs.
+        let emitter2 = emitter.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(time::Duration::from_millis(5));
+            emitter2.emit(42);
+        });
+
+        // The values are forwarded at each instant, until one is emitted.
+        let forward = emitter.forward().until(|&n| n == 0);
+        let (n, v) = engine::execute_process(forward.join(s.await_in()));
+        assert_eq!((n, v), (1, 42));
+        handle.join().unwrap();
+    }
 
     #[test]
     fn test_join_mut() {
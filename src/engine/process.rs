@@ -1,9 +1,12 @@
-use super::Runtime;
+use super::{CatchPoint, Runtime};
 use super::continuation::Continuation;
-use std::sync::{Arc, Mutex};
+use std::any::Any;
+use std::panic;
+use std::sync::{Arc, Mutex, Weak};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
 use super::signal::*;
-use super::signal::signal_runtime::ValueRuntime;
-use std::thread;
+use super::signal::signal_runtime::{SignalRuntimeRef, ValueRuntime};
 
 /// A reactive process.
 pub trait Process: 'static + Send {
@@ -18,6 +21,50 @@ pub trait Process: 'static + Send {
         Pause {process: self}
     }
 
+    /// Creates a new process that waits `n` instants before returning the value of the process.
+    /// This is equivalent to `n` calls to `pause`, without nesting their types.
+    fn delay(self, n: usize) -> Delay<Self> where Self: Sized, Self::Value: Send {
+        Delay { process: self, n }
+    }
+
+    /// Creates a new process that executes `self` at the end of the current instant, once all the
+    /// continuations of the instant have been executed. As any end of instant execution, the
+    /// runtime calls of `self` behave as if they were executed during the next instant.
+    fn at_end_of_instant(self) -> AtEndOfInstant<Self> where Self: Sized {
+        AtEndOfInstant {process: self}
+    }
+
+    /// Creates a new process that executes `self`, then waits until at least `period` has elapsed
+    /// on the clock of the runtime since it started. The worker is blocked while waiting, so this
+    /// is meant to slow down a loop to a given rate, e.g. when displaying it.
+    fn throttle(self, period: Duration) -> Throttle<Self> where Self: Sized {
+        Throttle { process: self, period }
+    }
+
+    /// Creates a new process that executes `self`, then returns its value once at least `duration`
+    /// has elapsed on the clock of the runtime. Unlike `throttle`, no worker is blocked while
+    /// waiting: the value is returned at the first instant starting after `duration`, so the other
+    /// processes keep running in the meantime.
+    fn sleep(self, duration: Duration) -> Sleep<Self> where Self: Sized, Self::Value: Send {
+        Sleep { process: self, duration }
+    }
+
+    /// Creates a new process that executes `self` for at most `k` instants after the current one.
+    /// It returns `Some` value if `self` returns in time. Otherwise `self` is aborted at the end of
+    /// the `k`-th instant, where `None` is returned: as any end of instant execution, the runtime
+    /// calls of the continuation behave as if they were executed during the next instant. With
+    /// `k == 0`, `self` has to return during the current instant.
+    fn timeout(self, k: usize) -> Timeout<Self> where Self: Sized {
+        Timeout { process: self, k }
+    }
+
+    /// Creates a new process that erases the type of `self`, which stops the growth of the types
+    /// of nested processes. Calling the boxed process costs a dynamic dispatch, and the boxing of
+    /// its continuation.
+    fn boxed(self) -> BoxedProcess<Self::Value> where Self: Sized {
+        BoxedProcess { process: Box::new(self) }
+    }
+
     /// Creates a new process that applies a function to the output value of `self`.
     fn map<F, V2>(self, map: F) -> Map<Self, F>
         where Self: Sized, F: FnOnce(Self::Value) -> V2 + 'static + Send
@@ -25,6 +72,31 @@ pub trait Process: 'static + Send {
         Map { process: self, map }
     }
 
+    /// Creates a new process that applies a function to the output value of `self`, with access to
+    /// the runtime, e.g. to read the current instant.
+    fn map_rt<F, V2>(self, f: F) -> MapRt<Self, F>
+        where Self: Sized, F: FnOnce(&mut Runtime, Self::Value) -> V2 + 'static + Send
+    {
+        MapRt { process: self, map: f }
+    }
+
+    /// Creates a new process that calls `f` on a reference to the output value of `self`, then
+    /// returns the value unchanged. Useful to debug a chain of processes.
+    fn inspect<F>(self, f: F) -> Inspect<Self, F>
+        where Self: Sized, F: Fn(&Self::Value) + 'static + Send
+    {
+        Inspect { process: self, inspect: f }
+    }
+
+    /// Creates a new process that calls `f` once `self` has returned, then returns the value of
+    /// `self` unchanged. Unlike `inspect`, `f` does not see the value: it is meant for some cleanup
+    /// to do whatever the value, e.g. closing a window.
+    fn on_complete<F>(self, f: F) -> OnComplete<Self, F>
+        where Self: Sized, F: FnOnce() + 'static + Send
+    {
+        OnComplete { process: self, on_complete: f }
+    }
+
     /// Creates a new process that executes the process returned by `self`.
     fn flatten<>(self) -> Flatten<Self>
         where Self: Sized, Self::Value: Process + Send {
@@ -32,8 +104,17 @@ pub trait Process: 'static + Send {
     }
 
 
+    /// Creates a new process that executes the process returned by `self` if it is `Ok`, or
+    /// returns the error otherwise.
+    fn flatten_result<Q, T, E>(self) -> FlattenResult<Self>
+        where Self: Sized + Process<Value=Result<Q, E>>, Q: Process<Value=Result<T, E>> {
+        FlattenResult { process: self }
+    }
+
     /// Creates a new process that executes the first process, applies the given function to the
     /// result, and executes the returned process.
+    /// In a loop, the returned process is created again at each iteration: use
+    /// `ProcessMut::and_then_mut` to keep its state between iterations.
     fn and_then<F, P>(self, function: F) -> AndThen<Self, F>
         where F: FnOnce(Self::Value) -> P + 'static + Send, Self: Sized, P: Process {
         self.map(function).flatten()
@@ -46,12 +127,57 @@ pub trait Process: 'static + Send {
         Then {process1: self, process2: process}
     }
 
+    /// Creates a new process that executes `self`, discards its returned value, and returns `v`.
+    fn then_return<V>(self, v: V) -> ThenReturn<Self, V> where Self: Sized, V: Send + 'static {
+        ThenReturn { process: self, value: v }
+    }
+
+    /// Creates a new process that executes `self`, and discards its returned value.
+    fn discard(self) -> Discard<Self> where Self: Sized {
+        self.then_return(())
+    }
+
+    /// Creates a new process that executes `self` once, then executes `body` in infinite loop.
+    fn then_loop<B>(self, body: B) -> ThenLoop<Self, B>
+        where Self: Process<Value=()> + Sized, B: ProcessMut<Value=()> + Sized {
+        self.then(body.loop_inf())
+    }
+
     /// Creates a new process that executes the two processes in parallel, and returns the couple of
     /// their return values.
     fn join<P>(self, process: P) -> Join<Self, P> where Self: Sized, P: Process + Sized {
         Join {process1: self, process2: process}
     }
 
+    /// Creates a new process that executes the two processes in parallel, and returns the result
+    /// of `f` on their return values.
+    fn zip_with<P, F, R>(self, process: P, f: F) -> ZipWith<Self, P, F>
+        where Self: Sized, P: Process + Sized, F: FnOnce(Self::Value, P::Value) -> R + 'static + Send
+    {
+        ZipWith { process1: self, process2: process, zip: f }
+    }
+
+    /// Creates a new process that executes the two processes in parallel, and returns the value of
+    /// the first one to terminate. The other one is aborted: its pending continuations become
+    /// inert, so that an infinite loop does not prevent the program from terminating.
+    /// If both terminate during the same instant, any of them may win.
+    fn race<P>(self, process: P) -> Race<Self, P>
+        where Self: Sized, P: Process<Value=Self::Value> + Sized
+    {
+        Race { process1: self, process2: process }
+    }
+
+    /// Creates a new process that executes `self`, and returns `Err` with the panic payload instead
+    /// of letting a panic of `self` stop the worker.
+    ///
+    /// The panics raised in any instant of the execution of `self` are caught. After a panic, the
+    /// remaining continuations of `self` become inert, as in an aborted `do_until` block. A panic
+    /// raised once `self` has terminated, by the continuation of the new process for instance, is
+    /// not caught. The panic hook still reports the caught panics.
+    fn catch_unwind(self) -> CatchUnwind<Self> where Self: Sized, Self::Value: Send {
+        CatchUnwind { process: self }
+    }
+
     /// Creates a new process that executes `self` and all the processes contained in `ps` in
     /// parallel, and returns a pair of values (`Self::Value`, `Vec<P::Value>`).
     fn multi_join<P>(self, ps: Vec<P>) -> Join<Self, MultiJoin<P>>
@@ -66,6 +192,14 @@ pub trait Process: 'static + Send {
         ThenElse { condition: self, q1, q2}
     }
 
+    /// Creates a new process that executes process `q1` if the result of `self` is true, and `q2`
+    /// otherwise. Unlike `then_else`, the branches may return different types: the value of `q1`
+    /// is returned as `Left`, and the one of `q2` as `Right`.
+    fn branch<Q1, Q2>(self, q1: Q1, q2: Q2) -> Branch<Self, Q1, Q2>
+        where Self: Process<Value=bool> + Sized, Q1: Process, Q2: Process {
+        Branch { condition: self, q1, q2 }
+    }
+
     /// Creates a new process that emits the value returned by `self` on the signal `s`, without
     /// consuming `s`.
     fn emit<S>(self, s: &S) -> Emit<S, Self>
@@ -74,6 +208,31 @@ pub trait Process: 'static + Send {
         s.emit(self)
     }
 
+    /// Creates a new process that applies a fallible function to the output value of `self`. The
+    /// `Ok` values are emitted on `ok_signal`, the `Err` values on `err_signal`, and the result is
+    /// returned.
+    fn map_result_into_signal<F, T, E, S1, S2>(self, map: F, ok_signal: &S1, err_signal: &S2)
+        -> MapResultIntoSignal<Self, F, S1, S2>
+        where Self: Sized, F: FnOnce(Self::Value) -> Result<T, E> + 'static + Send,
+              S1: SEmit, S2: SEmit, T: Clone, E: Clone,
+              S1::VR: ValueRuntime<V1=T>, S2::VR: ValueRuntime<V1=E>
+    {
+        MapResultIntoSignal {
+            process: self, map, ok_signal: ok_signal.runtime(), err_signal: err_signal.runtime(),
+        }
+    }
+
+    /// Creates a new process that executes `self`, then waits for the first signal of `arms` to be
+    /// emitted, and applies the mapping of its arm to the value returned by `self` at the next
+    /// instant, like `await_any`.
+    /// If several signals are emitted during the same instant, the first arm among them, in the
+    /// order of `arms`, is selected.
+    fn select_map<V>(self, arms: Vec<SelectArm<Self::Value, V>>) -> SelectMap<Self, V>
+        where Self: Sized
+    {
+        SelectMap { process: self, arms }
+    }
+
     /// Creates a new process that emits the value returned by `self` on the signal `s`, and
     /// consumes `s`.
     fn emit_consume<S>(self, s: S) -> Emit<S, Self>
@@ -96,6 +255,27 @@ pub trait ProcessMut: Process {
         Mut { process: self }
     }
 
+    /// Creates a process that executes `self`, applies the given function to the result and to
+    /// the process it returned at the previous execution, and executes the returned process.
+    /// Unlike `and_then`, which creates a fresh process at each execution, the returned ProcessMut
+    /// is kept between executions, so that its internal state persists in a loop. The function
+    /// receives `None` at the first execution.
+    fn and_then_mut<F, Q>(self, function: F) -> AndThenMut<Self, F, Q>
+        where Self: Sized, F: FnMut(Self::Value, Option<Q>) -> Q + 'static + Send, Q: ProcessMut
+    {
+        AndThenMut { process: self, function, inner: None }
+    }
+
+    /// Creates a process that executes `self`, applies the given function to the result, and
+    /// executes the returned process. This is `and_then` for a function called at each execution:
+    /// its captured state is kept between executions, while the returned process is created again
+    /// each time. Use `and_then_mut` to keep the returned process instead.
+    fn flat_map<F, Q>(self, function: F) -> AndThen<Self, F>
+        where Self: Sized, F: FnMut(Self::Value) -> Q + 'static + Send, Q: Process
+    {
+        self.and_then(function)
+    }
+
     /// Creates a process that executes a ProcessMut with return type LoopStatus until it returns
     /// Exit(v).
     fn loop_while(self) -> While<Self> where Self: Sized {
@@ -111,6 +291,42 @@ pub trait ProcessMut: Process {
         };
         While { process: self.map(c) }
     }
+
+    /// Creates a process that executes a ProcessMut with return type `Option<T>` until it returns
+    /// `None`. Each value `Some(v)` is combined with the accumulator, starting with `init`, by
+    /// `acc = f(acc, v)`. The final accumulator is returned.
+    /// `LoopStatus::Continue` carries no value, hence the iterations report their values with
+    /// `Some(v)`, and the end of the loop with `None`.
+    fn fold<A, F, T>(self, init: A, f: F) -> Fold<Self, A, F>
+        where Self: Process<Value=Option<T>> + Sized, F: FnMut(A, T) -> A + 'static + Send,
+              A: Clone + 'static + Send
+    {
+        Fold { process: self, init: init.clone(), acc: init, fold: f }
+    }
+
+    /// Creates a process that executes a ProcessMut `n` times in a row, and returns the vector of
+    /// the values of the iterations. No pause is added between two iterations.
+    fn take(self, n: usize) -> Take<Self> where Self: Sized, Self::Value: Send {
+        Take { process: self, n }
+    }
+
+    /// Creates a process that executes a ProcessMut while `pred` holds on its values, and returns
+    /// the first value for which `pred` returns `false`. This is `loop_while` for a body which
+    /// returns plain values.
+    fn until<F>(self, pred: F) -> Until<Self, F>
+        where Self: Sized, F: FnMut(&Self::Value) -> bool + 'static + Send
+    {
+        Until { process: self, pred }
+    }
+
+    /// Creates a process that executes a ProcessMut with return type `Result` until it returns
+    /// `Ok`, at most `max_attempts` times, and returns the last result. The process is executed at
+    /// least once, even if `max_attempts` is 0.
+    fn retry<T, E>(self, max_attempts: usize) -> Retry<Self>
+        where Self: Process<Value=Result<T, E>> + Sized
+    {
+        Retry { process: self, max_attempts }
+    }
 }
 
 
@@ -148,6 +364,59 @@ impl<V> ProcessMut for Value<V> where V: Copy + 'static + Send {
 }
 
 
+/// A basic process that returns a clone of a fixed value. Unlike `Value`, it can be executed in a
+/// loop as soon as the value is `Clone`.
+pub struct CloneValue<V> {
+    value: V,
+}
+
+/// Creates a process that returns a clone of the value `v`.
+pub fn clone_value<V>(v: V) -> CloneValue<V> where V: Clone + 'static {
+    CloneValue { value: v }
+}
+
+impl<V> Process for CloneValue<V> where V: Clone + 'static + Send {
+    type Value = V;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        next.call(runtime, self.value);
+    }
+}
+
+impl<V> ProcessMut for CloneValue<V> where V: Clone + 'static + Send {
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)> {
+        let v = self.value.clone();
+        next.call(runtime, (self, v));
+    }
+}
+
+/// A process that returns the number of the current instant.
+pub struct Instant {}
+
+/// Creates a process that returns the number of the current instant.
+pub fn instant() -> Instant {
+    Instant {}
+}
+
+impl Process for Instant {
+    type Value = u64;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let instant = runtime.current_instant();
+        next.call(runtime, instant);
+    }
+}
+
+impl ProcessMut for Instant {
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)> {
+        let instant = runtime.current_instant();
+        next.call(runtime, (self, instant));
+    }
+}
+
+
 /// A process that waits an instant before returning the value of process.
 pub struct Pause<P> {
     process: P,
@@ -178,6 +447,224 @@ impl<P> ProcessMut for Pause<P> where P: ProcessMut, P::Value: Send {
 }
 
 
+/// A process that waits some instants before returning the value of process.
+pub struct Delay<P> {
+    process: P,
+    n: usize,
+}
+
+/// Calls `next` with `value` after `n` instants.
+fn delay_value<V, C>(runtime: &mut Runtime, n: usize, value: V, next: C)
+    where V: Send + 'static, C: Continuation<V>
+{
+    if n == 0 {
+        next.call(runtime, value);
+    } else {
+        runtime.on_next_instant(Box::new(move |r: &mut Runtime, ()| {
+            delay_value(r, n - 1, value, next);
+        }));
+    }
+}
+
+impl<P> Process for Delay<P> where P: Process, P::Value: Send {
+    type Value = P::Value;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let n = self.n;
+        self.process.call(runtime, move |runtime: &mut Runtime, value: P::Value| {
+            delay_value(runtime, n, value, next);
+        });
+    }
+}
+
+impl<P> ProcessMut for Delay<P> where P: ProcessMut, P::Value: Send {
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        let n = self.n;
+        self.process.call_mut(runtime, move |runtime: &mut Runtime, (p, v): (P, P::Value)| {
+            delay_value(runtime, n, (p.delay(n), v), next);
+        });
+    }
+}
+
+
+/// A process that executes a Process at the end of the instant.
+pub struct AtEndOfInstant<P> {
+    process: P,
+}
+
+impl<P> Process for AtEndOfInstant<P> where P: Process {
+    type Value = P::Value;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        runtime.on_end_of_instant(Box::new(move |r: &mut Runtime, ()| {
+            self.process.call(r, next);
+        }));
+    }
+}
+
+impl<P> ProcessMut for AtEndOfInstant<P> where P: ProcessMut {
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)> {
+        runtime.on_end_of_instant(Box::new(move |r: &mut Runtime, ()| {
+            self.process.call_mut(r, move |r: &mut Runtime, (p, v): (P, P::Value)| {
+                next.call(r, (p.at_end_of_instant(), v));
+            });
+        }));
+    }
+}
+
+
+/// A process that lasts at least a given period, according to the clock of the runtime.
+pub struct Throttle<P> {
+    process: P,
+    period: Duration,
+}
+
+/// Waits on the clock of the runtime until `period` has elapsed since `start`.
+fn wait_period(runtime: &mut Runtime, start: Duration, period: Duration) {
+    let elapsed = runtime.clock().now() - start;
+    if elapsed < period {
+        runtime.clock().advance(period - elapsed);
+    }
+}
+
+impl<P> Process for Throttle<P> where P: Process {
+    type Value = P::Value;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let start = runtime.clock().now();
+        let period = self.period;
+        self.process.call(runtime, move |r: &mut Runtime, v: P::Value| {
+            wait_period(r, start, period);
+            next.call(r, v);
+        });
+    }
+}
+
+impl<P> ProcessMut for Throttle<P> where P: ProcessMut {
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)> {
+        let start = runtime.clock().now();
+        let period = self.period;
+        self.process.call_mut(runtime, move |r: &mut Runtime, (p, v): (P, P::Value)| {
+            wait_period(r, start, period);
+            next.call(r, (p.throttle(period), v));
+        });
+    }
+}
+
+
+/// A process that returns the value of a process once a given duration has elapsed, according to
+/// the clock of the runtime.
+pub struct Sleep<P> {
+    process: P,
+    duration: Duration,
+}
+
+impl<P> Process for Sleep<P> where P: Process, P::Value: Send {
+    type Value = P::Value;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let duration = self.duration;
+        self.process.call(runtime, move |r: &mut Runtime, v: P::Value| {
+            let time = r.clock().now() + duration;
+            r.on_time(time, Box::new(move |r: &mut Runtime, ()| next.call(r, v)));
+        });
+    }
+}
+
+impl<P> ProcessMut for Sleep<P> where P: ProcessMut, P::Value: Send {
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)> {
+        let duration = self.duration;
+        self.process.call_mut(runtime, move |r: &mut Runtime, (p, v): (P, P::Value)| {
+            let time = r.clock().now() + duration;
+            r.on_time(time, Box::new(move |r: &mut Runtime, ()| next.call(r, (p.sleep(duration), v))));
+        });
+    }
+}
+
+
+/// A process that is aborted if it does not return within some instants.
+pub struct Timeout<P> {
+    process: P,
+    k: usize,
+}
+
+/// Calls `c` at the end of the `k`-th instant after the current one, as long as `pending` returns
+/// true. Once it returns false, the remaining instants are not scheduled anymore.
+fn at_end_of_kth_instant<F, C>(runtime: &mut Runtime, k: usize, pending: F, c: C)
+    where F: Fn() -> bool + Send + 'static, C: Continuation<()>
+{
+    if !pending() {
+        return;
+    }
+    if k == 0 {
+        runtime.on_end_of_instant(Box::new(c));
+    } else {
+        runtime.on_next_instant(Box::new(move |r: &mut Runtime, ()| {
+            at_end_of_kth_instant(r, k - 1, pending, c);
+        }));
+    }
+}
+
+impl<P> Process for Timeout<P> where P: Process {
+    type Value = Option<P::Value>;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        // The process races against the timeout, which stops counting the instants once the
+        // process has returned. On timeout, all the pending continuations of the process become
+        // inert.
+        let next = Arc::new(Mutex::new(Some(next)));
+        let abort_flag = Arc::new(AtomicBool::new(false));
+        let returned = Arc::new(AtomicBool::new(false));
+
+        let returned2 = returned.clone();
+        let pending = move || !returned2.load(Ordering::SeqCst);
+        let (next2, abort_flag2) = (next.clone(), abort_flag.clone());
+        at_end_of_kth_instant(runtime, self.k, pending, move |r: &mut Runtime, ()| {
+            let next = next2.lock().unwrap().take();
+            if let Some(next) = next {
+                abort_flag2.store(true, Ordering::SeqCst);
+                next.call(r, None);
+            }
+        });
+
+        race_branch(runtime, self.process.map(Some), next, abort_flag, returned);
+    }
+}
+
+
+/// Object-safe version of `Process`, taking a boxed continuation.
+trait ProcessBox<V>: Send {
+    /// Calls the boxed process.
+    fn call_box(self: Box<Self>, runtime: &mut Runtime, next: Box<Continuation<V>>);
+}
+
+impl<P> ProcessBox<P::Value> for P where P: Process {
+    fn call_box(self: Box<Self>, runtime: &mut Runtime, next: Box<Continuation<P::Value>>) {
+        (*self).call(runtime, move |r: &mut Runtime, v: P::Value| {
+            next.call_box(r, v);
+        });
+    }
+}
+
+/// A process whose type has been erased.
+pub struct BoxedProcess<V> {
+    process: Box<ProcessBox<V>>,
+}
+
+impl<V> Process for BoxedProcess<V> where V: 'static {
+    type Value = V;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        self.process.call_box(runtime, Box::new(next));
+    }
+}
+
+
 /// A process that applies a function to the output of a Process.
 pub struct Map<P, F> {
     process: P,
@@ -214,75 +701,258 @@ impl<P, F, V2> ProcessMut for Map<P, F>
 }
 
 
-/// A process that executes the process returned by a Process.
-pub struct Flatten<P> {
+/// A process that applies a function to the output of a Process, with access to the runtime.
+pub struct MapRt<P, F> {
     process: P,
+    map: F,
 }
 
-impl<P> Process for Flatten<P>
-    where P: Process, P::Value: Process
+impl<P, F, V2> Process for MapRt<P, F>
+    where P: Process, F: FnOnce(&mut Runtime, P::Value) -> V2 + 'static + Send
 {
-    type Value = <P::Value as Process>::Value;
+    type Value = V2;
 
     fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
-        self.process.call(runtime, |r: &mut Runtime, v: P::Value|{
-           v.call(r, next);
+        let map = self.map;
+        self.process.call(runtime, |r: &mut Runtime, v: P::Value| {
+            let v2 = map(r, v);
+            next.call(r, v2);
         });
     }
 }
 
-impl<P> ProcessMut for Flatten<P>
-    where P: ProcessMut, P::Value: Process
+impl<P, F, V2> ProcessMut for MapRt<P, F>
+    where P: ProcessMut, F: FnMut(&mut Runtime, P::Value) -> V2 + 'static + Send
 {
     fn call_mut<C>(self, runtime: &mut Runtime, next: C)
         where Self: Sized, C: Continuation<(Self, Self::Value)>
     {
-        self.process.call_mut(runtime, |r: &mut Runtime, (p, v): (P, P::Value)|{
-            v.call(r, |runtime: &mut Runtime, result: Self::Value| {
-                next.call(runtime, (p.flatten(), result));
-            });
+        let mut map = self.map;
+        self.process.call_mut(runtime, move |r: &mut Runtime, (p, v): (P, P::Value)| {
+            let v2 = map(r, v);
+            next.call(r, (p.map_rt(map), v2));
         });
     }
 }
 
-type AndThen<P, F> = Flatten<Map<P, F>>;
-
-/// A process that executes two processes sequentially, and return the value of the last process.
-
-pub struct Then<P, Q> {
-    process1: P,
-    process2: Q,
+/// A process that calls a function on a reference to the output of a Process.
+pub struct Inspect<P, F> {
+    process: P,
+    inspect: F,
 }
 
-impl <P, Q> Process for Then<P, Q>
-    where P: Process, Q: Process
+impl<P, F> Process for Inspect<P, F>
+    where P: Process, F: Fn(&P::Value) + 'static + Send
 {
-    type Value = Q::Value;
-
-    fn call<C>(self, runtime: &mut Runtime, next: C)
-        where C: Continuation<Self::Value> + Sized {
-
-        let p2 = self.process2;
-
-        let c = move |runtime: &mut Runtime, _: P::Value| {
-            p2.call(runtime, next);
-        };
+    type Value = P::Value;
 
-        self.process1.call(runtime, c);
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let inspect = self.inspect;
+        self.process.call(runtime,
+                          move |r: &mut Runtime, v: P::Value| {
+                              inspect(&v);
+                              next.call(r, v);
+                          });
     }
 }
 
-
-impl<P, Q> ProcessMut for Then<P, Q>
-    where P: ProcessMut, Q:ProcessMut
+impl<P, F> ProcessMut for Inspect<P, F>
+    where P: ProcessMut, F: Fn(&P::Value) + 'static + Send
 {
     fn call_mut<C>(self, runtime: &mut Runtime, next: C)
         where Self: Sized, C: Continuation<(Self, Self::Value)>
     {
-        let p2 = self.process2;
+        let inspect = self.inspect;
+        self.process.call_mut(runtime,
+                          move |r: &mut Runtime, (p, v): (P, P::Value)| {
+                              inspect(&v);
+                              next.call(r, (p.inspect(inspect), v));
+                          });
+    }
+}
+
+/// A process that calls a function once a process has returned.
+pub struct OnComplete<P, F> {
+    process: P,
+    on_complete: F,
+}
+
+impl<P, F> Process for OnComplete<P, F>
+    where P: Process, F: FnOnce() + 'static + Send
+{
+    type Value = P::Value;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let on_complete = self.on_complete;
+        self.process.call(runtime,
+                          move |r: &mut Runtime, v: P::Value| {
+                              on_complete();
+                              next.call(r, v);
+                          });
+    }
+}
+
+impl<P, F> ProcessMut for OnComplete<P, F>
+    where P: ProcessMut, F: FnMut() + 'static + Send
+{
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        let mut on_complete = self.on_complete;
+        self.process.call_mut(runtime,
+                          move |r: &mut Runtime, (p, v): (P, P::Value)| {
+                              on_complete();
+                              next.call(r, (p.on_complete(on_complete), v));
+                          });
+    }
+}
+
+/// A process that executes the process returned by a Process.
+pub struct Flatten<P> {
+    process: P,
+}
+
+impl<P> Process for Flatten<P>
+    where P: Process, P::Value: Process
+{
+    type Value = <P::Value as Process>::Value;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        self.process.call(runtime, |r: &mut Runtime, v: P::Value|{
+           v.call(r, next);
+        });
+    }
+}
+
+impl<P> ProcessMut for Flatten<P>
+    where P: ProcessMut, P::Value: Process
+{
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        self.process.call_mut(runtime, |r: &mut Runtime, (p, v): (P, P::Value)|{
+            v.call(r, |runtime: &mut Runtime, result: Self::Value| {
+                next.call(runtime, (p.flatten(), result));
+            });
+        });
+    }
+}
+
+type AndThen<P, F> = Flatten<Map<P, F>>;
+
+
+/// A process that executes the ProcessMut returned by a function applied to the output of a
+/// Process, and keeps it for the next execution.
+pub struct AndThenMut<P, F, Q> {
+    process: P,
+    function: F,
+    inner: Option<Q>,
+}
+
+impl<P, F, Q> Process for AndThenMut<P, F, Q>
+    where P: Process, F: FnMut(P::Value, Option<Q>) -> Q + 'static + Send, Q: ProcessMut
+{
+    type Value = Q::Value;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let mut function = self.function;
+        let inner = self.inner;
+        self.process.call(runtime, move |r: &mut Runtime, v: P::Value| {
+            function(v, inner).call(r, next);
+        });
+    }
+}
+
+impl<P, F, Q> ProcessMut for AndThenMut<P, F, Q>
+    where P: ProcessMut, F: FnMut(P::Value, Option<Q>) -> Q + 'static + Send, Q: ProcessMut
+{
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        let mut function = self.function;
+        let inner = self.inner;
+        self.process.call_mut(runtime, move |r: &mut Runtime, (p, v): (P, P::Value)| {
+            function(v, inner).call_mut(r, move |r: &mut Runtime, (q, result): (Q, Q::Value)| {
+                next.call(r, (AndThenMut { process: p, function, inner: Some(q) }, result));
+            });
+        });
+    }
+}
+
+
+/// A process that executes the fallible process returned by a Process, if it is `Ok`.
+pub struct FlattenResult<P> {
+    process: P,
+}
+
+impl<P, Q, T, E> Process for FlattenResult<P>
+    where P: Process<Value=Result<Q, E>>, Q: Process<Value=Result<T, E>>
+{
+    type Value = Result<T, E>;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        self.process.call(runtime, |r: &mut Runtime, v: Result<Q, E>| {
+            match v {
+                Ok(q) => q.call(r, next),
+                Err(e) => next.call(r, Err(e)),
+            }
+        });
+    }
+}
+
+impl<P, Q, T, E> ProcessMut for FlattenResult<P>
+    where P: ProcessMut + Process<Value=Result<Q, E>>, Q: Process<Value=Result<T, E>>
+{
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        self.process.call_mut(runtime, |r: &mut Runtime, (p, v): (P, Result<Q, E>)| {
+            match v {
+                Ok(q) => q.call(r, |runtime: &mut Runtime, result: Result<T, E>| {
+                    next.call(runtime, (p.flatten_result(), result));
+                }),
+                Err(e) => next.call(r, (p.flatten_result(), Err(e))),
+            }
+        });
+    }
+}
+
+/// A process that executes two processes sequentially, and return the value of the last process.
+
+pub struct Then<P, Q> {
+    process1: P,
+    process2: Q,
+}
+
+impl <P, Q> Process for Then<P, Q>
+    where P: Process, Q: Process
+{
+    type Value = Q::Value;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C)
+        where C: Continuation<Self::Value> + Sized {
+
+        let p2 = self.process2;
+
+        let c = move |runtime: &mut Runtime, _: P::Value| {
+            p2.call(runtime, next);
+        };
+
+        self.process1.call(runtime, c);
+    }
+}
 
-        let c = move |runtime: &mut Runtime, v: (P, P::Value)| {
-            let (p1, _) = v;
+
+impl<P, Q> ProcessMut for Then<P, Q>
+    where P: ProcessMut, Q:ProcessMut
+{
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        let p2 = self.process2;
+
+        let c = move |runtime: &mut Runtime, v: (P, P::Value)| {
+            let (p1, _) = v;
 
             let c2 = next.map(move |v: (Q, Q::Value)| {
                 let (p2, v2) = v;
@@ -296,6 +966,42 @@ impl<P, Q> ProcessMut for Then<P, Q>
 }
 
 
+/// A process that executes a Process, and returns a fixed value instead of its returned value.
+pub struct ThenReturn<P, V> {
+    process: P,
+    value: V,
+}
+
+impl<P, V> Process for ThenReturn<P, V> where P: Process, V: Send + 'static {
+    type Value = V;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let v = self.value;
+        self.process.call(runtime, move |r: &mut Runtime, _: P::Value| {
+            next.call(r, v);
+        });
+    }
+}
+
+impl<P, V> ProcessMut for ThenReturn<P, V> where P: ProcessMut, V: Clone + Send + 'static {
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        let v = self.value;
+        self.process.call_mut(runtime, move |r: &mut Runtime, (p, _): (P, P::Value)| {
+            let v2 = v.clone();
+            next.call(r, (p.then_return(v), v2));
+        });
+    }
+}
+
+/// A process that executes a process, and returns `()` instead of its value.
+pub type Discard<P> = ThenReturn<P, ()>;
+
+/// A process that executes a setup process once, and then a `ProcessMut` in infinite loop.
+pub type ThenLoop<P, B> = Then<P, While<Map<B, fn(()) -> LoopStatus<()>>>>;
+
+
 /// A process that executes two processes in parallel, and returns both values.
 pub struct Join<P, Q> {
     process1: P,
@@ -386,6 +1092,139 @@ impl<P, Q> ProcessMut for Join<P, Q>
     }
 }
 
+
+/// A process that executes two processes in parallel, and combines their values with a function.
+pub struct ZipWith<P, Q, F> {
+    process1: P,
+    process2: Q,
+    zip: F,
+}
+
+impl<P, Q, F, R> Process for ZipWith<P, Q, F>
+    where P: Process, Q: Process, P::Value: Send, Q::Value: Send,
+          F: FnOnce(P::Value, Q::Value) -> R + 'static + Send
+{
+    type Value = R;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let zip = self.zip;
+        let join = Join { process1: self.process1, process2: self.process2 };
+        join.call(runtime, next.map(move |(v1, v2)| zip(v1, v2)));
+    }
+}
+
+impl<P, Q, F, R> ProcessMut for ZipWith<P, Q, F>
+    where P: ProcessMut, Q: ProcessMut, P::Value: Send, Q::Value: Send,
+          F: FnMut(P::Value, Q::Value) -> R + 'static + Send
+{
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        let mut zip = self.zip;
+        let join = Join { process1: self.process1.get_mut(), process2: self.process2.get_mut() };
+        join.call(runtime, next.map(move |((p1, v1), (p2, v2))| {
+            let r = zip(v1, v2);
+            (ZipWith { process1: p1, process2: p2, zip }, r)
+        }));
+    }
+}
+
+/// A process that executes two processes in parallel, returns the value of the first one to
+/// terminate, and aborts the other one.
+pub struct Race<P, Q> {
+    process1: P,
+    process2: Q,
+}
+
+/// Executes `process` under the abort flag `own_flag`. When it terminates, if `next` has not been
+/// called yet, `other_flag` is raised and `next` is called with the returned value.
+fn race_branch<P, C>(runtime: &mut Runtime, process: P, next: Arc<Mutex<Option<C>>>,
+                     own_flag: Arc<AtomicBool>, other_flag: Arc<AtomicBool>)
+    where P: Process, C: Continuation<P::Value>
+{
+    let outer_flags = runtime.abort_flags.clone();
+    let mut inner_flags = outer_flags.clone();
+    inner_flags.push(own_flag);
+
+    // The process is executed inside the block, but its continuation is not.
+    runtime.with_abort_flags(inner_flags, move |r: &mut Runtime| {
+        process.call(r, move |r: &mut Runtime, v: P::Value| {
+            let next = next.lock().unwrap().take();
+            if let Some(next) = next {
+                other_flag.store(true, Ordering::SeqCst);
+                r.with_abort_flags(outer_flags, move |r: &mut Runtime| {
+                    next.call(r, v);
+                });
+            }
+        });
+    });
+}
+
+impl<P, Q> Process for Race<P, Q> where P: Process, Q: Process<Value=P::Value> {
+    type Value = P::Value;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        // The continuation is called by the first process to terminate, which aborts the other.
+        let next = Arc::new(Mutex::new(Some(next)));
+        let flag1 = Arc::new(AtomicBool::new(false));
+        let flag2 = Arc::new(AtomicBool::new(false));
+
+        race_branch(runtime, self.process1, next.clone(), flag1.clone(), flag2.clone());
+        race_branch(runtime, self.process2, next, flag2, flag1);
+    }
+}
+
+/// A process that executes a process, and returns the payload of its panic if it panics.
+pub struct CatchUnwind<P> {
+    process: P,
+}
+
+impl<P> Process for CatchUnwind<P> where P: Process, P::Value: Send {
+    type Value = Result<P::Value, Box<Any + Send>>;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        // The continuation is shared with the catching point, so that the one which takes it
+        // calls it, outside of the process. A panic raised by the continuation itself is thus not
+        // caught.
+        let next = Arc::new(Mutex::new(Some(next)));
+        let outer_flags = runtime.abort_flags.clone();
+        let outer_points = runtime.catch_points.clone();
+
+        let (next2, flags2, points2) = (next.clone(), outer_flags.clone(), outer_points.clone());
+        let c = move |r: &mut Runtime, v: P::Value| {
+            let next = next2.lock().unwrap().take();
+            if let Some(next) = next {
+                r.within(flags2, points2, move |r: &mut Runtime| next.call(r, Ok(v)));
+            }
+        };
+
+        // The catching point raises the abort flag of the process, so that its remaining
+        // continuations are discarded.
+        let aborted = Arc::new(AtomicBool::new(false));
+        let (flags2, points2) = (outer_flags.clone(), outer_points.clone());
+        let catch_point = Arc::new(CatchPoint {
+            aborted: aborted.clone(),
+            handler: Box::new(move |r: &mut Runtime, payload: Box<Any + Send>| {
+                let next = next.lock().unwrap().take();
+                match next {
+                    Some(next) => r.within(flags2.clone(), points2.clone(), move |r: &mut Runtime| {
+                        next.call(r, Err(payload))
+                    }),
+                    None => panic::resume_unwind(payload),
+                }
+            }),
+        });
+
+        let mut inner_flags = outer_flags;
+        inner_flags.push(aborted);
+        let mut inner_points = outer_points;
+        inner_points.push(catch_point);
+        let process = self.process;
+        runtime.within(inner_flags, inner_points, move |r: &mut Runtime| process.call(r, c));
+    }
+}
+
+
 /// A process that executes many processes in parallel, and returns a vector of values.
 pub struct MultiJoin<P> {
     ps: Vec<P>,
@@ -393,18 +1232,49 @@ pub struct MultiJoin<P> {
 
 /// Structure used to join a vector of processes.
 struct MultiJoinPoint<V, C> where C: Continuation<Vec<V>> {
-    remaining: Mutex<usize>,
-    value: Mutex<Vec<Option<V>>>,
+    /// Number of processes still running, plus one for the launcher.
+    remaining: AtomicUsize,
+    /// One slot for the value of each process.
+    values: Vec<Mutex<Option<V>>>,
     continuation: Mutex<Option<C>>,
 }
 
+impl<V, C> MultiJoinPoint<V, C> where C: Continuation<Vec<V>> {
+    /// Signals that a process, or the launcher, is done. The last one takes the continuation and
+    /// calls it with the values, in original order.
+    fn done(&self, runtime: &mut Runtime) {
+        // Values are stored before the decrement, so the last one sees all of them.
+        if self.remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+            let continuation = self.continuation.lock().unwrap().take().unwrap();
+            let values = self.values.iter().map(|v| { v.lock().unwrap().take().unwrap() }).collect();
+            continuation.call(runtime, values);
+        }
+    }
+}
+
 /// Creates a process that executes the processes contained in `ps` in parallel, and returns the
-/// vector of their values.
+/// vector of their values. The values are in the order of `ps`, whatever the order in which the
+/// processes finish.
 pub fn multi_join<P>(ps: Vec<P>) -> MultiJoin<P> {
     MultiJoin { ps }
 }
 
-use std::time;
+/// Like `multi_join`, but takes the processes from any iterator, so that they do not have to be
+/// collected beforehand. The vector of processes is presized with the lower bound of
+/// `size_hint`, which is the exact length for an `ExactSizeIterator`; the join point is then
+/// sized from the number of processes.
+pub fn multi_join_iter<I>(iter: I) -> MultiJoin<I::Item> where I: IntoIterator, I::Item: Process {
+    let iter = iter.into_iter();
+    let mut ps = Vec::with_capacity(iter.size_hint().0);
+    ps.extend(iter);
+    MultiJoin { ps }
+}
+
+/// Creates a process that maps each input through `f`, executes the resulting processes in
+/// parallel, and returns the vector of their values in the order of `inputs`.
+pub fn par_map<I, F, P>(inputs: Vec<I>, f: F) -> MultiJoin<P> where F: FnMut(I) -> P, P: Process {
+    multi_join_iter(inputs.into_iter().map(f))
+}
 
 /// Parallel execution of a list of processes.
 impl<P> Process for MultiJoin<P>
@@ -416,8 +1286,8 @@ impl<P> Process for MultiJoin<P>
     fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value>, C: Sized {
         // Shared data structure containing worker data.
         let join_point_original = Arc::new(MultiJoinPoint {
-            remaining: Mutex::new(self.ps.len()+1),
-            value: Mutex::new((0..self.ps.len()).map(|_| { None }).collect()),
+            remaining: AtomicUsize::new(self.ps.len()+1),
+            values: (0..self.ps.len()).map(|_| { Mutex::new(None) }).collect(),
             continuation: Mutex::new(Some(next)),
         });
 
@@ -427,79 +1297,105 @@ impl<P> Process for MultiJoin<P>
             let join_point = join_point_original.clone();
             // Create end of process continuation.
             let c = move |runtime: &mut Runtime, v: P::Value| {
-                // Check if someone is still working.
-                let ok;
-                {
-                    let mut remaining = join_point.remaining.lock().unwrap();
-                    if *remaining == 1 {
-                        ok = true;
-                    } else {
-                        ok = false;
-                    }
-                    *remaining -= 1;
-
-                    if !ok { // Free the reference as soon as possible.
-                        (*join_point.value.lock().unwrap())[i] = Some(v);
-                        return;
-                    }
-                }
-                // Here this is executed when the last process has finished.
-
-                // Wait for remaining references to be free.
-                while Arc::strong_count(&join_point) > 1 {
-                    thread::sleep(time::Duration::from_micros(1));
-                }
-
-                // Get ownership of `join_point`.
-                let join_point = match Arc::try_unwrap(join_point) {
-                    Ok(val) => val,
-                    _ => unreachable!("Process join failed."),
-                };
-
-                // Get ownership of processes values and next continuation.
-                let mut value = join_point.value.into_inner().unwrap();
-                let continuation = join_point.continuation.into_inner().unwrap().unwrap();
-                value[i] = Some(v);
-
-                // Call next continuation.
-                continuation.call(runtime, value.into_iter().map(|v| { v.unwrap() }).collect());
-
+                *join_point.values[i].lock().unwrap() = Some(v);
+                join_point.done(runtime);
             };
             runtime.on_current_instant(Box::new(move |runtime: &mut Runtime, _| {
                 p.call(runtime, c);
             }));
         };
 
-        // Maybe everything has been done so quickly that `join_point_original` is the last reference to the join point structure.
-        // Then we have to do the same work as before by getting ownership of the data and callling the next continuation.
+        // Maybe everything has been done so quickly that the launcher is the last one, then it
+        // calls the next continuation.
+        join_point_original.done(runtime);
+    }
+}
 
-        let ok;
-        {
-            let mut remaining = join_point_original.remaining.lock().unwrap();
-            if *remaining == 1 {
-                ok = true;
-            } else {
-                ok = false;
-            }
-            *remaining -= 1;
+/// A process that executes a vector of processes in parallel, and folds their values as they
+/// arrive.
+pub struct MultiJoinCollectWith<P, A, F> {
+    ps: Vec<P>,
+    init: A,
+    fold: F,
+}
+
+/// Structure used to fold the values of a vector of processes.
+struct MultiJoinFoldPoint<A, F, C> where C: Continuation<A> {
+    /// Number of processes still running, plus one for the launcher.
+    remaining: AtomicUsize,
+    /// Accumulator, shared by the workers on which the processes finish.
+    acc: Mutex<Option<A>>,
+    fold: F,
+    continuation: Mutex<Option<C>>,
+}
+
+impl<A, F, C> MultiJoinFoldPoint<A, F, C> where C: Continuation<A> {
+    /// Signals that a process, or the launcher, is done. The last one takes the continuation and
+    /// calls it with the accumulator.
+    fn done(&self, runtime: &mut Runtime) {
+        if self.remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+            let continuation = self.continuation.lock().unwrap().take().unwrap();
+            let acc = self.acc.lock().unwrap().take().unwrap();
+            continuation.call(runtime, acc);
         }
+    }
+}
 
-        if ok {
-            // Wait for remaining references to be free.
-            while Arc::strong_count(&join_point_original) > 1 {
-                thread::sleep(time::Duration::from_micros(1));
-            }
+/// Creates a process that executes the processes contained in `ps` in parallel, and folds their
+/// values with `fold`, starting from `init`. `fold` is called as soon as a process finishes, with
+/// the index of the process in `ps` and its value.
+pub fn multi_join_collect_with<P, A, F>(ps: Vec<P>, init: A, fold: F) -> MultiJoinCollectWith<P, A, F>
+    where P: Process, F: Fn(A, usize, P::Value) -> A
+{
+    MultiJoinCollectWith { ps, init, fold }
+}
 
+/// A process that executes a vector of processes in parallel, and returns their values with their
+/// indices, in the order in which they finished.
+pub type MultiJoinIndexed<P> = MultiJoinCollectWith<P, Vec<(usize, <P as Process>::Value)>,
+    fn(Vec<(usize, <P as Process>::Value)>, usize, <P as Process>::Value) -> Vec<(usize, <P as Process>::Value)>>;
 
-            let join_point_original = match Arc::try_unwrap(join_point_original) {
-                Ok(val) => val,
-                _ => unreachable! ("Process join failed."),
-            };
+/// Creates a process that executes the processes contained in `ps` in parallel, and returns the
+/// vector of their values with their indices in `ps`, in the order in which they finished.
+pub fn multi_join_indexed<P>(ps: Vec<P>) -> MultiJoinIndexed<P> where P: Process {
+    fn push<V>(mut acc: Vec<(usize, V)>, i: usize, v: V) -> Vec<(usize, V)> {
+        acc.push((i, v));
+        acc
+    }
+    let n = ps.len();
+    multi_join_collect_with(ps, Vec::with_capacity(n), push)
+}
+
+impl<P, A, F> Process for MultiJoinCollectWith<P, A, F>
+    where P: Process, P::Value: Send, A: Send + 'static, F: Fn(A, usize, P::Value) -> A + Send + Sync + 'static
+{
+    type Value = A;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let join_point_original = Arc::new(MultiJoinFoldPoint {
+            remaining: AtomicUsize::new(self.ps.len()+1),
+            acc: Mutex::new(Some(self.init)),
+            fold: self.fold,
+            continuation: Mutex::new(Some(next)),
+        });
 
-            let value = join_point_original.value.into_inner().unwrap();
-            let continuation = join_point_original.continuation.into_inner().unwrap().unwrap();
-            continuation.call(runtime, value.into_iter().map(| v | { v.unwrap() }).collect());
+        for (i, p) in self.ps.into_iter().enumerate() {
+            let join_point = join_point_original.clone();
+            let c = move |runtime: &mut Runtime, v: P::Value| {
+                {
+                    // The processes may finish on different workers, the accumulator is locked.
+                    let mut acc = join_point.acc.lock().unwrap();
+                    let a = acc.take().unwrap();
+                    *acc = Some((join_point.fold)(a, i, v));
+                }
+                join_point.done(runtime);
+            };
+            runtime.on_current_instant(Box::new(move |runtime: &mut Runtime, _| {
+                p.call(runtime, c);
+            }));
         }
+
+        join_point_original.done(runtime);
     }
 }
 
@@ -521,6 +1417,193 @@ impl<P> ProcessMut for MultiJoin<P>
     }
 }
 
+
+/// A process that executes processes in parallel like `MultiJoin`, while accepting new processes
+/// through a `DynamicJoinHandle` during its execution. The added processes are launched at the
+/// instant following their addition.
+pub struct DynamicMultiJoin<P> {
+    ps: Vec<P>,
+    pending: Arc<Mutex<DynamicPending<P>>>,
+}
+
+/// Handle to add processes to a running `DynamicMultiJoin`. It can be cloned, and moved to the
+/// processes of the join themselves.
+pub struct DynamicJoinHandle<P> {
+    pending: Arc<Mutex<DynamicPending<P>>>,
+}
+
+impl<P> Clone for DynamicJoinHandle<P> {
+    fn clone(&self) -> Self {
+        DynamicJoinHandle { pending: self.pending.clone() }
+    }
+}
+
+impl<P> DynamicJoinHandle<P> {
+    /// Adds a process to the join. It is launched at the next instant, unless the join has already
+    /// returned, in which case it is never executed. If the join has not started yet, it is
+    /// launched with the initial processes.
+    pub fn add(&self, runtime: &mut Runtime, p: P) {
+        let join = {
+            let mut pending = self.pending.lock().unwrap();
+            pending.processes.push(p);
+            pending.join.as_ref().and_then(|join| join.upgrade())
+        };
+        if let Some(join) = join {
+            join.arm(runtime);
+        }
+    }
+}
+
+/// Creates a process that executes the processes of `ps` in parallel, and a handle to add more
+/// processes to it while it runs. The process returns the vector of the values of all the
+/// processes, in the order in which they were added, at the instant following the first one where
+/// all of them have finished and no process is pending.
+pub fn dynamic_multi_join<P>(ps: Vec<P>) -> (DynamicMultiJoin<P>, DynamicJoinHandle<P>) {
+    let pending = Arc::new(Mutex::new(DynamicPending { processes: vec!(), join: None }));
+    (DynamicMultiJoin { ps, pending: pending.clone() }, DynamicJoinHandle { pending })
+}
+
+/// Processes added to a `DynamicMultiJoin`, waiting to be launched.
+struct DynamicPending<P> {
+    processes: Vec<P>,
+    /// Join point of the join, once it runs and until it returns.
+    join: Option<Weak<DynamicJoinArm>>,
+}
+
+/// Join point of a running `DynamicMultiJoin`, seen from its handles.
+trait DynamicJoinArm: Send + Sync {
+    /// Schedules a drain of the join at the end of the instant, unless one is already scheduled.
+    fn arm(self: Arc<Self>, runtime: &mut Runtime);
+}
+
+/// Structure used to join a growing vector of processes.
+struct DynamicJoinPoint<P, C> where P: Process, C: Continuation<Vec<P::Value>> {
+    /// Number of processes still running.
+    remaining: AtomicUsize,
+    /// One slot for the value of each process launched so far.
+    values: Mutex<Vec<Option<P::Value>>>,
+    continuation: Mutex<Option<C>>,
+    pending: Arc<Mutex<DynamicPending<P>>>,
+    /// Indicates if a drain is scheduled at the end of the instant.
+    armed: AtomicBool,
+    /// Abort flags of the `do_until` blocks enclosing the join.
+    abort_flags: Vec<Arc<AtomicBool>>,
+    /// Catching points of the `catch_unwind` processes enclosing the join.
+    catch_points: Vec<Arc<CatchPoint>>,
+}
+
+impl<P, C> DynamicJoinPoint<P, C>
+    where P: Process, P::Value: Send, C: Continuation<Vec<P::Value>>
+{
+    /// Launches `p` at the current instant, storing its value in a new slot.
+    fn launch(join_point: &Arc<Self>, runtime: &mut Runtime, p: P) {
+        let i = {
+            let mut values = join_point.values.lock().unwrap();
+            values.push(None);
+            values.len() - 1
+        };
+        join_point.remaining.fetch_add(1, Ordering::AcqRel);
+
+        let join_point = join_point.clone();
+        let c = move |r: &mut Runtime, v: P::Value| {
+            join_point.values.lock().unwrap()[i] = Some(v);
+            // The last process to finish checks whether the join returns.
+            if join_point.remaining.fetch_sub(1, Ordering::AcqRel) == 1 {
+                join_point.arm(r);
+            }
+        };
+        runtime.on_current_instant(Box::new(move |runtime: &mut Runtime, _| {
+            p.call(runtime, c);
+        }));
+    }
+
+    /// At the end of the instant, launches the pending processes, or returns if every process has
+    /// finished. The drain is only scheduled when a process is added or when the last running
+    /// one finishes, so a join whose processes are blocked lets the runtime detect a deadlock.
+    fn drain(join_point: Arc<Self>, runtime: &mut Runtime) {
+        let (ps, done) = {
+            let mut pending = join_point.pending.lock().unwrap();
+            if pending.join.is_none() {
+                // The join already returned.
+                return;
+            }
+            join_point.armed.store(false, Ordering::SeqCst);
+            let done = pending.processes.is_empty()
+                && join_point.remaining.load(Ordering::Acquire) == 0;
+            if done {
+                // The processes added from now on are never executed.
+                pending.join = None;
+            }
+            (pending.processes.drain(..).collect::<Vec<_>>(), done)
+        };
+
+        if done {
+            let continuation = join_point.continuation.lock().unwrap().take().unwrap();
+            let values = join_point.values.lock().unwrap().drain(..).map(|v| v.unwrap()).collect();
+            runtime.on_current_instant(Box::new(move |r: &mut Runtime, ()| {
+                continuation.call(r, values);
+            }));
+        } else {
+            // Called at the end of the instant, the processes start at the next one.
+            for p in ps {
+                DynamicJoinPoint::launch(&join_point, runtime, p);
+            }
+        }
+    }
+}
+
+impl<P, C> DynamicJoinArm for DynamicJoinPoint<P, C>
+    where P: Process, P::Value: Send, C: Continuation<Vec<P::Value>>
+{
+    fn arm(self: Arc<Self>, runtime: &mut Runtime) {
+        if self.armed.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        // The drain belongs to the join, whichever process triggers it.
+        let abort_flags = self.abort_flags.clone();
+        let catch_points = self.catch_points.clone();
+        runtime.within(abort_flags, catch_points, move |r: &mut Runtime| {
+            r.on_end_of_instant(Box::new(move |r: &mut Runtime, ()| {
+                DynamicJoinPoint::drain(self, r);
+            }));
+        });
+    }
+}
+
+impl<P> Process for DynamicMultiJoin<P>
+    where P: Process, P::Value: Send
+{
+    type Value = Vec<P::Value>;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let join_point = Arc::new(DynamicJoinPoint {
+            remaining: AtomicUsize::new(0),
+            values: Mutex::new(Vec::with_capacity(self.ps.len())),
+            continuation: Mutex::new(Some(next)),
+            pending: self.pending.clone(),
+            armed: AtomicBool::new(false),
+            abort_flags: runtime.abort_flags.clone(),
+            catch_points: runtime.catch_points.clone(),
+        });
+
+        // The processes added before the start are launched at the next instant, and a join
+        // without any process returns then.
+        let added = {
+            let mut pending = self.pending.lock().unwrap();
+            let join: Arc<DynamicJoinArm> = join_point.clone();
+            pending.join = Some(Arc::downgrade(&join));
+            !pending.processes.is_empty()
+        };
+        if added || self.ps.is_empty() {
+            join_point.clone().arm(runtime);
+        }
+
+        for p in self.ps {
+            DynamicJoinPoint::launch(&join_point, runtime, p);
+        }
+    }
+}
+
 /// A process that executes `q1` or `q2` depending on `condition` result.
 pub struct ThenElse<P, Q1, Q2> {
     condition: P,
@@ -566,6 +1649,52 @@ impl<P, Q1, Q2> ProcessMut for ThenElse<P, Q1, Q2>
     }
 }
 
+/// A process that executes a process or another depending on a condition, and returns the value
+/// of either of them.
+pub struct Branch<P, Q1, Q2> {
+    condition: P,
+    q1: Q1,
+    q2: Q2,
+}
+
+impl<P, Q1, Q2> Process for Branch<P, Q1, Q2>
+    where P: Process<Value=bool>, Q1: Process, Q2: Process
+{
+    type Value = Either<Q1::Value, Q2::Value>;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let q1 = self.q1;
+        let q2 = self.q2;
+        self.condition.call(runtime, move |r: &mut Runtime, v: bool| {
+            if v {
+                q1.call(r, next.map(Either::Left));
+            } else {
+                q2.call(r, next.map(Either::Right));
+            }
+        });
+    }
+}
+
+impl<P, Q1, Q2> ProcessMut for Branch<P, Q1, Q2>
+    where P: ProcessMut<Value=bool>, Q1: ProcessMut, Q2: ProcessMut
+{
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C) where Self: Sized, C: Continuation<(Self, Self::Value)> {
+        let q1 = self.q1;
+        let q2 = self.q2;
+        self.condition.call_mut(runtime, move |r: &mut Runtime, (p, v): (P, bool)| {
+            if v {
+                q1.call_mut(r, move |r: &mut Runtime, (q1, v)| {
+                    next.call(r, (p.branch(q1, q2), Either::Left(v)));
+                });
+            } else {
+                q2.call_mut(r, move |r: &mut Runtime, (q2, v)| {
+                    next.call(r, (p.branch(q1, q2), Either::Right(v)));
+                });
+            }
+        });
+    }
+}
+
 /// A process that executes a ProcessMut once and returns it with the obtained value.
 pub struct Mut<P> {
     process: P,
@@ -589,6 +1718,13 @@ pub enum LoopStatus<V> {
 impl<V> Copy for LoopStatus<V> where V: Copy {}
 
 
+/// A value of one type or another, e.g. returned by the branches of `branch`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Either<L, R> {
+    Left(L), Right(R)
+}
+
+
 /// A process that build a while loop around a `ProcessMut` with return type `LoopStatus`.
 pub struct While<P> {
     process: P,
@@ -620,3 +1756,314 @@ impl<P, V> ProcessMut for While<P> where P: ProcessMut, P: Process<Value=LoopSta
         });
     }
 }
+
+
+/// A process that folds the values of a `ProcessMut` with return type `Option` until it returns
+/// `None`.
+pub struct Fold<P, A, F> {
+    process: P,
+    init: A,
+    acc: A,
+    fold: F,
+}
+
+impl<P, A, F, T> Process for Fold<P, A, F>
+    where P: ProcessMut, P: Process<Value=Option<T>>, F: FnMut(A, T) -> A + 'static + Send,
+          A: Clone + 'static + Send
+{
+    type Value = A;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let Fold { process, init, acc, mut fold } = self;
+        process.call_mut(runtime, move |runtime: &mut Runtime, (p, v): (P, P::Value)| {
+            match v {
+                Some(v) => {
+                    let acc = fold(acc, v);
+                    Fold { process: p, init, acc, fold }.call(runtime, next)
+                },
+                None => next.call(runtime, acc),
+            }
+        });
+    }
+}
+
+impl<P, A, F, T> ProcessMut for Fold<P, A, F>
+    where P: ProcessMut, P: Process<Value=Option<T>>, F: FnMut(A, T) -> A + 'static + Send,
+          A: Clone + 'static + Send
+{
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        let Fold { process, init, acc, mut fold } = self;
+        process.call_mut(runtime, move |runtime: &mut Runtime, (p, v): (P, P::Value)| {
+            match v {
+                Some(v) => {
+                    let acc = fold(acc, v);
+                    Fold { process: p, init, acc, fold }.call_mut(runtime, next)
+                },
+                None => {
+                    // The next execution starts again from the initial accumulator.
+                    let acc2 = init.clone();
+                    next.call(runtime, (Fold { process: p, init, acc: acc2, fold }, acc))
+                },
+            }
+        });
+    }
+}
+
+
+/// A process that executes a `ProcessMut` a fixed number of times, and collects its values.
+pub struct Take<P> {
+    process: P,
+    n: usize,
+}
+
+/// Executes `process` `remaining` more times, pushing its values to `values`, then calls `next`
+/// with the process and the values.
+fn take_loop<P, C>(process: P, runtime: &mut Runtime, remaining: usize, mut values: Vec<P::Value>, next: C)
+    where P: ProcessMut, P::Value: Send, C: Continuation<(P, Vec<P::Value>)>
+{
+    if remaining == 0 {
+        next.call(runtime, (process, values));
+    } else {
+        process.call_mut(runtime, move |runtime: &mut Runtime, (p, v): (P, P::Value)| {
+            values.push(v);
+            take_loop(p, runtime, remaining - 1, values, next);
+        });
+    }
+}
+
+impl<P> Process for Take<P> where P: ProcessMut, P::Value: Send {
+    type Value = Vec<P::Value>;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let values = Vec::with_capacity(self.n);
+        take_loop(self.process, runtime, self.n, values, next.map(|(_, values)| values));
+    }
+}
+
+impl<P> ProcessMut for Take<P> where P: ProcessMut, P::Value: Send {
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        let n = self.n;
+        let values = Vec::with_capacity(n);
+        take_loop(self.process, runtime, n, values, next.map(move |(process, values)| {
+            (Take { process, n }, values)
+        }));
+    }
+}
+
+/// A process that executes a `ProcessMut` while a predicate holds on its values.
+pub struct Until<P, F> {
+    process: P,
+    pred: F,
+}
+
+impl<P, F> Process for Until<P, F>
+    where P: ProcessMut, F: FnMut(&P::Value) -> bool + 'static + Send
+{
+    type Value = P::Value;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let mut pred = self.pred;
+        self.process.call_mut(runtime, move |runtime: &mut Runtime, (p, v): (P, P::Value)| {
+            if pred(&v) {
+                p.until(pred).call(runtime, next);
+            } else {
+                next.call(runtime, v);
+            }
+        });
+    }
+}
+
+impl<P, F> ProcessMut for Until<P, F>
+    where P: ProcessMut, F: FnMut(&P::Value) -> bool + 'static + Send
+{
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        let mut pred = self.pred;
+        self.process.call_mut(runtime, move |runtime: &mut Runtime, (p, v): (P, P::Value)| {
+            if pred(&v) {
+                p.until(pred).call_mut(runtime, next);
+            } else {
+                next.call(runtime, (p.until(pred), v));
+            }
+        });
+    }
+}
+
+/// A process that executes a `ProcessMut` until it succeeds, or a number of attempts is reached.
+pub struct Retry<P> {
+    process: P,
+    max_attempts: usize,
+}
+
+/// Executes `process` until it returns `Ok`, or `attempts` reaches `max_attempts`, and calls `next`
+/// with the process and its last result.
+fn retry_loop<P, T, E, C>(process: P, runtime: &mut Runtime, attempts: usize, max_attempts: usize, next: C)
+    where P: ProcessMut + Process<Value=Result<T, E>>, C: Continuation<(P, Result<T, E>)>
+{
+    process.call_mut(runtime, move |runtime: &mut Runtime, (p, v): (P, Result<T, E>)| {
+        if v.is_err() && attempts + 1 < max_attempts {
+            retry_loop(p, runtime, attempts + 1, max_attempts, next);
+        } else {
+            next.call(runtime, (p, v));
+        }
+    });
+}
+
+impl<P, T, E> Process for Retry<P> where P: ProcessMut + Process<Value=Result<T, E>> {
+    type Value = Result<T, E>;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        retry_loop(self.process, runtime, 0, self.max_attempts, next.map(|(_, v)| v));
+    }
+}
+
+impl<P, T, E> ProcessMut for Retry<P> where P: ProcessMut + Process<Value=Result<T, E>> {
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        let max_attempts = self.max_attempts;
+        retry_loop(self.process, runtime, 0, max_attempts, next.map(move |(process, v)| {
+            (Retry { process, max_attempts }, v)
+        }));
+    }
+}
+
+/// A process that applies a fallible function to the output of a Process, and emits the `Ok` and
+/// `Err` values on two distinct signals.
+pub struct MapResultIntoSignal<P, F, S1, S2> where S1: Signal, S2: Signal {
+    process: P,
+    map: F,
+    ok_signal: SignalRuntimeRef<S1::VR>,
+    err_signal: SignalRuntimeRef<S2::VR>,
+}
+
+impl<P, F, T, E, S1, S2> Process for MapResultIntoSignal<P, F, S1, S2>
+    where P: Process, F: FnOnce(P::Value) -> Result<T, E> + 'static + Send,
+          S1: Signal + 'static, S2: Signal + 'static, T: Clone, E: Clone,
+          S1::VR: ValueRuntime<V1=T>, S2::VR: ValueRuntime<V1=E>
+{
+    type Value = Result<T, E>;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let map = self.map;
+        let ok_signal = self.ok_signal;
+        let err_signal = self.err_signal;
+        self.process.call(runtime, move |r: &mut Runtime, v: P::Value| {
+            let result = map(v);
+            match result {
+                Ok(ref v) => ok_signal.emit(r, v.clone()),
+                Err(ref e) => err_signal.emit(r, e.clone()),
+            }
+            next.call(r, result);
+        });
+    }
+}
+
+impl<P, F, T, E, S1, S2> ProcessMut for MapResultIntoSignal<P, F, S1, S2>
+    where P: ProcessMut, F: FnMut(P::Value) -> Result<T, E> + 'static + Send,
+          S1: Signal + 'static, S2: Signal + 'static, T: Clone, E: Clone,
+          S1::VR: ValueRuntime<V1=T>, S2::VR: ValueRuntime<V1=E>
+{
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        let mut map = self.map;
+        let ok_signal = self.ok_signal;
+        let err_signal = self.err_signal;
+        self.process.call_mut(runtime, move |r: &mut Runtime, (p, v): (P, P::Value)| {
+            let result = map(v);
+            match result {
+                Ok(ref v) => ok_signal.emit(r, v.clone()),
+                Err(ref e) => err_signal.emit(r, e.clone()),
+            }
+            let process = MapResultIntoSignal { process: p, map, ok_signal, err_signal };
+            next.call(r, (process, result));
+        });
+    }
+}
+
+
+/// An arm of `select_map`: a signal and the mapping to apply if it is selected.
+pub struct SelectArm<T, V> {
+    on_signal: Box<Fn(&mut Runtime, &Arc<AtomicBool>, Box<Continuation<()>>) + Send>,
+    map: Box<FnMut(T) -> V + Send>,
+}
+
+/// Creates an arm of `select_map`, applying `map` if `signal` is the first emitted signal.
+/// Only the presence of the signal is used.
+pub fn select_arm<S, T, V, F>(signal: &S, map: F) -> SelectArm<T, V>
+    where S: Signal + 'static, F: FnMut(T) -> V + 'static + Send
+{
+    let signal = signal.runtime();
+    SelectArm {
+        on_signal: Box::new(move |r: &mut Runtime, cancelled: &Arc<AtomicBool>, c: Box<Continuation<()>>| {
+            signal.on_signal_cancellable(r, cancelled, move |r: &mut Runtime, ()| c.call_box(r, ()));
+        }),
+        map: Box::new(map),
+    }
+}
+
+/// A process that maps the output of a process depending on the first emitted signal.
+pub struct SelectMap<P, V> where P: Process {
+    process: P,
+    arms: Vec<SelectArm<P::Value, V>>,
+}
+
+/// Structure shared by the arms of a running `SelectMap`.
+struct SelectPoint<T, V, C> {
+    /// Lowest index of the arms whose signal was emitted in the instant, if any.
+    fired: Option<usize>,
+    /// Mappings of the arms, the value to map and the continuation, taken when the process
+    /// completes.
+    selection: Option<(Vec<Box<FnMut(T) -> V + Send>>, T, C)>,
+}
+
+impl<P, V> Process for SelectMap<P, V> where P: Process, P::Value: Send + 'static, V: 'static {
+    type Value = V;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let arms = self.arms;
+        self.process.call(runtime, move |r: &mut Runtime, v: P::Value| {
+            let (registrations, maps): (Vec<_>, Vec<_>) =
+                arms.into_iter().map(|arm| (arm.on_signal, arm.map)).unzip();
+            let point = Arc::new(Mutex::new(SelectPoint { fired: None, selection: Some((maps, v, next)) }));
+            // Set once an arm is selected, so that the signals drop the registrations of the
+            // other arms.
+            let completed = Arc::new(AtomicBool::new(false));
+
+            for (i, on_signal) in registrations.into_iter().enumerate() {
+                let point = point.clone();
+                let completed2 = completed.clone();
+                on_signal(r, &completed, Box::new(move |r: &mut Runtime, ()| {
+                    let mut p = point.lock().unwrap();
+                    match p.fired {
+                        // An arm was already selected in a previous instant.
+                        _ if p.selection.is_none() => {},
+                        Some(j) => p.fired = Some(usize::min(i, j)),
+                        None => {
+                            // The selected arm is only known once all the emissions of the
+                            // instant are done, hence at the end of the instant.
+                            p.fired = Some(i);
+                            let point = point.clone();
+                            r.on_end_of_instant(Box::new(move |r: &mut Runtime, ()| {
+                                completed2.store(true, Ordering::SeqCst);
+                                let (i, (mut maps, v, next)) = {
+                                    let mut p = point.lock().unwrap();
+                                    (p.fired.unwrap(), p.selection.take().unwrap())
+                                };
+                                r.on_current_instant(Box::new(move |r: &mut Runtime, ()| {
+                                    next.call(r, (maps[i])(v));
+                                }));
+                            }));
+                        }
+                    }
+                }));
+            }
+        });
+    }
+}
@@ -0,0 +1,185 @@
+//! A module for MPSC signals counting and bounding their emissions.
+use super::*;
+use std::sync::Mutex;
+use std::sync::atomic::AtomicUsize;
+
+
+/*
+        Bounded MPSC Signal
+    The Bounded MPSC Signal is a MPSC signal which counts its emissions in the current instant.
+    It can be given a cap: once the cap is reached, the extra emissions of the instant are not
+    gathered, but passed to an overflow handler. This helps catching producers which emit more
+    often than expected. The signal is still present when all the emissions overflow.
+    Like `mpsc_signal::new`, the init functions return two different parts:
+    - BoundedMPSCSignalSender:      implements SEmit.
+    - BoundedMPSCSignalReceiver:    implements SAwaitInConsume.
+*/
+
+/// Value Runtime for Bounded MPSC Signals.
+pub struct BoundedMPSCSignalValueRuntime<V1, V2> {
+    waiting_in: Mutex<Option<Box<Continuation<V2>>>>,
+    value: Mutex<Option<V2>>,
+    gather: Box<(Fn(V1, V2) -> V2) + Send + Sync>,
+    count: AtomicUsize,
+    cap: Option<usize>,
+    overflow: Box<Fn(V1) + Send + Sync>,
+}
+
+impl<V1, V2> BoundedMPSCSignalValueRuntime<V1, V2> {
+    /// Returns the number of emissions in the current instant, overflowing ones included.
+    pub fn emission_count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+}
+
+impl<V1, V2> ValueRuntime for BoundedMPSCSignalValueRuntime<V1, V2>
+    where V1: Send + Sync, V2: Default + 'static + Send + Sync {
+    type V1 = V1;
+    type V2 = V2;
+    type V3 = V1;
+
+    fn emit(&self, _runtime: &mut Runtime, v: Self::V1) {
+        let count = self.count.fetch_add(1, Ordering::SeqCst);
+        if self.cap.map_or(false, |cap| count >= cap) {
+            (self.overflow)(v);
+            return;
+        }
+
+        let mut opt_v2 = self.value.lock().unwrap();
+        let v2 = unpack_mutex(&mut opt_v2);
+        *opt_v2 = Some((self.gather)(v, v2));
+    }
+
+    fn await_in<C>(&self, _runtime: &mut Runtime, c:C) where C: Continuation<Self::V2> {
+        *self.waiting_in.lock().unwrap() = Some(Box::new(c));
+    }
+
+    fn release_await_in(&self, runtime: &mut Runtime) {
+        let mut waiting_in = self.waiting_in.lock().unwrap();
+        let mut opt_value = self.value.lock().unwrap();
+        let value = unpack_mutex(&mut opt_value);
+        let mut empty = None;
+        swap(&mut empty, &mut *waiting_in);
+
+        if let Some(c) = empty {
+            runtime.on_current_instant(Box::new(move |r: &mut Runtime, ()| {
+                c.call_box(r, value);
+            }));
+        }
+
+        // Finally, we reset the signal value and the emission counter.
+        *opt_value = Some(V2::default());
+        self.count.store(0, Ordering::SeqCst);
+    }
+
+    fn get(&self) -> Self::V3 {
+        unreachable!()
+    }
+
+    fn reset(&self) {
+        *self.waiting_in.lock().unwrap() = None;
+        *self.value.lock().unwrap() = Some(V2::default());
+        self.count.store(0, Ordering::SeqCst);
+    }
+}
+
+
+#[derive(Clone)]
+/// Sender part for Bounded MPSC, which is Clone.
+pub struct BoundedMPSCSignalSender<V1, V2>
+    where V1: Send + Sync, V2: Default + 'static + Send + Sync
+{
+    signal: SignalRuntimeRef<BoundedMPSCSignalValueRuntime<V1, V2>>,
+}
+
+/// Receiver part for Bounded MPSC, which is not Clone.
+pub struct BoundedMPSCSignalReceiver<V1, V2>
+    where V1: Send + Sync, V2: Default + 'static + Send + Sync
+{
+    signal: SignalRuntimeRef<BoundedMPSCSignalValueRuntime<V1, V2>>,
+}
+
+impl<V1, V2> BoundedMPSCSignalSender<V1, V2>
+    where V1: Send + Sync, V2: Default + 'static + Send + Sync
+{
+    /// Returns the number of emissions in the current instant, overflowing ones included.
+    pub fn emission_count(&self) -> usize {
+        self.signal.runtime.value_runtime().emission_count()
+    }
+}
+
+impl<V1, V2> BoundedMPSCSignalReceiver<V1, V2>
+    where V1: Send + Sync, V2: Default + 'static + Send + Sync
+{
+    /// Returns the number of emissions in the current instant, overflowing ones included.
+    pub fn emission_count(&self) -> usize {
+        self.signal.runtime.value_runtime().emission_count()
+    }
+}
+
+impl<V1, V2> Signal for BoundedMPSCSignalSender<V1, V2>
+    where V1: 'static + Send + Sync, V2: 'static + Default + Send + Sync
+{
+    type VR = BoundedMPSCSignalValueRuntime<V1, V2>;
+
+    fn runtime(&self) -> SignalRuntimeRef<Self::VR> {
+        self.signal.clone()
+    }
+}
+
+impl<V1, V2> Signal for BoundedMPSCSignalReceiver<V1, V2>
+    where V1: 'static + Send + Sync, V2: 'static + Default + Send + Sync
+{
+    type VR = BoundedMPSCSignalValueRuntime<V1, V2>;
+
+    fn runtime(&self) -> SignalRuntimeRef<Self::VR> {
+        self.signal.clone()
+    }
+}
+
+impl<V1, V2> SEmit for BoundedMPSCSignalSender<V1, V2>
+    where V1: 'static + Send + Sync, V2: 'static + Default + Send + Sync {}
+
+impl<V1, V2> SAwaitInConsume for BoundedMPSCSignalReceiver<V1, V2>
+    where V1: 'static + Send + Sync, V2: 'static + Default + Send + Sync {}
+
+
+/// Creates a new Bounded MPSC Signal without cap, which only counts its emissions.
+/// Returns Sender and Receiver parts.
+pub fn new<V1, V2, F>(gather: F) -> (BoundedMPSCSignalSender<V1, V2>, BoundedMPSCSignalReceiver<V1, V2>)
+    where V1: 'static + Send + Sync, V2: Default + Send + Sync,
+          F: Fn(V1, V2) -> V2 + 'static, F: Send + Sync
+{
+    create(gather, None, |_| {})
+}
+
+/// Creates a new Bounded MPSC Signal gathering at most `cap` emissions per instant. The extra
+/// emissions are passed to `overflow`. Returns Sender and Receiver parts.
+pub fn with_cap<V1, V2, F, O>(gather: F, cap: usize, overflow: O)
+    -> (BoundedMPSCSignalSender<V1, V2>, BoundedMPSCSignalReceiver<V1, V2>)
+    where V1: 'static + Send + Sync, V2: Default + Send + Sync,
+          F: Fn(V1, V2) -> V2 + 'static, F: Send + Sync,
+          O: Fn(V1) + 'static, O: Send + Sync
+{
+    create(gather, Some(cap), overflow)
+}
+
+/// Creates a new Bounded MPSC Signal with an optional cap.
+fn create<V1, V2, F, O>(gather: F, cap: Option<usize>, overflow: O)
+    -> (BoundedMPSCSignalSender<V1, V2>, BoundedMPSCSignalReceiver<V1, V2>)
+    where V1: 'static + Send + Sync, V2: Default + Send + Sync,
+          F: Fn(V1, V2) -> V2 + 'static, F: Send + Sync,
+          O: Fn(V1) + 'static, O: Send + Sync
+{
+    let value_runtime = BoundedMPSCSignalValueRuntime {
+        waiting_in: Mutex::new(None),
+        value: Mutex::new(Some(V2::default())),
+        gather: Box::new(gather),
+        count: AtomicUsize::new(0),
+        cap,
+        overflow: Box::new(overflow),
+    };
+    let runtime_ref = SignalRuntimeRef::new(value_runtime);
+    (BoundedMPSCSignalSender { signal: runtime_ref.clone() },
+     BoundedMPSCSignalReceiver { signal : runtime_ref })
+}
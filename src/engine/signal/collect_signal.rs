@@ -0,0 +1,95 @@
+//! A module for signals collecting all their emitted values.
+
+use super::*;
+use std::mem;
+use std::sync::Mutex;
+
+/*
+        Collect Signal
+    The Collect Signal is a MPMC (Multiple Producer, Multiple Consumer) signal which keeps every
+    emitted value of an instant, instead of gathering them into one value.
+    It implements SEmit and SAwaitIn.
+
+    The values are collected in emission order: the order in which the `emit` calls were executed
+    during the instant. When several workers emit concurrently, this order depends on the
+    scheduling, but emissions made sequentially by a same process keep their order.
+    The collected vector is reset to empty at the end of each instant.
+*/
+
+/// Value Runtime for CollectSignal.
+pub struct CollectSignalValueRuntime<V> {
+    waiting_in: Mutex<Vec<Box<Continuation<Vec<V>>>>>,
+    values: Mutex<Vec<V>>,
+}
+
+impl<V> ValueRuntime for CollectSignalValueRuntime<V> where V: Clone + 'static + Send + Sync {
+    type V1 = V;
+    type V2 = Vec<V>;
+    type V3 = V;
+
+    fn emit(&self, _runtime: &mut Runtime, v: Self::V1) {
+        self.values.lock().unwrap().push(v);
+    }
+
+    fn await_in<C>(&self, _runtime: &mut Runtime, c:C) where C: Continuation<Self::V2> {
+        self.waiting_in.lock().unwrap().push(Box::new(c));
+    }
+
+    fn release_await_in(&self, runtime: &mut Runtime) {
+        let mut waiting_in = self.waiting_in.lock().unwrap();
+        // This also resets the value of the signal.
+        let values = mem::replace(&mut *self.values.lock().unwrap(), vec!());
+
+        while let Some(cont) = waiting_in.pop() {
+            let v = values.clone();
+            runtime.on_current_instant(Box::new(move |r: &mut Runtime, ()| {
+                cont.call_box(r, v);
+            }));
+        }
+    }
+
+    fn get(&self) -> Self::V3 {
+        unreachable!()
+    }
+
+    fn reset(&self) {
+        self.waiting_in.lock().unwrap().clear();
+        self.values.lock().unwrap().clear();
+    }
+}
+
+
+#[derive(Clone)]
+/// MPMC signal collecting all the values emitted during an instant.
+pub struct CollectSignal<V> where V: 'static + Clone + Send + Sync {
+    signal: SignalRuntimeRef<CollectSignalValueRuntime<V>>,
+}
+
+impl<V> CollectSignal<V> where V: 'static + Clone + Send + Sync {
+    /// Creates a new Collect Signal.
+    pub fn new() -> Self {
+        let value_runtime = CollectSignalValueRuntime {
+            waiting_in: Mutex::new(vec!()),
+            values: Mutex::new(vec!()),
+        };
+
+        CollectSignal { signal: SignalRuntimeRef::new(value_runtime) }
+    }
+}
+
+impl<V> Signal for CollectSignal<V> where V: 'static + Clone + Send + Sync {
+    type VR = CollectSignalValueRuntime<V>;
+
+    fn runtime(&self) -> SignalRuntimeRef<Self::VR> {
+        self.signal.clone()
+    }
+}
+
+impl<V> SEmit for CollectSignal<V> where V: 'static + Clone + Send + Sync {}
+impl<V> SAwaitIn for CollectSignal<V> where V: 'static + Clone + Send + Sync {}
+
+
+/// Creates a new Collect Signal.
+pub fn new<V>() -> CollectSignal<V> where V: 'static + Clone + Send + Sync {
+    CollectSignal::new()
+}
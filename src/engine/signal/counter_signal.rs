@@ -0,0 +1,18 @@
+//! A module for signals counting their emissions.
+
+use super::value_signal::{self, ValueSignal};
+
+/*
+        Counter Signal
+    The Counter Signal is a Value Signal whose emissions carry no value, and whose value is the
+    number of emissions in the instant. As any Value Signal, its value is reset to 0 at the end
+    of each instant.
+*/
+
+/// MPMC signal counting its emissions in each instant.
+pub type CounterSignal = ValueSignal<(), usize>;
+
+/// Creates a new Counter Signal.
+pub fn new() -> CounterSignal {
+    value_signal::new(0, |(), n| n + 1)
+}
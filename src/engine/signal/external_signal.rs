@@ -0,0 +1,243 @@
+//! A module for signals fed with values coming from outside of the reactive program.
+
+use super::*;
+use std::mem;
+use std::sync::{Arc, Mutex, Weak};
+
+
+/*
+        External Signal
+    The External Signal is emitted by the outside world (sensors, keyboard, other threads...).
+    To guarantee this, the signal init function `external_signal::new` returns two different parts:
+    - ExternalFeeder:   can be sent to any thread, and pushes values into a shared buffer.
+    - ExternalSignal:   implements SAwaitIn.
+
+    While some continuations are waiting for the value of the signal, a polling continuation
+    drains the buffer at each end of instant, and emits the signal with all the pushed values.
+    The signal is thus present at the instant following the push, and `await_in` returns the
+    vector of values pushed since the previous emission.
+
+        External Emitter
+    The External Emitter allows to emit any signal implementing SEmit from outside of the reactive
+    program. Its `emit` can be called from any thread, and pushes values into an inbox. The
+    forwarding process returned by `forward` drains the inbox at the end of the instant, and emits
+    the signal with each value, as if it was emitted during the next instant.
+*/
+
+/// Data shared between the feeder and the polling continuation.
+struct ExternalData<V> {
+    buffer: Mutex<Vec<V>>,
+    waiting_in: Mutex<Vec<Box<Continuation<Vec<V>>>>>,
+    polling: Mutex<bool>,
+}
+
+/// Value Runtime for External Signals.
+pub struct ExternalSignalValueRuntime<V> where V: Clone + 'static + Send + Sync {
+    data: Arc<ExternalData<V>>,
+    value: Mutex<Vec<V>>,
+    signal: Mutex<Option<Weak<SignalRuntime<ExternalSignalValueRuntime<V>>>>>,
+}
+
+impl<V> ExternalSignalValueRuntime<V> where V: Clone + 'static + Send + Sync {
+    /// Registers the continuation which drains the buffer at the end of the instant.
+    fn poll(signal: SignalRuntimeRef<Self>, data: Arc<ExternalData<V>>, runtime: &mut Runtime) {
+        let c = move |r: &mut Runtime, ()| {
+            // Emissions done at the end of instant behave as if they were done during the next
+            // instant.
+            let values = mem::replace(&mut *data.buffer.lock().unwrap(), vec!());
+            for v in values {
+                signal.emit(r, v);
+            }
+
+            // We keep polling while someone is waiting for the signal.
+            let mut polling = data.polling.lock().unwrap();
+            if data.waiting_in.lock().unwrap().is_empty() {
+                *polling = false;
+            } else {
+                Self::poll(signal, data.clone(), r);
+            }
+        };
+        runtime.unguarded(|r: &mut Runtime| r.on_end_of_instant(Box::new(c)));
+    }
+}
+
+impl<V> ValueRuntime for ExternalSignalValueRuntime<V> where V: Clone + 'static + Send + Sync {
+    type V1 = V;
+    type V2 = Vec<V>;
+    type V3 = V;
+
+    fn emit(&self, _runtime: &mut Runtime, v: Self::V1) {
+        self.value.lock().unwrap().push(v);
+    }
+
+    fn await_in<C>(&self, runtime: &mut Runtime, c:C) where C: Continuation<Self::V2> {
+        let mut polling = self.data.polling.lock().unwrap();
+        self.data.waiting_in.lock().unwrap().push(Box::new(c));
+
+        // If nobody was waiting, the buffer is not polled anymore.
+        if !*polling {
+            *polling = true;
+            let signal = self.signal.lock().unwrap().as_ref().and_then(|s| s.upgrade());
+            let signal = SignalRuntimeRef { runtime: signal.unwrap() };
+            Self::poll(signal, self.data.clone(), runtime);
+        }
+    }
+
+    fn release_await_in(&self, runtime: &mut Runtime) {
+        let mut waiting_in = self.data.waiting_in.lock().unwrap();
+        // This also resets the value of the signal.
+        let value = mem::replace(&mut *self.value.lock().unwrap(), vec!());
+
+        while let Some(cont) = waiting_in.pop() {
+            let v = value.clone();
+            runtime.on_current_instant(Box::new(move |r: &mut Runtime, ()| {
+                cont.call_box(r, v);
+            }));
+        }
+    }
+
+    fn get(&self) -> Self::V3 {
+        unreachable!()
+    }
+
+    fn reset(&self) {
+        // The values pushed by the feeders but not emitted yet are kept, they will be emitted
+        // during the next execution.
+        self.data.waiting_in.lock().unwrap().clear();
+        *self.data.polling.lock().unwrap() = false;
+        self.value.lock().unwrap().clear();
+    }
+}
+
+
+/// Feeder part of External Signals, which can be sent to any thread.
+pub struct ExternalFeeder<V> {
+    data: Arc<ExternalData<V>>,
+}
+
+impl<V> Clone for ExternalFeeder<V> {
+    fn clone(&self) -> Self {
+        ExternalFeeder { data: self.data.clone() }
+    }
+}
+
+impl<V> ExternalFeeder<V> {
+    /// Pushes a value, which will be emitted on the signal at the next instant boundary.
+    pub fn push(&self, v: V) {
+        self.data.buffer.lock().unwrap().push(v);
+    }
+}
+
+/// Signal part of External Signals, which is Clone.
+#[derive(Clone)]
+pub struct ExternalSignal<V> where V: Clone + 'static + Send + Sync {
+    signal: SignalRuntimeRef<ExternalSignalValueRuntime<V>>,
+}
+
+impl<V> Signal for ExternalSignal<V> where V: Clone + 'static + Send + Sync {
+    type VR = ExternalSignalValueRuntime<V>;
+
+    fn runtime(&self) -> SignalRuntimeRef<Self::VR> {
+        self.signal.clone()
+    }
+}
+
+impl<V> SAwaitIn for ExternalSignal<V> where V: Clone + 'static + Send + Sync {}
+
+
+/// Creates a new External Signal. Returns Feeder and Signal parts.
+pub fn new<V>() -> (ExternalFeeder<V>, ExternalSignal<V>) where V: Clone + 'static + Send + Sync {
+    let data = Arc::new(ExternalData {
+        buffer: Mutex::new(vec!()),
+        waiting_in: Mutex::new(vec!()),
+        polling: Mutex::new(false),
+    });
+    let value_runtime = ExternalSignalValueRuntime {
+        data: data.clone(),
+        value: Mutex::new(vec!()),
+        signal: Mutex::new(None),
+    };
+    let runtime_ref = SignalRuntimeRef::new(value_runtime);
+
+    // The value runtime needs to emit the signal it belongs to.
+    *runtime_ref.runtime.value_runtime().signal.lock().unwrap() =
+        Some(Arc::downgrade(&runtime_ref.runtime));
+
+    (ExternalFeeder { data }, ExternalSignal { signal: runtime_ref })
+}
+
+
+/// Handle emitting a signal from outside of the reactive program, which can be sent to any thread.
+/// The values are only emitted by the process returned by `forward`, which must run in the
+/// reactive program.
+pub struct ExternalEmitter<S> where S: Signal {
+    signal: SignalRuntimeRef<S::VR>,
+    inbox: Arc<Mutex<Vec<<S::VR as ValueRuntime>::V1>>>,
+}
+
+impl<S> Clone for ExternalEmitter<S> where S: Signal {
+    fn clone(&self) -> Self {
+        ExternalEmitter { signal: self.signal.clone(), inbox: self.inbox.clone() }
+    }
+}
+
+impl<S> ExternalEmitter<S> where S: SEmit + 'static {
+    /// Creates a handle emitting `signal` from outside of the reactive program.
+    pub fn new(signal: &S) -> Self {
+        ExternalEmitter { signal: signal.runtime(), inbox: Arc::new(Mutex::new(vec!())) }
+    }
+
+    /// Pushes a value, which will be emitted on the signal at the next instant boundary reached by
+    /// the forwarding process.
+    pub fn emit(&self, v: <S::VR as ValueRuntime>::V1) {
+        self.inbox.lock().unwrap().push(v);
+    }
+
+    /// Returns a process that waits for the end of the instant, emits the signal with the values
+    /// pushed since the previous forwarding, and returns their number. As a `ProcessMut`, it can be
+    /// looped on to forward the values at each instant.
+    pub fn forward(&self) -> Forward<S> {
+        Forward { signal: self.signal.clone(), inbox: self.inbox.clone() }
+    }
+}
+
+/// A process that emits the values pushed into the inbox of an `ExternalEmitter`.
+pub struct Forward<S> where S: Signal {
+    signal: SignalRuntimeRef<S::VR>,
+    inbox: Arc<Mutex<Vec<<S::VR as ValueRuntime>::V1>>>,
+}
+
+impl<S> Forward<S> where S: SEmit + 'static {
+    /// Emits the values of the inbox. Emissions done at the end of instant behave as if they were
+    /// done during the next instant.
+    fn drain(&self, runtime: &mut Runtime) -> usize {
+        let values = mem::replace(&mut *self.inbox.lock().unwrap(), vec!());
+        let n = values.len();
+        for v in values {
+            self.signal.emit(runtime, v);
+        }
+        n
+    }
+}
+
+impl<S> Process for Forward<S> where S: SEmit + 'static {
+    type Value = usize;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        runtime.on_end_of_instant(Box::new(move |r: &mut Runtime, ()| {
+            let n = self.drain(r);
+            next.call(r, n);
+        }));
+    }
+}
+
+impl<S> ProcessMut for Forward<S> where S: SEmit + 'static {
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        runtime.on_end_of_instant(Box::new(move |r: &mut Runtime, ()| {
+            let n = self.drain(r);
+            next.call(r, (self, n));
+        }));
+    }
+}
@@ -0,0 +1,122 @@
+//! A module for signals recording their latest emitted values, for debugging.
+
+use super::*;
+use std::collections::VecDeque;
+
+/*
+        History Recorder
+    The History Recorder is a view of another signal, which records the last emitted values with
+    the number of the instant of their emission. It shares the status and the values of the
+    original signal, so it behaves exactly as this signal: emitting through the recorder is the
+    same as emitting the original signal, except that the value is recorded.
+    Only the emissions made through the recorder are recorded.
+
+    The history is bounded: once `capacity` values are recorded, each new emission drops the
+    oldest one.
+*/
+
+/// Value Runtime for HistoryRecorder.
+pub struct HistoryValueRuntime<VR> where VR: ValueRuntime {
+    signal: SignalRuntimeRef<VR>,
+    capacity: usize,
+    history: Mutex<VecDeque<(u64, VR::V1)>>,
+}
+
+impl<VR> ValueRuntime for HistoryValueRuntime<VR> where VR: ValueRuntime + 'static, VR::V1: Clone {
+    type V1 = VR::V1;
+    type V2 = VR::V2;
+    type V3 = VR::V3;
+
+    fn emit(&self, runtime: &mut Runtime, v: Self::V1) {
+        if self.capacity > 0 {
+            let mut history = self.history.lock().unwrap();
+            if history.len() == self.capacity {
+                history.pop_front();
+            }
+            history.push_back((runtime.current_instant(), v.clone()));
+        }
+        self.signal.runtime.value_runtime().emit(runtime, v);
+    }
+
+    fn await_in<C>(&self, runtime: &mut Runtime, c:C) where C: Continuation<Self::V2> {
+        self.signal.runtime.value_runtime().await_in(runtime, c);
+    }
+
+    fn release_await_in(&self, runtime: &mut Runtime) {
+        // Called only when the first emission of the instant is made through the recorder: the
+        // original signal does not release its continuations by itself then.
+        self.signal.runtime.value_runtime().release_await_in(runtime);
+    }
+
+    fn get(&self) -> Self::V3 {
+        self.signal.runtime.value_runtime().get()
+    }
+
+    fn reset(&self) {
+        self.history.lock().unwrap().clear();
+        self.signal.reset();
+    }
+}
+
+
+/// Signal recording the last values emitted through it, with the instants of their emission.
+pub struct HistoryRecorder<S> where S: Signal, HistoryValueRuntime<S::VR>: ValueRuntime {
+    signal: SignalRuntimeRef<HistoryValueRuntime<S::VR>>,
+}
+
+impl<S> HistoryRecorder<S>
+    where S: Signal, S::VR: 'static, <S::VR as ValueRuntime>::V1: Clone
+{
+    /// Creates a view of `signal`, recording the last `capacity` values emitted through it.
+    pub fn new(signal: &S, capacity: usize) -> Self {
+        let signal = signal.runtime();
+        let runtime = SignalRuntime::with_status_of(&signal.runtime, HistoryValueRuntime {
+            signal: signal.clone(),
+            capacity,
+            history: Mutex::new(VecDeque::with_capacity(capacity)),
+        });
+
+        HistoryRecorder { signal: SignalRuntimeRef { runtime: Arc::new(runtime) } }
+    }
+
+    /// Returns a snapshot of the recorded values, from the oldest to the latest, with the number
+    /// of the instant in which they were emitted.
+    pub fn history(&self) -> Vec<(u64, <S::VR as ValueRuntime>::V1)> {
+        let history = self.signal.runtime.value_runtime().history.lock().unwrap();
+        history.iter().cloned().collect()
+    }
+}
+
+impl<S> Clone for HistoryRecorder<S>
+    where S: Signal, HistoryValueRuntime<S::VR>: ValueRuntime + 'static
+{
+    fn clone(&self) -> Self {
+        HistoryRecorder { signal: self.signal.clone() }
+    }
+}
+
+impl<S> Signal for HistoryRecorder<S>
+    where S: Signal, S::VR: 'static, <S::VR as ValueRuntime>::V1: Clone
+{
+    type VR = HistoryValueRuntime<S::VR>;
+
+    fn runtime(&self) -> SignalRuntimeRef<Self::VR> {
+        self.signal.clone()
+    }
+}
+
+impl<S> SEmit for HistoryRecorder<S>
+    where S: SEmit, S::VR: 'static, <S::VR as ValueRuntime>::V1: Clone
+{}
+
+impl<S> SAwaitIn for HistoryRecorder<S>
+    where S: SAwaitIn, S::VR: 'static, <S::VR as ValueRuntime>::V1: Clone
+{}
+
+impl<S> SAwaitOneImmediate for HistoryRecorder<S>
+    where S: SAwaitOneImmediate, S::VR: 'static, <S::VR as ValueRuntime>::V1: Clone
+{}
+
+impl<S> SReadLatest for HistoryRecorder<S>
+    where S: SReadLatest, S::VR: 'static, <S::VR as ValueRuntime>::V1: Clone
+{}
@@ -0,0 +1,97 @@
+//! A module for signals keeping their latest value.
+
+use super::*;
+use std::sync::Mutex;
+
+/*
+        Latch Signal
+    The Latch Signal is a MPMC (Multiple Producer, Multiple Consumer) signal whose value persists
+    across instants: each emission overwrites the value, which is never reset.
+    It implements SEmit, SAwaitIn, SAwaitOneImmediate and SReadLatest.
+
+    The value can thus be read at any instant with `read_latest`, even if the signal is absent.
+    Reading during an instant where the signal is emitted may return either the previous or the
+    new value, depending on the order of execution.
+*/
+
+/// Value Runtime for LatchSignal.
+pub struct LatchSignalValueRuntime<V> {
+    waiting_in: Mutex<Vec<Box<Continuation<V>>>>,
+    value: Mutex<V>,
+    initial: V,
+}
+
+impl<V> ValueRuntime for LatchSignalValueRuntime<V> where V: Clone + 'static + Send + Sync {
+    type V1 = V;
+    type V2 = V;
+    type V3 = V;
+
+    fn emit(&self, _runtime: &mut Runtime, v: Self::V1) {
+        *self.value.lock().unwrap() = v;
+    }
+
+    fn await_in<C>(&self, _runtime: &mut Runtime, c:C) where C: Continuation<Self::V2> {
+        self.waiting_in.lock().unwrap().push(Box::new(c));
+    }
+
+    fn release_await_in(&self, runtime: &mut Runtime) {
+        let mut waiting_in = self.waiting_in.lock().unwrap();
+        let value = self.value.lock().unwrap();
+        while let Some(cont) = waiting_in.pop() {
+            let v = value.clone();
+            runtime.on_current_instant(Box::new(move |r: &mut Runtime, ()| {
+                cont.call_box(r, v);
+            }));
+        }
+
+        // Unlike other signals, the value is not reset.
+    }
+
+    fn get(&self) -> Self::V3 {
+        self.value.lock().unwrap().clone()
+    }
+
+    fn reset(&self) {
+        self.waiting_in.lock().unwrap().clear();
+        *self.value.lock().unwrap() = self.initial.clone();
+    }
+}
+
+
+#[derive(Clone)]
+/// MPMC signal keeping its latest emitted value.
+pub struct LatchSignal<V> where V: 'static + Clone + Send + Sync {
+    signal: SignalRuntimeRef<LatchSignalValueRuntime<V>>,
+}
+
+impl<V> LatchSignal<V> where V: 'static + Clone + Send + Sync {
+    /// Creates a new Latch Signal holding the value `initial`.
+    pub fn new(initial: V) -> Self {
+        let value_runtime = LatchSignalValueRuntime {
+            waiting_in: Mutex::new(vec!()),
+            value: Mutex::new(initial.clone()),
+            initial,
+        };
+
+        LatchSignal { signal: SignalRuntimeRef::new(value_runtime) }
+    }
+}
+
+impl<V> Signal for LatchSignal<V> where V: 'static + Clone + Send + Sync {
+    type VR = LatchSignalValueRuntime<V>;
+
+    fn runtime(&self) -> SignalRuntimeRef<Self::VR> {
+        self.signal.clone()
+    }
+}
+
+impl<V> SEmit for LatchSignal<V> where V: 'static + Clone + Send + Sync {}
+impl<V> SAwaitIn for LatchSignal<V> where V: 'static + Clone + Send + Sync {}
+impl<V> SAwaitOneImmediate for LatchSignal<V> where V: 'static + Clone + Send + Sync {}
+impl<V> SReadLatest for LatchSignal<V> where V: 'static + Clone + Send + Sync {}
+
+
+/// Creates a new Latch Signal holding the value `initial`.
+pub fn new<V>(initial: V) -> LatchSignal<V> where V: 'static + Clone + Send + Sync {
+    LatchSignal::new(initial)
+}
@@ -0,0 +1,99 @@
+//! A module for signals viewing another signal through a function.
+
+use super::*;
+
+/*
+        Mapped Signal
+    The Mapped Signal is a view of another signal, whose received values are transformed by a
+    function. It shares the status of the original signal, so it is present at the same instants,
+    and it cannot be emitted.
+    It implements SAwaitIn if the original signal implements it.
+
+    The function is applied once for each continuation waiting for the value, hence it has to be
+    cloneable and shareable between workers.
+*/
+
+/// Value Runtime for MappedSignal.
+pub struct MappedSignalValueRuntime<VR, F> where VR: ValueRuntime {
+    signal: SignalRuntimeRef<VR>,
+    map: F,
+}
+
+impl<VR, F, W> ValueRuntime for MappedSignalValueRuntime<VR, F>
+    where VR: ValueRuntime + 'static, F: Fn(VR::V2) -> W + Clone + 'static + Send + Sync,
+          W: 'static + Send + Sync
+{
+    type V1 = VR::V1;
+    type V2 = W;
+    type V3 = VR::V3;
+
+    fn emit(&self, _runtime: &mut Runtime, _v: Self::V1) {
+        unreachable!();
+    }
+
+    fn await_in<C>(&self, runtime: &mut Runtime, c:C) where C: Continuation<Self::V2> {
+        // The continuation waits for the value of the original signal, which releases it.
+        let map = self.map.clone();
+        self.signal.runtime.value_runtime().await_in(runtime, move |r: &mut Runtime, v: VR::V2| {
+            c.call(r, map(v));
+        });
+    }
+
+    fn release_await_in(&self, _runtime: &mut Runtime) {
+        // The continuations are released by the original signal.
+    }
+
+    fn get(&self) -> Self::V3 {
+        self.signal.runtime.value_runtime().get()
+    }
+
+    fn reset(&self) {
+        self.signal.reset();
+    }
+}
+
+
+/// Signal whose received values are the ones of another signal, transformed by a function.
+pub struct MappedSignal<S, F> where S: Signal, MappedSignalValueRuntime<S::VR, F>: ValueRuntime {
+    signal: SignalRuntimeRef<MappedSignalValueRuntime<S::VR, F>>,
+}
+
+impl<S, F, W> MappedSignal<S, F>
+    where S: Signal, S::VR: 'static, F: Fn(<S::VR as ValueRuntime>::V2) -> W + Clone + 'static + Send + Sync,
+          W: 'static + Send + Sync
+{
+    /// Creates a view of `signal`, whose received values are transformed by `map`.
+    pub fn new(signal: &S, map: F) -> Self {
+        let signal = signal.runtime();
+        let runtime = SignalRuntime::with_status_of(&signal.runtime, MappedSignalValueRuntime {
+            signal: signal.clone(),
+            map,
+        });
+
+        MappedSignal { signal: SignalRuntimeRef { runtime: Arc::new(runtime) } }
+    }
+}
+
+impl<S, F> Clone for MappedSignal<S, F>
+    where S: Signal, MappedSignalValueRuntime<S::VR, F>: ValueRuntime + 'static
+{
+    fn clone(&self) -> Self {
+        MappedSignal { signal: self.signal.clone() }
+    }
+}
+
+impl<S, F, W> Signal for MappedSignal<S, F>
+    where S: Signal, S::VR: 'static, F: Fn(<S::VR as ValueRuntime>::V2) -> W + Clone + 'static + Send + Sync,
+          W: 'static + Send + Sync
+{
+    type VR = MappedSignalValueRuntime<S::VR, F>;
+
+    fn runtime(&self) -> SignalRuntimeRef<Self::VR> {
+        self.signal.clone()
+    }
+}
+
+impl<S, F, W> SAwaitIn for MappedSignal<S, F>
+    where S: SAwaitIn, S::VR: 'static, F: Fn(<S::VR as ValueRuntime>::V2) -> W + Clone + 'static + Send + Sync,
+          W: 'static + Send + Sync
+{}
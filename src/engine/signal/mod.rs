@@ -2,15 +2,26 @@ pub mod signal_runtime; // Contains the definition of SignalRuntime and ValueRun
 
 pub mod puresignal;     // Defines the unit signal: PureSignal.
 pub mod value_signal;   // Defines a basic value signal (MPMC): ValueSignal.
+pub mod counter_signal; // Defines a value signal counting its emissions: CounterSignal.
 pub mod mpsc_signal;    // Defines a MPSC signal.
+pub mod bounded_mpsc_signal; // Defines a MPSC signal counting and bounding its emissions.
 pub mod spmc_signal;    // Defines a SPMC signal.
+pub mod spsc_signal;    // Defines a SPSC signal.
+pub mod latch_signal;   // Defines a signal keeping its latest value: LatchSignal.
+pub mod collect_signal; // Defines a signal collecting all its emitted values: CollectSignal.
+pub mod mapped_signal;  // Defines a view of a signal transforming its values: MappedSignal.
+pub mod external_signal; // Defines a signal fed from outside of the reactive program.
+pub mod reduce_signal;  // Defines a signal reducing its emissions in parallel: ReduceSignal.
+pub mod history_recorder; // Defines a view of a signal recording its emissions: HistoryRecorder.
 
 use super::Runtime;
 use super::continuation::Continuation;
-use super::process::{Process, ProcessMut};
+use super::process::{Map, Process, ProcessMut};
 use self::signal_runtime::*;
+use self::mapped_signal::MappedSignal;
 use std::mem::swap;
-use std::sync::MutexGuard;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 
 /*
@@ -58,13 +69,68 @@ pub trait Signal where {
         Await { signal: self.runtime() }
     }
 
+    /// Returns a process that waits until the signal has been present in `n` instants, current
+    /// instant included. It returns during the instant of the `n`-th emission, as
+    /// `await_immediate`, or immediately if `n == 0`.
+    fn await_n(&self, n: usize) -> AwaitN<Self> where Self: Sized {
+        AwaitN { signal: self.runtime(), n }
+    }
+
+    /// Returns a process that returns `true` if the signal is present, or `false` at the next
+    /// instant if the signal is not present.
+    fn is_present(&self) -> IsPresent<Self> where Self: Sized {
+        IsPresent { signal: self.runtime() }
+    }
+
     /// Returns a process that calls `p` if the signal is present, and calls `q` at the next instant
     /// if the signal is not present.
+    ///
+    /// `p` is called as soon as the signal is known to be present: immediately if it has already
+    /// been emitted in the current instant, or as soon as it is emitted. Its absence is only known
+    /// at the end of the instant, hence `q` cannot be called earlier.
+    ///
+    /// As a `ProcessMut`, each iteration only advances the branch it executes: the other one keeps
+    /// its state until an iteration executes it. A state that both branches must see evolve has to
+    /// be kept outside of them, e.g. in an `Arc<Mutex<_>>` shared by both.
     fn present<P, Q, V>(&self, p: P, q: Q) -> Present<P, Q, Self>
         where P: Process<Value=V>, Q: Process<Value=V>, Self: Sized
     {
         Present { signal: self.runtime(), process1: p, process2: q }
     }
+
+    /// Same as `present`, but also returns whether the signal was present, i.e. `true` if `p` was
+    /// called and `false` if `q` was.
+    fn present_value<P, Q, V>(&self, p: P, q: Q) -> PresentValue<P, Q, Self>
+        where P: Process<Value=V>, Q: Process<Value=V>, V: 'static, Self: Sized
+    {
+        let present: fn(V) -> (bool, V) = |v| (true, v);
+        let absent: fn(V) -> (bool, V) = |v| (false, v);
+        self.present(p.map(present), q.map(absent))
+    }
+
+    /// Returns a process that calls `p` if the signal is present, and returns `default` at the
+    /// next instant if the signal is not present. This is `present(p, value(default))`, without
+    /// building a process for the default value.
+    fn present_with_default<P>(&self, p: P, default: P::Value) -> PresentWithDefault<P, Self>
+        where P: Process, P::Value: Send, Self: Sized
+    {
+        PresentWithDefault { signal: self.runtime(), process: p, default }
+    }
+
+    /// Returns a process that executes `p`, and aborts it at the end of the instant in which the
+    /// signal is emitted. Returns `Some` value of `p` if it terminated, `None` if it was aborted.
+    fn do_until<P>(&self, p: P) -> DoUntil<P, Self> where P: Process, Self: Sized {
+        DoUntil { signal: self.runtime(), process: p }
+    }
+
+    /// Returns a view of the signal, present at the same instants, whose received values are
+    /// transformed by `map`.
+    fn map_in<F, W>(&self, map: F) -> MappedSignal<Self, F>
+        where Self: Sized, Self::VR: 'static, F: Fn(<Self::VR as ValueRuntime>::V2) -> W + Clone + 'static + Send + Sync,
+              W: 'static + Send + Sync
+    {
+        MappedSignal::new(self, map)
+    }
 }
 
 
@@ -75,6 +141,15 @@ pub trait SEmit: Signal {
     fn emit<P>(&self, p: P) -> Emit<Self, P> where P: Process<Value=<Self::VR as ValueRuntime>::V1>, Self: Sized {
         Emit { signal: self.runtime(), process: p }
     }
+
+    /// Returns a process that executes `p`, and emits its returned value only if `pred` holds on
+    /// it. Otherwise, the signal is left untouched.
+    fn emit_if<P, F>(&self, p: P, pred: F) -> EmitIf<Self, P, F>
+        where P: Process<Value=<Self::VR as ValueRuntime>::V1>,
+              F: FnOnce(&<Self::VR as ValueRuntime>::V1) -> bool + 'static + Send, Self: Sized
+    {
+        EmitIf { signal: self.runtime(), process: p, pred }
+    }
 }
 
 
@@ -95,6 +170,40 @@ pub trait SAwaitIn: Signal {
     fn await_in(&self) -> AwaitIn<Self> where Self: Sized {
         AwaitIn { signal: self.runtime() }
     }
+
+    /// Returns a process that waits for the signal, and at next instant folds its value into an
+    /// accumulator, starting with `init`, by `acc = f(acc, v)`, and returns the accumulator. As a
+    /// `ProcessMut`, the accumulator is kept from one iteration to the next, so that looping on it
+    /// gives the running fold of all the values of the signal.
+    fn scan<A, F>(&self, init: A, f: F) -> Scan<Self, A, F>
+        where Self: Sized, A: Clone + 'static + Send,
+              F: FnMut(A, <Self::VR as ValueRuntime>::V2) -> A + 'static + Send
+    {
+        Scan { signal: self.runtime(), acc: init, fold: f }
+    }
+}
+
+
+/// An object-safe version of `SAwaitIn`, which forgets the type of the signal but keeps the type
+/// of its values. Thus `Box<AwaitInDyn<Value=V>>` can hold any signal whose `await_in` returns a
+/// `V`, which allows to collect different kinds of signals.
+pub trait AwaitInDyn {
+    type Value;
+
+    /// Returns a process that waits for the signal, and at next instant returns its value.
+    fn await_in_dyn(&self) -> AwaitInBoxed<Self::Value>;
+}
+
+impl<S> AwaitInDyn for S where S: SAwaitIn + 'static {
+    type Value = <S::VR as ValueRuntime>::V2;
+
+    fn await_in_dyn(&self) -> AwaitInBoxed<Self::Value> {
+        let signal = self.runtime();
+        let await_in = move |r: &mut Runtime, next: Box<Continuation<Self::Value>>| {
+            signal.await_in(r, move |r: &mut Runtime, v| next.call_box(r, v));
+        };
+        AwaitInBoxed { await_in: Arc::new(await_in) }
+    }
 }
 
 
@@ -119,6 +228,31 @@ pub trait SAwaitOneImmediate: Signal {
 
 
 
+/// A reactive signal whose latest emitted value can be read at any time.
+pub trait SReadLatest: Signal {
+
+    /// Returns a process that returns the latest emitted value of the signal, in the current
+    /// instant.
+    fn read_latest(&self) -> ReadLatest<Self> where Self: Sized {
+        ReadLatest { signal: self.runtime() }
+    }
+}
+
+
+/// A reactive signal which can be reset, to be used again by another execution.
+pub trait SReset: Signal {
+
+    /// Resets the signal to the state it had when it was created: it is absent, no continuation
+    /// waits for it anymore and its value is the initial one. This must only be called between
+    /// two executions, a signal must not be reset while a process uses it.
+    fn reset(&self) where Self::VR: 'static {
+        self.runtime().reset();
+    }
+}
+
+/// All the signals can be reset.
+impl<S> SReset for S where S: Signal {}
+
 
 /*
     Process and ProcessMut implementations for the aboved used return types.
@@ -152,6 +286,58 @@ impl<S> ProcessMut for AwaitImmediate<S> where S: Signal + 'static {
 }
 
 
+/*
+    AwaitN
+*/
+/// A process that waits until the signal has been present in a given number of instants.
+pub struct AwaitN<S> where S: Signal {
+    signal: SignalRuntimeRef<S::VR>,
+    n: usize,
+}
+
+/// Calls `next` once `signal` has been present in `n` instants, current instant included.
+fn await_n<VR, C>(signal: SignalRuntimeRef<VR>, runtime: &mut Runtime, n: usize, next: C)
+    where VR: ValueRuntime + 'static, C: Continuation<()>
+{
+    if n == 0 {
+        next.call(runtime, ());
+    } else {
+        let signal2 = signal.clone();
+        signal.on_signal(runtime, move |r: &mut Runtime, ()| {
+            if n == 1 {
+                next.call(r, ());
+            } else {
+                // The signal stays present until the end of the instant: the next emission can
+                // only be awaited from the next instant.
+                r.on_next_instant(Box::new(move |r: &mut Runtime, ()| {
+                    await_n(signal2, r, n - 1, next);
+                }));
+            }
+        });
+    }
+}
+
+impl<S> Process for AwaitN<S> where S: Signal + 'static {
+    type Value = ();
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        await_n(self.signal, runtime, self.n, next);
+    }
+}
+
+impl<S> ProcessMut for AwaitN<S> where S: Signal + 'static {
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        let signal = self.signal.clone();
+        let n = self.n;
+        await_n(self.signal, runtime, n, move |r: &mut Runtime, ()| {
+            next.call(r, (AwaitN { signal, n }, ()))
+        });
+    }
+}
+
+
 /*
     Await
 */
@@ -180,6 +366,107 @@ impl<S> ProcessMut for Await<S> where S: Signal + 'static {
 }
 
 
+/*
+    AwaitAny
+*/
+/// A process that waits for the instant following the next emission of any of several signals,
+/// and returns the index of the emitted one.
+pub struct AwaitAny<S> where S: Signal {
+    signals: Vec<SignalRuntimeRef<S::VR>>,
+}
+
+/// Creates a process that waits for the instant following the next emission of any of `signals`,
+/// and returns its index in `signals`. If several of them are emitted in this instant, the lowest
+/// index is returned.
+pub fn await_any<S>(signals: &[S]) -> AwaitAny<S> where S: Signal {
+    AwaitAny { signals: signals.iter().map(|s| s.runtime()).collect() }
+}
+
+/// Structure shared by the continuations waiting for the signals of an `AwaitAny`.
+struct AwaitAnyPoint<C> {
+    /// Lowest index of the signals emitted in the instant, if any.
+    fired: Option<usize>,
+    /// Continuation to call, taken when the process completes.
+    continuation: Option<C>,
+}
+
+impl<S> Process for AwaitAny<S> where S: Signal + 'static {
+    type Value = usize;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let point = Arc::new(Mutex::new(AwaitAnyPoint { fired: None, continuation: Some(next) }));
+        // Set once the process completes, so that the signals drop the registrations which lost.
+        let completed = Arc::new(AtomicBool::new(false));
+
+        for (i, signal) in self.signals.iter().enumerate() {
+            let point = point.clone();
+            let completed2 = completed.clone();
+            signal.on_signal_cancellable(runtime, &completed, move |r: &mut Runtime, ()| {
+                let mut p = point.lock().unwrap();
+                match p.fired {
+                    // The process already completed in a previous instant.
+                    _ if p.continuation.is_none() => {},
+                    Some(j) => p.fired = Some(usize::min(i, j)),
+                    None => {
+                        // The first emission of the instant: the winner is only known once all
+                        // the emissions of the instant are done, hence at the end of the instant.
+                        p.fired = Some(i);
+                        let point = point.clone();
+                        r.on_end_of_instant(Box::new(move |r: &mut Runtime, ()| {
+                            completed2.store(true, Ordering::SeqCst);
+                            let (i, c) = {
+                                let mut p = point.lock().unwrap();
+                                (p.fired.unwrap(), p.continuation.take().unwrap())
+                            };
+                            r.on_current_instant(Box::new(move |r: &mut Runtime, ()| c.call(r, i)));
+                        }));
+                    }
+                }
+            });
+        }
+    }
+}
+
+impl<S> ProcessMut for AwaitAny<S> where S: Signal + 'static {
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        let signals = self.signals.clone();
+        self.call(runtime, move |r: &mut Runtime, i| {
+            next.call(r, (AwaitAny { signals }, i))
+        });
+    }
+}
+
+/*
+    IsPresent
+*/
+/// A process that returns `true` if the signal is present, or `false` at the next instant if the
+/// signal is not present.
+pub struct IsPresent<S> where S: Signal {
+    signal: SignalRuntimeRef<S::VR>,
+}
+
+impl<S> Process for IsPresent<S> where S: Signal + 'static {
+    type Value = bool;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        self.signal.present(runtime, next);
+    }
+}
+
+impl<S> ProcessMut for IsPresent<S> where S: Signal + 'static {
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        let signal = self.signal.clone();
+        self.signal.present(runtime, move |r: &mut Runtime, present: bool| {
+            next.call(r, (IsPresent { signal }, present))
+        });
+    }
+}
+
+
 /*
     Present
 */
@@ -208,6 +495,7 @@ impl<P, Q, S, V> Process for Present<P, Q, S>
     }
 }
 
+/// Only the executed branch is replaced by its next state, the other one is kept as is.
 impl<P, Q, S, V> ProcessMut for Present<P, Q, S>
     where P: Process<Value=V> + ProcessMut, Q: Process<Value=V> + ProcessMut, S: Signal + 'static
 {
@@ -233,6 +521,67 @@ impl<P, Q, S, V> ProcessMut for Present<P, Q, S>
 }
 
 
+/*
+    PresentValue
+*/
+/// A process that calls a process if the signal is present, or calls another at the next instant
+/// if the signal is not present, and returns the presence of the signal along with their value.
+pub type PresentValue<P, Q, S> = Present<WithPresence<P>, WithPresence<Q>, S>;
+
+/// A process returning its value along with the presence of a signal.
+type WithPresence<P> = Map<P, fn(<P as Process>::Value) -> (bool, <P as Process>::Value)>;
+
+
+/*
+    PresentWithDefault
+*/
+/// A process that calls a process if the signal is present, or returns a default value at the
+/// next instant if the signal is not present.
+pub struct PresentWithDefault<P, S> where P: Process, S: Signal {
+    signal: SignalRuntimeRef<S::VR>,
+    process: P,
+    default: P::Value,
+}
+
+impl<P, S> Process for PresentWithDefault<P, S>
+    where P: Process, P::Value: Send, S: Signal + 'static
+{
+    type Value = P::Value;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let signal = self.signal.clone();
+        signal.present(runtime, move |r: &mut Runtime, present: bool| {
+            if present {
+                self.process.call(r, next);
+            } else {
+                next.call(r, self.default);
+            }
+        });
+    }
+}
+
+impl<P, S> ProcessMut for PresentWithDefault<P, S>
+    where P: ProcessMut, P::Value: Send + Clone, S: Signal + 'static
+{
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        let signal = self.signal.clone();
+        let signal2 = self.signal.clone();
+        signal.present(runtime, move |r: &mut Runtime, present: bool| {
+            let default = self.default;
+            if present {
+                self.process.get_mut().map(move |(p, v)| {
+                    (PresentWithDefault { signal: signal2, process: p, default }, v)
+                }).call(r, next);
+            } else {
+                let v = default.clone();
+                next.call(r, (PresentWithDefault { signal: signal2, process: self.process, default }, v));
+            }
+        });
+    }
+}
+
 /*
     Emit
 */
@@ -270,6 +619,54 @@ impl<S, P> ProcessMut for Emit<S, P>
 }
 
 
+/*
+    EmitIf
+*/
+/// A process that emits the returned value of a process, if it satisfies a predicate.
+pub struct EmitIf<S, P, F> where S: Signal {
+    signal: SignalRuntimeRef<S::VR>,
+    process: P,
+    pred: F,
+}
+
+impl<S, P, F> Process for EmitIf<S, P, F>
+    where S: Signal + 'static, P: Process<Value=<S::VR as ValueRuntime>::V1>,
+          F: FnOnce(&<S::VR as ValueRuntime>::V1) -> bool + 'static + Send
+{
+    type Value = ();
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let signal = self.signal;
+        let pred = self.pred;
+        self.process.call(runtime, move |r: &mut Runtime, v: <S::VR as ValueRuntime>::V1| {
+            if pred(&v) {
+                signal.emit(r, v);
+            }
+            next.call(r, ());
+        });
+    }
+}
+
+impl<S, P, F> ProcessMut for EmitIf<S, P, F>
+    where S: Signal + 'static, P: Process<Value=<S::VR as ValueRuntime>::V1>, P: ProcessMut,
+          F: FnMut(&<S::VR as ValueRuntime>::V1) -> bool + 'static + Send
+{
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        let signal_copy = self.signal.clone();
+        let signal = self.signal;
+        let mut pred = self.pred;
+        self.process.call_mut(runtime, move|r: &mut Runtime, (p, v): (P, <S::VR as ValueRuntime>::V1)| {
+            if pred(&v) {
+                signal.emit(r, v);
+            }
+            next.call(r, (EmitIf { signal: signal_copy, process: p, pred }, ()));
+        });
+    }
+}
+
+
 /*
     AwaitIn
 */
@@ -298,6 +695,75 @@ impl<S> ProcessMut for AwaitIn<S> where S: Signal + 'static {
 }
 
 
+/*
+    Scan
+*/
+/// A process that waits for the signal, and at next instant folds its value into an accumulator.
+pub struct Scan<S, A, F> where S: Signal {
+    signal: SignalRuntimeRef<S::VR>,
+    acc: A,
+    fold: F,
+}
+
+impl<S, A, F> Process for Scan<S, A, F>
+    where S: Signal + 'static, A: Clone + 'static + Send,
+          F: FnMut(A, <S::VR as ValueRuntime>::V2) -> A + 'static + Send
+{
+    type Value = A;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        let (acc, mut fold) = (self.acc, self.fold);
+        self.signal.await_in(runtime, move |r: &mut Runtime, v| {
+            next.call(r, fold(acc, v))
+        });
+    }
+}
+
+impl<S, A, F> ProcessMut for Scan<S, A, F>
+    where S: Signal + 'static, A: Clone + 'static + Send,
+          F: FnMut(A, <S::VR as ValueRuntime>::V2) -> A + 'static + Send
+{
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        let signal = self.signal.clone();
+        let (acc, mut fold) = (self.acc, self.fold);
+        self.signal.await_in(runtime, move |r: &mut Runtime, v| {
+            let acc = fold(acc, v);
+            next.call(r, (Scan { signal, acc: acc.clone(), fold }, acc))
+        });
+    }
+}
+
+
+/*
+    AwaitInBoxed
+*/
+/// A process that waits for a signal of unknown type, and at next instant returns its value.
+pub struct AwaitInBoxed<V> {
+    await_in: Arc<Fn(&mut Runtime, Box<Continuation<V>>) + Send + Sync>,
+}
+
+impl<V> Process for AwaitInBoxed<V> where V: 'static {
+    type Value = V;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        (self.await_in)(runtime, Box::new(next));
+    }
+}
+
+impl<V> ProcessMut for AwaitInBoxed<V> where V: 'static {
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        let await_in = self.await_in.clone();
+        (self.await_in)(runtime, Box::new(move |r: &mut Runtime, v| {
+            next.call(r, (AwaitInBoxed { await_in }, v))
+        }));
+    }
+}
+
+
 /*
     AwaitOneImmediate
 */
@@ -307,7 +773,7 @@ pub struct AwaitOneImmediate<S> where S: Signal {
 }
 
 impl<S> Process for AwaitOneImmediate<S> where S: Signal + 'static {
-    type Value = <S::VR as ValueRuntime>::V1;
+    type Value = <S::VR as ValueRuntime>::V3;
 
     fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
         self.signal.await_one_immediate(runtime, next);
@@ -324,3 +790,83 @@ impl<S> ProcessMut for AwaitOneImmediate<S> where S: Signal + 'static {
         });
     }
 }
+
+
+/*
+    ReadLatest
+*/
+/// A process that returns the latest emitted value of the signal.
+pub struct ReadLatest<S> where S: Signal {
+    signal: SignalRuntimeRef<S::VR>,
+}
+
+impl<S> Process for ReadLatest<S> where S: Signal + 'static {
+    type Value = <S::VR as ValueRuntime>::V3;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        next.call(runtime, self.signal.runtime.value_runtime().get());
+    }
+}
+
+impl<S> ProcessMut for ReadLatest<S> where S: Signal + 'static {
+    fn call_mut<C>(self, runtime: &mut Runtime, next: C)
+        where Self: Sized, C: Continuation<(Self, Self::Value)>
+    {
+        let v = self.signal.runtime.value_runtime().get();
+        next.call(runtime, (self, v));
+    }
+}
+
+
+/*
+    DoUntil
+*/
+/// A process that executes a process until the signal is emitted.
+pub struct DoUntil<P, S> where S: Signal {
+    signal: SignalRuntimeRef<S::VR>,
+    process: P,
+}
+
+impl<P, S> Process for DoUntil<P, S> where P: Process, S: Signal + 'static {
+    type Value = Option<P::Value>;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<Self::Value> {
+        // The continuation is called by whoever comes first: the termination of the process or
+        // its abortion.
+        let next = Arc::new(Mutex::new(Some(next)));
+        let abort_flag = Arc::new(AtomicBool::new(false));
+        let outer_flags = runtime.abort_flags.clone();
+        let mut inner_flags = outer_flags.clone();
+        inner_flags.push(abort_flag.clone());
+
+        // When the signal is emitted, the process is aborted at the end of the instant: all its
+        // pending continuations become inert.
+        let next_abort = next.clone();
+        // Set once the process completes normally, so that the signal drops the abortion.
+        let completed = Arc::new(AtomicBool::new(false));
+        let completed2 = completed.clone();
+        self.signal.on_signal_cancellable(runtime, &completed, move |r: &mut Runtime, ()| {
+            r.on_end_of_instant(Box::new(move |r: &mut Runtime, ()| {
+                abort_flag.store(true, Ordering::SeqCst);
+                let next = next_abort.lock().unwrap().take();
+                if let Some(next) = next {
+                    next.call(r, None);
+                }
+            }));
+        });
+
+        // The process is executed inside the block, but its continuation is not.
+        let process = self.process;
+        runtime.with_abort_flags(inner_flags, move |r: &mut Runtime| {
+            process.call(r, move |r: &mut Runtime, v: P::Value| {
+                let next = next.lock().unwrap().take();
+                if let Some(next) = next {
+                    completed2.store(true, Ordering::SeqCst);
+                    r.with_abort_flags(outer_flags, move |r: &mut Runtime| {
+                        next.call(r, Some(v));
+                    });
+                }
+            });
+        });
+    }
+}
@@ -9,9 +9,16 @@ use std::sync::Mutex;
     but can only be received once. To guarantee this, the signal init function `mpsc_signal::new`
     returns two different parts:
     - MPSCSignalSender:     implements SEmit.
-    - MPSCSignalReceiver:   implements SAwaitInConsume.
+    - MPSCSignalReceiver:   implements SAwaitInConsume and SAwaitOneImmediate.
 
     Both parts implement Signal trait, so they both allow all actions on signal status.
+
+    NB:
+    The emitted values are moved into `gather`, without being required to be Clone. The gathered
+    value is cloned to be read immediately: `await_one_immediate` returns the value gathered so far
+    in the emission instant, i.e. by the first emission for the processes already waiting. It is
+    only complete at the end of the instant, once all the producers have emitted: the complete
+    value has to be read with `await_in`, in the next instant.
 */
 
 /// Value Runtime for MPSC Signals.
@@ -22,9 +29,10 @@ pub struct MPSCSignalValueRuntime<V1, V2> {
 }
 
 impl<V1, V2> ValueRuntime for MPSCSignalValueRuntime<V1, V2>
-    where V1: Send + Sync, V2: Default + 'static + Send + Sync {
+    where V1: Send + Sync, V2: Default + Clone + 'static + Send + Sync {
     type V1 = V1;
     type V2 = V2;
+    type V3 = V2;
 
     fn emit(&self, _runtime: &mut Runtime, v: Self::V1) {
         let mut opt_v2 = self.value.lock().unwrap();
@@ -53,8 +61,13 @@ impl<V1, V2> ValueRuntime for MPSCSignalValueRuntime<V1, V2>
         *opt_value = Some(V2::default());
     }
 
-    fn get(&self) -> V1 {
-        unreachable!()
+    fn get(&self) -> Self::V3 {
+        self.value.lock().unwrap().clone().unwrap()
+    }
+
+    fn reset(&self) {
+        *self.waiting_in.lock().unwrap() = None;
+        *self.value.lock().unwrap() = Some(V2::default());
     }
 }
 
@@ -62,20 +75,20 @@ impl<V1, V2> ValueRuntime for MPSCSignalValueRuntime<V1, V2>
 #[derive(Clone)]
 /// Sender part for MPSC, which is Clone.
 pub struct MPSCSignalSender<V1, V2>
-    where V1: Send + Sync, V2: Default + 'static + Send + Sync
+    where V1: Send + Sync, V2: Default + Clone + 'static + Send + Sync
 {
     signal: SignalRuntimeRef<MPSCSignalValueRuntime<V1, V2>>,
 }
 
 /// Receiver part for MPSC, which is not Clone.
 pub struct MPSCSignalReceiver<V1, V2>
-    where V1: Send + Sync, V2: Default + 'static + Send + Sync
+    where V1: Send + Sync, V2: Default + Clone + 'static + Send + Sync
 {
     signal: SignalRuntimeRef<MPSCSignalValueRuntime<V1, V2>>,
 }
 
 impl<V1, V2> Signal for MPSCSignalSender<V1, V2>
-    where V1: 'static + Send + Sync, V2: 'static + Default + Send + Sync
+    where V1: 'static + Send + Sync, V2: 'static + Default + Clone + Send + Sync
 {
     type VR = MPSCSignalValueRuntime<V1, V2>;
 
@@ -85,7 +98,7 @@ impl<V1, V2> Signal for MPSCSignalSender<V1, V2>
 }
 
 impl<V1, V2> Signal for MPSCSignalReceiver<V1, V2>
-    where V1: 'static + Send + Sync, V2: 'static + Default + Send + Sync
+    where V1: 'static + Send + Sync, V2: 'static + Default + Clone + Send + Sync
 {
     type VR = MPSCSignalValueRuntime<V1, V2>;
 
@@ -95,15 +108,18 @@ impl<V1, V2> Signal for MPSCSignalReceiver<V1, V2>
 }
 
 impl<V1, V2> SEmit for MPSCSignalSender<V1, V2>
-    where V1: 'static + Send + Sync, V2: 'static + Default + Send + Sync {}
+    where V1: 'static + Send + Sync, V2: 'static + Default + Clone + Send + Sync {}
 
 impl<V1, V2> SAwaitInConsume for MPSCSignalReceiver<V1, V2>
-    where V1: 'static + Send + Sync, V2: 'static + Default + Send + Sync {}
+    where V1: 'static + Send + Sync, V2: 'static + Default + Clone + Send + Sync {}
+
+impl<V1, V2> SAwaitOneImmediate for MPSCSignalReceiver<V1, V2>
+    where V1: 'static + Send + Sync, V2: 'static + Default + Clone + Send + Sync {}
 
 
 /// Creates a new MPSC Signal. Returns Sender and Receiver parts.
 pub fn new<V1, V2, F>(gather: F) -> (MPSCSignalSender<V1, V2>, MPSCSignalReceiver<V1, V2>)
-    where V1: 'static + Send + Sync, V2: Default + Send + Sync,
+    where V1: 'static + Send + Sync, V2: Default + Clone + Send + Sync,
           F: Fn(V1, V2) -> V2 + 'static, F: Send + Sync
 {
     let value_runtime = MPSCSignalValueRuntime {
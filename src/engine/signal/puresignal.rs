@@ -17,6 +17,7 @@ pub struct PureSignalValueRuntime {}
 impl ValueRuntime for PureSignalValueRuntime {
     type V1 = ();
     type V2 = ();
+    type V3 = ();
 
     fn emit(&self, _runtime: &mut Runtime, _v: Self::V1) {
         return;
@@ -30,9 +31,13 @@ impl ValueRuntime for PureSignalValueRuntime {
         return;
     }
 
-    fn get(&self) -> Self::V2 {
+    fn get(&self) -> Self::V3 {
         unreachable!()
     }
+
+    fn reset(&self) {
+        return;
+    }
 }
 
 #[derive(Clone)]
@@ -61,4 +66,15 @@ impl SEmit for PureSignal {}
 /// Creates a new PureSignal.
 pub fn new() -> PureSignal {
     PureSignal::new()
-}
\ No newline at end of file
+}
+
+/// Creates a new PureSignal which is present at each instant where `a` or `b` is present. It is
+/// emitted in the same instant as the first of them, without any relay process.
+pub fn any(a: &PureSignal, b: &PureSignal) -> PureSignal {
+    let result = PureSignal::new();
+    for source in [a, b].iter() {
+        let target = result.runtime();
+        source.runtime().on_emit(move |r: &mut Runtime| target.emit(r, ()));
+    }
+    result
+}
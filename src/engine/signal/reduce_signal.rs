@@ -0,0 +1,114 @@
+//! A module for signals reducing their emitted values in parallel.
+
+use super::*;
+use engine::available_workers;
+use std::sync::Mutex;
+
+/*
+        Reduce Signal
+    The Reduce Signal is a MPMC (Multiple Producer, Multiple Consumer) signal whose emitted values
+    are combined by an associative function, like a Value Signal whose input and output types are
+    the same.
+    It implements SEmit and SAwaitIn.
+
+    Instead of a single value locked by every emission, the signal keeps one partial value per
+    worker, indexed by `Runtime::worker_id`. The partial values are only merged at the end of the
+    instant, so workers emitting concurrently do not contend for the same lock. Since the order of
+    the emissions is unknown, the function must be associative and commutative, and `init` must be
+    its identity.
+*/
+
+/// Value Runtime for ReduceSignal.
+pub struct ReduceSignalValueRuntime<V> {
+    waiting_in: Mutex<Vec<Box<Continuation<V>>>>,
+    partials: Vec<Mutex<Option<V>>>,
+    init: V,
+    reduce: Box<(Fn(V, V) -> V) + Send + Sync>,
+}
+
+impl<V> ValueRuntime for ReduceSignalValueRuntime<V> where V: Clone + 'static + Send + Sync {
+    type V1 = V;
+    type V2 = V;
+    type V3 = V;
+
+    fn emit(&self, runtime: &mut Runtime, v: Self::V1) {
+        // Workers beyond the number of partial values share them.
+        let slot = runtime.worker_id() % self.partials.len();
+        let mut partial = self.partials[slot].lock().unwrap();
+        *partial = Some(match partial.take() {
+            Some(p) => (self.reduce)(p, v),
+            None => v,
+        });
+    }
+
+    fn await_in<C>(&self, _runtime: &mut Runtime, c:C) where C: Continuation<Self::V2> {
+        self.waiting_in.lock().unwrap().push(Box::new(c));
+    }
+
+    fn release_await_in(&self, runtime: &mut Runtime) {
+        // This also resets the partial values.
+        let value = self.partials.iter()
+            .filter_map(|partial| partial.lock().unwrap().take())
+            .fold(self.init.clone(), |acc, p| (self.reduce)(acc, p));
+
+        let mut waiting_in = self.waiting_in.lock().unwrap();
+        while let Some(cont) = waiting_in.pop() {
+            let v = value.clone();
+            runtime.on_current_instant(Box::new(move |r: &mut Runtime, ()| {
+                cont.call_box(r, v);
+            }));
+        }
+    }
+
+    fn get(&self) -> Self::V3 {
+        unreachable!()
+    }
+
+    fn reset(&self) {
+        self.waiting_in.lock().unwrap().clear();
+        for partial in self.partials.iter() {
+            *partial.lock().unwrap() = None;
+        }
+    }
+}
+
+
+#[derive(Clone)]
+/// MPMC signal reducing its emitted values with an associative function.
+pub struct ReduceSignal<V> where V: 'static + Clone + Send + Sync {
+    signal: SignalRuntimeRef<ReduceSignalValueRuntime<V>>,
+}
+
+impl<V> ReduceSignal<V> where V: 'static + Clone + Send + Sync {
+    /// Creates a new Reduce Signal from the identity `init` of the function `reduce`, with one
+    /// partial value per logical CPU.
+    pub fn new<F>(init: V, reduce: F) -> Self where F: Fn(V, V) -> V + 'static + Send + Sync {
+        let value_runtime = ReduceSignalValueRuntime {
+            waiting_in: Mutex::new(vec!()),
+            partials: (0..available_workers()).map(|_| Mutex::new(None)).collect(),
+            init,
+            reduce: Box::new(reduce),
+        };
+
+        ReduceSignal { signal: SignalRuntimeRef::new(value_runtime) }
+    }
+}
+
+impl<V> Signal for ReduceSignal<V> where V: 'static + Clone + Send + Sync {
+    type VR = ReduceSignalValueRuntime<V>;
+
+    fn runtime(&self) -> SignalRuntimeRef<Self::VR> {
+        self.signal.clone()
+    }
+}
+
+impl<V> SEmit for ReduceSignal<V> where V: 'static + Clone + Send + Sync {}
+impl<V> SAwaitIn for ReduceSignal<V> where V: 'static + Clone + Send + Sync {}
+
+
+/// Creates a new Reduce Signal from the identity `init` of the function `reduce`.
+pub fn new<V, F>(init: V, reduce: F) -> ReduceSignal<V>
+    where V: 'static + Clone + Send + Sync, F: Fn(V, V) -> V + 'static + Send + Sync
+{
+    ReduceSignal::new(init, reduce)
+}
@@ -2,6 +2,7 @@
 
 use super::*;
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 
 /// ValueRuntime: part of the SignalRuntime which manipulates the values of the signal.
@@ -13,6 +14,9 @@ pub trait ValueRuntime: Send + Sync {
     /// Output type of the signal (type of received values).
     type V2: Send + Sync;
 
+    /// Type of the values read in the instant of an emission.
+    type V3: Send + Sync;
+
     /// Updates the runtime value with the emission of the value `v`.
     /// Only needs to be implemented if the signal implements the trait SEmit.
     fn emit(&self, runtime: &mut Runtime, v: Self::V1);
@@ -26,37 +30,104 @@ pub trait ValueRuntime: Send + Sync {
     /// Only needs to be implemented if the signal implements the trait SAwaitIn.
     fn release_await_in(&self, runtime: &mut Runtime);
 
-    /// Gets one of the emitted values of the signal, to pass it to continuations which called
-    /// `await_one_immediate`.
+    /// Gets the value of the signal in the current instant, to pass it to continuations which
+    /// called `await_one_immediate`.
     /// Only needs to be implemented if the signal implements the trait SAwaitOneImmediate.
-    fn get(&self) -> Self::V1;
+    fn get(&self) -> Self::V3;
+
+    /// Restores the value of the signal to the one it had when it was created, and drops the
+    /// continuations waiting for this value.
+    fn reset(&self);
+}
+
+
+/// Minimum number of continuations waiting for an emission from which the cancelled ones are
+/// removed.
+const MIN_PRUNE_LEN: usize = 16;
+
+/// Continuations waiting for the emission of a signal. Some of them can be cancelled before the
+/// emission: they are never called, and are removed once the list has grown enough since the last
+/// removal, so that a signal which is never emitted does not accumulate them.
+struct WaitingImmediate {
+    /// Each continuation, with the flag cancelling it if it can be cancelled.
+    continuations: Vec<(Option<Arc<AtomicBool>>, Box<Continuation<()>>)>,
+    /// Length of the list from which the cancelled continuations are removed.
+    prune_len: usize,
+}
+
+impl WaitingImmediate {
+    fn new() -> Self {
+        WaitingImmediate { continuations: vec!(), prune_len: MIN_PRUNE_LEN }
+    }
+
+    /// Adds a continuation, and removes the cancelled ones if the list has grown enough.
+    fn push(&mut self, cancelled: Option<Arc<AtomicBool>>, c: Box<Continuation<()>>) {
+        if self.continuations.len() >= self.prune_len {
+            self.continuations.retain(|&(ref cancelled, _)| !is_cancelled(cancelled));
+            // Doubling the threshold keeps the cost of the removals linear in the registrations.
+            self.prune_len = usize::max(MIN_PRUNE_LEN, 2 * self.continuations.len());
+        }
+        self.continuations.push((cancelled, c));
+    }
+
+    /// Removes all the continuations which are not cancelled.
+    fn take(&mut self) -> Vec<Box<Continuation<()>>> {
+        self.prune_len = MIN_PRUNE_LEN;
+        self.continuations.drain(..).filter(|&(ref cancelled, _)| !is_cancelled(cancelled))
+            .map(|(_, c)| c).collect()
+    }
 }
 
+/// Returns true if the registration with the optional flag `cancelled` has been cancelled.
+fn is_cancelled(cancelled: &Option<Arc<AtomicBool>>) -> bool {
+    cancelled.as_ref().map_or(false, |c| c.load(Ordering::SeqCst))
+}
+
+/// Status of a signal, and the continuations interacting with this status.
+/// It can be shared by several signal runtimes viewing the same signal.
+pub struct SignalStatus<V3> {
+    present: Mutex<bool>,
+    waiting_immediate: Mutex<WaitingImmediate>,
+    waiting_one_immediate: Mutex<Vec<Box<Continuation<V3>>>>,
+    testing_present: Mutex<Vec<Box<Continuation<bool>>>>,
+    waiting: Mutex<Vec<Box<Continuation<()>>>>,
+    on_emit: Mutex<Vec<Box<Fn(&mut Runtime) + Send + Sync>>>,
+}
 
 /// Signal Runtime: contains all the information concerning the signal status, and the continuations
 /// interacting with this status.
 /// Contains a `ValueRuntime`, to handle the value of the signal and the continuations
 /// waiting for this value.
 pub struct SignalRuntime<VR> where VR: ValueRuntime {
-    present: Mutex<bool>,
-    waiting_immediate: Mutex<Vec<Box<Continuation<()>>>>,
-    waiting_one_immediate: Mutex<Vec<Box<Continuation<VR::V1>>>>,
-    testing_present: Mutex<Vec<Box<Continuation<bool>>>>,
-    waiting: Mutex<Vec<Box<Continuation<()>>>>,
+    status: Arc<SignalStatus<VR::V3>>,
     value_runtime: VR,
 }
 
 impl<VR> SignalRuntime<VR> where VR: ValueRuntime {
     /// Creates a new `SignalRuntime` from `value_runtime`.
     pub fn new(value_runtime: VR) -> Self {
-        SignalRuntime {
+        let status = SignalStatus {
             present: Mutex::new(false),
-            waiting_immediate: Mutex::new(vec!()),
+            waiting_immediate: Mutex::new(WaitingImmediate::new()),
             testing_present: Mutex::new(vec!()),
             waiting: Mutex::new(vec!()),
             waiting_one_immediate: Mutex::new(vec!()),
-            value_runtime
-        }
+            on_emit: Mutex::new(vec!()),
+        };
+        SignalRuntime { status: Arc::new(status), value_runtime }
+    }
+
+    /// Creates a new `SignalRuntime` from `value_runtime`, sharing the status of `other`: both
+    /// runtimes are present at the same instants.
+    pub fn with_status_of<VR2>(other: &SignalRuntime<VR2>, value_runtime: VR) -> Self
+        where VR2: ValueRuntime<V3=VR::V3>
+    {
+        SignalRuntime { status: other.status.clone(), value_runtime }
+    }
+
+    /// Returns the value runtime of the signal.
+    pub fn value_runtime(&self) -> &VR {
+        &self.value_runtime
     }
 }
 
@@ -80,14 +151,62 @@ impl<VR> SignalRuntimeRef<VR> where VR: ValueRuntime + 'static {
         SignalRuntimeRef { runtime: Arc::new(SignalRuntime::new(value_runtime)) }
     }
 
+    /// Registers `hook` to be called at each instant, as soon as the signal is emitted for the first
+    /// time in this instant. The hook is kept as long as the signal exists.
+    pub fn on_emit<F>(&self, hook: F) where F: Fn(&mut Runtime) + Send + Sync + 'static {
+        self.runtime.status.on_emit.lock().unwrap().push(Box::new(hook));
+    }
+
+    /// Resets the signal to the state it had when it was created: it is absent, no continuation
+    /// waits for it anymore and its value is the initial one. The hooks registered with `on_emit`
+    /// are kept. This must only be called between two executions.
+    pub fn reset(&self) {
+        let status = &self.runtime.status;
+        *status.present.lock().unwrap() = false;
+        *status.waiting_immediate.lock().unwrap() = WaitingImmediate::new();
+        status.waiting_one_immediate.lock().unwrap().clear();
+        status.testing_present.lock().unwrap().clear();
+        status.waiting.lock().unwrap().clear();
+        self.runtime.value_runtime.reset();
+    }
+
+    /// Returns the number of continuations waiting for the status of the signal: for its
+    /// emission, or to know whether it is present.
+    pub fn waiting_count(&self) -> usize {
+        let status = &self.runtime.status;
+        status.waiting_immediate.lock().unwrap().continuations.len()
+            + status.waiting_one_immediate.lock().unwrap().len()
+            + status.testing_present.lock().unwrap().len()
+            + status.waiting.lock().unwrap().len()
+    }
+
     /// Calls `c` at the first cycle where the signal is present.
     pub fn on_signal<C>(&self, runtime: &mut Runtime, c: C) where C: Continuation<()> {
-        if *self.runtime.present.lock().unwrap() {
+        self.on_signal_with(runtime, None, c);
+    }
+
+    /// Calls `c` at the first cycle where the signal is present, unless `cancelled` is set
+    /// before. A cancelled continuation is dropped without being called, and the signal
+    /// eventually removes it even if it is never emitted.
+    pub fn on_signal_cancellable<C>(&self, runtime: &mut Runtime, cancelled: &Arc<AtomicBool>, c: C)
+        where C: Continuation<()>
+    {
+        self.on_signal_with(runtime, Some(cancelled.clone()), c);
+    }
+
+    fn on_signal_with<C>(&self, runtime: &mut Runtime, cancelled: Option<Arc<AtomicBool>>, c: C)
+        where C: Continuation<()>
+    {
+        let c = runtime.guard(c);
+        if is_cancelled(&cancelled) {
+            return;
+        }
+        if *self.runtime.status.present.lock().unwrap() {
             // If the signal is present, we call c.
             c.call(runtime, ());
         } else {
             // Otherwise, we register c to be called when signal is emitted.
-            self.runtime.waiting_immediate.lock().unwrap().push(Box::new(c));
+            self.runtime.status.waiting_immediate.lock().unwrap().push(cancelled, Box::new(c));
         }
     }
 
@@ -95,7 +214,8 @@ impl<VR> SignalRuntimeRef<VR> where VR: ValueRuntime + 'static {
     pub fn present<C>(&self, runtime: &mut Runtime, c: C)
         where C: Continuation<bool>
     {
-        if *self.runtime.present.lock().unwrap() {
+        let c = runtime.guard(c);
+        if *self.runtime.status.present.lock().unwrap() {
             // If the signal is present, we call c with true.
             c.call(runtime, true);
         } else {
@@ -104,7 +224,7 @@ impl<VR> SignalRuntimeRef<VR> where VR: ValueRuntime + 'static {
 
             // First determines if testing_present is empty, and adds the new c to it.
             let empty = {
-                let mut testing_present = self.runtime.testing_present.lock().unwrap();
+                let mut testing_present = self.runtime.status.testing_present.lock().unwrap();
                 let b = testing_present.is_empty();
                 testing_present.push(Box::new(c));
                 b
@@ -116,14 +236,14 @@ impl<VR> SignalRuntimeRef<VR> where VR: ValueRuntime + 'static {
             if empty {
                 let sig_runtime_ref = self.clone();
                 let c_false = move |r: &mut Runtime, ()| {
-                    let mut testing_present = sig_runtime_ref.runtime.testing_present.lock().unwrap();
+                    let mut testing_present = sig_runtime_ref.runtime.status.testing_present.lock().unwrap();
                     while let Some(cont) = testing_present.pop() {
                         r.on_current_instant(Box::new(|r: &mut Runtime, ()| {
                             cont.call_box(r, false);
                         }));
                     }
                 };
-                runtime.on_end_of_instant(Box::new(c_false));
+                runtime.unguarded(|r: &mut Runtime| r.on_end_of_instant(Box::new(c_false)));
             }
         }
     }
@@ -143,21 +263,21 @@ impl<VR> SignalRuntimeRef<VR> where VR: ValueRuntime + 'static {
         self.runtime.value_runtime.emit(runtime, value);
 
         // We update the status of the signal.
-        let mut present = self.runtime.present.lock().unwrap();
+        let mut present = self.runtime.status.present.lock().unwrap();
         if !*present {
             // The signal status changes to true.
             // We first set the signal as emitted.
             *present = true;
 
             // Then we release all the continuations contained in waiting_immediate.
-            let mut waiting_immediate = self.runtime.waiting_immediate.lock().unwrap();
-            while let Some(c) = waiting_immediate.pop() {
+            let waiting_immediate = self.runtime.status.waiting_immediate.lock().unwrap().take();
+            for c in waiting_immediate.into_iter().rev() {
                 runtime.on_current_instant(c);
             }
 
             // Then we release all the continuations contained in waiting_one_immediate,
             // with the current value of the signal.
-            let mut waiting_one_immediate = self.runtime.waiting_one_immediate.lock().unwrap();
+            let mut waiting_one_immediate = self.runtime.status.waiting_one_immediate.lock().unwrap();
             while let Some(c) = waiting_one_immediate.pop() {
                 let v = self.runtime.value_runtime.get();
                 runtime.on_current_instant(Box::new(move |r: &mut Runtime, ()| {
@@ -167,7 +287,7 @@ impl<VR> SignalRuntimeRef<VR> where VR: ValueRuntime + 'static {
 
             // Then we release all the continuations contained in testing_present, with true as
             // argument.
-            let mut testing_present = self.runtime.testing_present.lock().unwrap();
+            let mut testing_present = self.runtime.status.testing_present.lock().unwrap();
             while let Some(c) = testing_present.pop() {
                 runtime.on_current_instant(Box::new(move |r: &mut Runtime, ()| {
                     c.call_box(r, true);
@@ -179,10 +299,10 @@ impl<VR> SignalRuntimeRef<VR> where VR: ValueRuntime + 'static {
             let sig_runtime_ref = self.clone();
             let end_update = move |r: &mut Runtime, ()| {
                 // Resets signal status.
-                *sig_runtime_ref.runtime.present.lock().unwrap() = false;
+                *sig_runtime_ref.runtime.status.present.lock().unwrap() = false;
 
                 // Releases all waiting continuations.
-                let mut waiting = sig_runtime_ref.runtime.waiting.lock().unwrap();
+                let mut waiting = sig_runtime_ref.runtime.status.waiting.lock().unwrap();
                 while let Some(cont) = waiting.pop() {
                     r.on_current_instant(cont);
                 }
@@ -192,7 +312,14 @@ impl<VR> SignalRuntimeRef<VR> where VR: ValueRuntime + 'static {
             };
 
             // Registers this continuation to be called at the end of instant.
-            runtime.on_end_of_instant(Box::new(end_update));
+            runtime.unguarded(|r: &mut Runtime| r.on_end_of_instant(Box::new(end_update)));
+
+            // Finally, we call the hooks registered on the emission of the signal, once its status
+            // is released.
+            drop(present);
+            for hook in self.runtime.status.on_emit.lock().unwrap().iter() {
+                hook(runtime);
+            }
         }
     }
 
@@ -201,19 +328,21 @@ impl<VR> SignalRuntimeRef<VR> where VR: ValueRuntime + 'static {
         where C: Continuation<VR::V2>
     {
         // Just forwards the action to the value runtime.
+        let c = runtime.guard(c);
         self.runtime.value_runtime.await_in(runtime, c);
     }
 
     /// Calls `c` at the first cycle where the signal is present, with its current value.
-    pub fn await_one_immediate<C>(&self, runtime: &mut Runtime, c: C) where C: Continuation<VR::V1>
+    pub fn await_one_immediate<C>(&self, runtime: &mut Runtime, c: C) where C: Continuation<VR::V3>
     {
-        if *self.runtime.present.lock().unwrap() {
+        let c = runtime.guard(c);
+        if *self.runtime.status.present.lock().unwrap() {
             // If the signal is present, we call c we the current value of the signal, that we can
             // get through the value runtime.
             c.call(runtime, self.runtime.value_runtime.get());
         } else {
             // Otherwise, we register c to be called when signal is emitted.
-            self.runtime.waiting_one_immediate.lock().unwrap().push(Box::new(c));
+            self.runtime.status.waiting_one_immediate.lock().unwrap().push(Box::new(c));
         }
     }
 }
\ No newline at end of file
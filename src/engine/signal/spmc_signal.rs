@@ -17,22 +17,30 @@ use std::sync::Mutex;
     NB:
     If it is placed in some immediate loop, each additional emission removes previous emitted value
     of the signal, setting it to the new emitted value.
-    In order to prevent this case from happening, we could use some `RepeatableIfNotImmediate`
-    marker, but it is not done yet.
+    The signals created by `spmc_signal::new_strict` detect this case, and panic on the second
+    emission of an instant.
 */
 
 /// A runtime for SPMC signals.
 pub struct SPMCSignalValueRuntime<V> {
     waiting_in: Mutex<Vec<Box<Continuation<V>>>>,
     value: Mutex<Option<V>>,
+    emitted_this_instant: Mutex<bool>,
+    strict: bool,   // True if a second emission in the same instant panics.
 }
 
 
 impl<V> ValueRuntime for SPMCSignalValueRuntime<V> where V: Clone + 'static + Send + Sync {
     type V1 = V;
     type V2 = V;
+    type V3 = V;
 
     fn emit(&self, _runtime: &mut Runtime, v: Self::V1) {
+        let mut emitted = self.emitted_this_instant.lock().unwrap();
+        if *emitted && self.strict {
+            panic!("SPMC signal emitted twice in the same instant.");
+        }
+        *emitted = true;
         *(self.value.lock().unwrap()) = Some(v);
     }
 
@@ -45,6 +53,7 @@ impl<V> ValueRuntime for SPMCSignalValueRuntime<V> where V: Clone + 'static + Se
         let mut opt_value = self.value.lock().unwrap();
         // This also resets the value of the signal.
         let value = unpack_mutex(&mut opt_value);
+        *self.emitted_this_instant.lock().unwrap() = false;
 
         while let Some(cont) = waiting_in.pop() {
             let v = value.clone();
@@ -54,9 +63,15 @@ impl<V> ValueRuntime for SPMCSignalValueRuntime<V> where V: Clone + 'static + Se
         }
     }
 
-    fn get(&self) -> Self::V1 {
+    fn get(&self) -> Self::V3 {
         (self.value.lock().unwrap()).clone().unwrap()
     }
+
+    fn reset(&self) {
+        self.waiting_in.lock().unwrap().clear();
+        *self.value.lock().unwrap() = None;
+        *self.emitted_this_instant.lock().unwrap() = false;
+    }
 }
 
 /// Receiver part for SPMC, which is Clone.
@@ -93,11 +108,28 @@ impl<V> SEmitConsume for SPMCSignalSender<V> where V: 'static + Clone + Send + S
 
 
 /// Creates a new SPMC Signal. Returns Sender and Receiver parts.
+/// If it is emitted several times in an instant, the last emitted value is kept.
 pub fn new<V>() -> (SPMCSignalSender<V>, SPMCSignalReceiver<V>) where V: Clone + Send + Sync
+{
+    with_strictness(false)
+}
+
+/// Creates a new SPMC Signal which panics if it is emitted several times in an instant. Returns
+/// Sender and Receiver parts.
+pub fn new_strict<V>() -> (SPMCSignalSender<V>, SPMCSignalReceiver<V>) where V: Clone + Send + Sync
+{
+    with_strictness(true)
+}
+
+/// Creates a new SPMC Signal, panicking on double emissions if `strict` is true.
+fn with_strictness<V>(strict: bool) -> (SPMCSignalSender<V>, SPMCSignalReceiver<V>)
+    where V: Clone + Send + Sync
 {
     let value_runtime = SPMCSignalValueRuntime {
         waiting_in: Mutex::new(vec!()),
         value: Mutex::new(None),
+        emitted_this_instant: Mutex::new(false),
+        strict,
     };
     let runtime_ref = SignalRuntimeRef::new(value_runtime);
     (SPMCSignalSender { signal : runtime_ref.clone() },
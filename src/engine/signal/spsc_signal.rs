@@ -0,0 +1,105 @@
+//! A module for Single Producer, Single Consumer signals.
+
+use super::*;
+use std::sync::Mutex;
+
+
+/*
+        SPSC Signal
+    The SPSC Signal (Single Producer, Single Consumer) is a signal which can be emitted only once
+    and received only once. To guarantee this, the signal init function `spsc_signal::new`
+    returns two different parts:
+    - SPSCSignalSender:     implements SEmitConsume.
+    - SPSCSignalReceiver:   implements SAwaitInConsume.
+
+    Both parts implement Signal trait, so they both allow all actions on signal status.
+
+    Since there is only one emission and one reception, the value is moved from the sender to the
+    receiver: there is no gather function, and the value does not need to be Clone.
+*/
+
+/// A runtime for SPSC signals.
+pub struct SPSCSignalValueRuntime<V> {
+    waiting_in: Mutex<Option<Box<Continuation<V>>>>,
+    value: Mutex<Option<V>>,
+}
+
+
+impl<V> ValueRuntime for SPSCSignalValueRuntime<V> where V: 'static + Send + Sync {
+    type V1 = V;
+    type V2 = V;
+    type V3 = V;
+
+    fn emit(&self, _runtime: &mut Runtime, v: Self::V1) {
+        *(self.value.lock().unwrap()) = Some(v);
+    }
+
+    fn await_in<C>(&self, _runtime: &mut Runtime, c:C) where C: Continuation<Self::V2> {
+        *self.waiting_in.lock().unwrap() = Some(Box::new(c));
+    }
+
+    fn release_await_in(&self, runtime: &mut Runtime) {
+        let mut waiting_in = self.waiting_in.lock().unwrap();
+        let mut opt_value = self.value.lock().unwrap();
+
+        // The value is only taken if the receiver is waiting for it.
+        if let Some(cont) = waiting_in.take() {
+            let v = unpack_mutex(&mut opt_value);
+            runtime.on_current_instant(Box::new(move |r: &mut Runtime, ()| {
+                cont.call_box(r, v);
+            }));
+        }
+    }
+
+    fn get(&self) -> Self::V3 {
+        unreachable!()
+    }
+
+    fn reset(&self) {
+        *self.waiting_in.lock().unwrap() = None;
+        *self.value.lock().unwrap() = None;
+    }
+}
+
+/// Receiver part for SPSC, which is not Clone.
+pub struct SPSCSignalReceiver<V> where V: 'static + Send + Sync {
+    signal: SignalRuntimeRef<SPSCSignalValueRuntime<V>>,
+}
+
+/// Sender part for SPSC, which is not Clone.
+pub struct SPSCSignalSender<V> where V: 'static + Send + Sync {
+    signal: SignalRuntimeRef<SPSCSignalValueRuntime<V>>,
+}
+
+impl<V> Signal for SPSCSignalReceiver<V> where V: 'static + Send + Sync {
+    type VR = SPSCSignalValueRuntime<V>;
+
+    fn runtime(&self) -> SignalRuntimeRef<Self::VR> {
+        self.signal.clone()
+    }
+}
+
+impl<V> Signal for SPSCSignalSender<V> where V: 'static + Send + Sync {
+    type VR = SPSCSignalValueRuntime<V>;
+
+    fn runtime(&self) -> SignalRuntimeRef<Self::VR> {
+        self.signal.clone()
+    }
+}
+
+impl<V> SAwaitInConsume for SPSCSignalReceiver<V> where V: 'static + Send + Sync {}
+
+impl<V> SEmitConsume for SPSCSignalSender<V> where V: 'static + Send + Sync {}
+
+
+/// Creates a new SPSC Signal. Returns Sender and Receiver parts.
+pub fn new<V>() -> (SPSCSignalSender<V>, SPSCSignalReceiver<V>) where V: Send + Sync
+{
+    let value_runtime = SPSCSignalValueRuntime {
+        waiting_in: Mutex::new(None),
+        value: Mutex::new(None),
+    };
+    let runtime_ref = SignalRuntimeRef::new(value_runtime);
+    (SPSCSignalSender { signal : runtime_ref.clone() },
+     SPSCSignalReceiver { signal: runtime_ref })
+}
@@ -27,6 +27,7 @@ impl<V1, V2> ValueRuntime for ValueSignalValueRuntime<V1, V2>
 {
     type V1 = V1;
     type V2 = V2;
+    type V3 = V1;
 
     fn emit(&self, _runtime: &mut Runtime, v: Self::V1) {
         let mut opt_v2 = self.value.lock().unwrap();
@@ -54,10 +55,16 @@ impl<V1, V2> ValueRuntime for ValueSignalValueRuntime<V1, V2>
         *opt_value = Some(self.default.clone());
     }
 
-    fn get(&self) -> Self::V1 {
+    fn get(&self) -> Self::V3 {
         let opt_v = self.last_emitted.lock().unwrap();
         opt_v.clone().unwrap()
     }
+
+    fn reset(&self) {
+        self.waiting_in.lock().unwrap().clear();
+        *self.value.lock().unwrap() = Some(self.default.clone());
+        *self.last_emitted.lock().unwrap() = None;
+    }
 }
 
 
@@ -108,8 +108,40 @@ pub fn game_of_life () {
     endwin();
 }
 
+/// Runs a simulation for `generations` steps without display, and prints the rendered frames.
+/// If `hex` is true, the grid is hexagonal and follows the rules B2/S34H.
+pub fn headless_game_of_life (generations: i32, hex: bool) {
+    let (n, m) = (60, 30);
+    let mut starting_grid = vec!();
+    for i in 0..n {
+        let mut line = vec!();
+        for j in 0..m {
+            line.push((i as isize - 10)*(i as isize - 10) + (j as isize - 10)*(j as isize - 10) < 5)
+        }
+        starting_grid.push(line);
+    }
+
+    let (watcher, ruleset, topology) = if hex {
+        (gameoflife::watcher::StringWatcher::hex(n, m), gameoflife::ruleset::Ruleset::hex(), gameoflife::Topology::Hex)
+    } else {
+        (gameoflife::watcher::StringWatcher::new(n, m), gameoflife::ruleset::Ruleset::conway(),
+         gameoflife::Topology::Square { wrap: false })
+    };
+    gameoflife::run_simulation_steps(starting_grid, Some(watcher.clone()), ruleset, topology, 4, generations);
+    for frame in watcher.frames() {
+        println!("{}", frame);
+    }
+}
+
 pub fn main() {
-    game_of_life();
+    // With `--headless <generations> [--hex]`, the frames are printed instead of being displayed.
+    let args: Vec<String> = std::env::args().collect();
+    if (args.len() == 3 || args.len() == 4) && args[1] == "--headless" {
+        let hex = args.get(3).map_or(false, |arg| arg == "--hex");
+        headless_game_of_life(args[2].parse().expect("Invalid number of generations."), hex);
+    } else {
+        game_of_life();
+    }
 }
 
 #[cfg(test)]
@@ -118,9 +150,51 @@ mod tests {
     extern crate rand;
 
     use gameoflife;
+    use gameoflife::Topology;
+    use reactivers::engine;
+    use reactivers::engine::{Backoff, RuntimeConfig};
+    use reactivers::engine::process::*;
+    use reactivers::engine::signal::*;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use gameoflife::ruleset::{Ruleset, RulesetError};
+    use gameoflife::watcher::StringWatcher;
     use tests::test::Bencher;
     use rand::distributions::IndependentSample;
 
+    /// Runs 10 generations of the cells of a random 100x100 grid on 4 workers, whose idle workers
+    /// follow `backoff`.
+    fn run_simulation_backoff(backoff: Backoff) {
+        let (n, m) = (100, 100);
+        let mut starting_grid = vec!();
+        let mut rng = rand::thread_rng();
+        let between = rand::distributions::Range::new(0f64, 1f64);
+        let p = 0.2;
+
+        for _ in 0..n {
+            let mut line = vec!();
+            for _ in 0..m {
+                line.push(between.ind_sample(&mut rng) < p);
+            }
+            starting_grid.push(line);
+        }
+
+        let (cells, _) = gameoflife::create_cells(&starting_grid, Ruleset::conway(), Topology::Square { wrap: false });
+        let config = RuntimeConfig::new().workers(4).max_iters(10).backoff(backoff);
+        // The cells never stop: the execution always reaches the limit of instants.
+        let _ = engine::execute_with_config(multi_join(cells), config);
+    }
+
+    #[bench]
+    fn bench_simulation_backoff_yield(b: &mut Bencher) {
+        b.iter(|| run_simulation_backoff(Backoff::Yield));
+    }
+
+    #[bench]
+    fn bench_simulation_backoff_sleep(b: &mut Bencher) {
+        b.iter(|| run_simulation_backoff(Backoff::Sleep(Duration::from_millis(1))));
+    }
+
     #[bench]
     fn bench_simulation_1_worker(b: &mut Bencher) {
         b.iter(|| {
@@ -138,7 +212,7 @@ mod tests {
                 starting_grid.push(line);
             }
 
-            gameoflife::run_simulation_steps(starting_grid, None, 1, 10);
+            gameoflife::run_simulation_steps(starting_grid, None::<StringWatcher>, Ruleset::conway(), Topology::Square { wrap: false }, 1, 10);
         });
     }
 
@@ -160,7 +234,7 @@ mod tests {
                 starting_grid.push(line);
             }
 
-            gameoflife::run_simulation_steps(starting_grid, None, 2, 10);
+            gameoflife::run_simulation_steps(starting_grid, None::<StringWatcher>, Ruleset::conway(), Topology::Square { wrap: false }, 2, 10);
         });
     }
 
@@ -182,7 +256,7 @@ mod tests {
                 starting_grid.push(line);
             }
 
-            gameoflife::run_simulation_steps(starting_grid, None, 4, 10);
+            gameoflife::run_simulation_steps(starting_grid, None::<StringWatcher>, Ruleset::conway(), Topology::Square { wrap: false }, 4, 10);
         });
     }
 
@@ -204,8 +278,158 @@ mod tests {
                 starting_grid.push(line);
             }
 
-            gameoflife::run_simulation_steps(starting_grid, None, 8, 10);
+            gameoflife::run_simulation_steps(starting_grid, None::<StringWatcher>, Ruleset::conway(), Topology::Square { wrap: false }, 8, 10);
         });
     }
-}
 
+    #[test]
+    fn test_extinction() {
+        // A single isolated cell dies at the first generation.
+        let mut starting_grid = vec![vec![false; 5]; 5];
+        starting_grid[2][2] = true;
+
+        assert_eq!(gameoflife::run_simulation_until_extinction(starting_grid.clone(), None::<StringWatcher>, Ruleset::conway(), Topology::Square { wrap: false }, 2, 100, 1), Some(1));
+        assert_eq!(gameoflife::run_simulation_until_extinction(starting_grid, None::<StringWatcher>, Ruleset::conway(), Topology::Square { wrap: false }, 2, 100, 3), Some(1));
+
+        // A blinker never dies out.
+        let mut starting_grid = vec![vec![false; 5]; 5];
+        for x in 1..4 {
+            starting_grid[x][2] = true;
+        }
+        assert_eq!(gameoflife::run_simulation_until_extinction(starting_grid, None::<StringWatcher>, Ruleset::conway(), Topology::Square { wrap: false }, 2, 20, 1), None);
+    }
+
+    #[test]
+    fn test_ruleset() {
+        assert_eq!("B3/S23".parse(), Ok(Ruleset::conway()));
+
+        let highlife: Ruleset = "B36/S23".parse().unwrap();
+        assert!(highlife.next_status(false, 6));
+        assert!(!Ruleset::conway().next_status(false, 6));
+        assert_eq!(highlife.to_string(), "B36/S23");
+
+        let seeds: Ruleset = "B2/S".parse().unwrap();
+        assert!(seeds.survive.is_empty());
+        assert!(!seeds.next_status(true, 2));
+        assert!(seeds.next_status(false, 2));
+
+        assert_eq!("B3S23".parse::<Ruleset>(), Err(RulesetError::Malformed));
+        assert_eq!("S23/B3".parse::<Ruleset>(), Err(RulesetError::Malformed));
+        assert_eq!("B3/S2x".parse::<Ruleset>(), Err(RulesetError::Malformed));
+        assert_eq!("B3/S23/B4".parse::<Ruleset>(), Err(RulesetError::Malformed));
+        assert_eq!("B39/S23".parse::<Ruleset>(), Err(RulesetError::CountTooLarge(9)));
+
+        assert_eq!("B2/S34H".parse(), Ok(Ruleset::hex()));
+        assert_eq!(Ruleset::hex().to_string(), "B2/S34H");
+        assert_eq!("B2/S37H".parse::<Ruleset>(), Err(RulesetError::CountTooLarge(7)));
+
+        // Two neighbor cells die out with Conway's rules, but survive forever with B3/S1.
+        let mut starting_grid = vec![vec![false; 5]; 5];
+        starting_grid[2][2] = true;
+        starting_grid[2][3] = true;
+        assert_eq!(gameoflife::run_simulation_until_extinction(starting_grid.clone(), None::<StringWatcher>, Ruleset::conway(), Topology::Square { wrap: false }, 2, 20, 1), Some(1));
+        let pairs = "B3/S1".parse().unwrap();
+        assert_eq!(gameoflife::run_simulation_until_extinction(starting_grid, None::<StringWatcher>, pairs, Topology::Square { wrap: false }, 2, 20, 1), None);
+    }
+
+    #[test]
+    fn test_string_watcher() {
+        // A blinker oscillates between a vertical and a horizontal line.
+        let mut starting_grid = vec![vec![false; 5]; 5];
+        for x in 1..4 {
+            starting_grid[x][2] = true;
+        }
+
+        let watcher = StringWatcher::new(5, 5);
+        gameoflife::run_simulation_steps(starting_grid, Some(watcher.clone()), Ruleset::conway(), Topology::Square { wrap: false }, 2, 3);
+
+        let frames = watcher.frames();
+        assert!(frames.len() >= 2);
+        assert_eq!(frames[0], ".....\n..#..\n..#..\n..#..\n.....\n");
+        assert_eq!(frames[1], ".....\n.....\n.###.\n.....\n.....\n");
+    }
+
+    /// Runs a simulation for `max_iters` steps, and returns the successive grids.
+    /// The generations in which no cell is alive are not recorded.
+    fn run_simulation_history(starting_grid: Vec<Vec<bool>>, ruleset: Ruleset, topology: Topology, max_iters: i32) -> Vec<Vec<Vec<bool>>> {
+        let (n, m) = (starting_grid.len(), starting_grid[0].len());
+        let (cell_processes, single_consumer) = gameoflife::create_cells(&starting_grid, ruleset, topology);
+
+        let history = Arc::new(Mutex::new(vec!()));
+        let history2 = history.clone();
+        let record = move |data: Vec<(usize, usize)>| {
+            let mut grid = vec![vec![false; m]; n];
+            for (x, y) in data {
+                grid[n-1-x][m-1-y] = true;
+            }
+            history2.lock().unwrap().push(grid);
+        };
+        let recorder = single_consumer.await_in().map(record).loop_inf();
+
+        engine::execute_process_steps(recorder.join(multi_join(cell_processes)), 2, max_iters);
+
+        let history = history.lock().unwrap().clone();
+        history
+    }
+
+    #[test]
+    fn test_wrap() {
+        // A glider moving one cell down and right every 4 generations, on a 6*6 grid.
+        let (n, m) = (6, 6);
+        let mut starting_grid = vec![vec![false; m]; n];
+        for &(x, y) in [(0, 1), (1, 2), (2, 0), (2, 1), (2, 2)].iter() {
+            starting_grid[x][y] = true;
+        }
+
+        let topology = Topology::Square { wrap: true };
+        let history = run_simulation_history(starting_grid.clone(), Ruleset::conway(), topology, 30);
+        assert!(history.len() > 24);
+
+        // The glider keeps its 5 cells, and lies on both sides of the grid while crossing the edges.
+        let alive_count = |grid: &Vec<Vec<bool>>| grid.iter().flat_map(|line| line.iter()).filter(|&&c| c).count();
+        assert!(history[..25].iter().all(|grid| alive_count(grid) == 5));
+        let crossing = |grid: &Vec<Vec<bool>>| grid[0].iter().any(|&c| c) && grid[n-1].iter().any(|&c| c);
+        assert!(history[..25].iter().any(crossing));
+
+        // After crossing, it re-enters from the opposite edges and gets back to its position.
+        assert_eq!(history[24], starting_grid);
+
+        // Without wrapping, the glider crashes into the corner.
+        let topology = Topology::Square { wrap: false };
+        let history = run_simulation_history(starting_grid.clone(), Ruleset::conway(), topology, 30);
+        assert!(history.len() <= 24 || history[24] != starting_grid);
+    }
+
+    #[test]
+    fn test_hex() {
+        // With B2/S34H, two neighbor cells die and give birth to the two cells neighboring both of
+        // them, which form a pair oscillating with period 2.
+        let cells = |alive: &[(usize, usize)]| {
+            let mut grid = vec![vec![false; 5]; 5];
+            for &(x, y) in alive {
+                grid[x][y] = true;
+            }
+            grid
+        };
+
+        // A pair in an even row: the common neighbors lie on the column of the right cell.
+        let history = run_simulation_history(cells(&[(2, 2), (2, 3)]), Ruleset::hex(), Topology::Hex, 12);
+        assert!(history.len() >= 3);
+        assert_eq!(history[1], cells(&[(1, 2), (3, 2)]));
+        assert_eq!(history[2], cells(&[(2, 2), (2, 3)]));
+
+        // A pair across an odd row, whose neighbors in the row below are shifted to the right.
+        let history = run_simulation_history(cells(&[(1, 1), (2, 2)]), Ruleset::hex(), Topology::Hex, 12);
+        assert!(history.len() >= 3);
+        assert_eq!(history[1], cells(&[(1, 2), (2, 1)]));
+        assert_eq!(history[2], cells(&[(1, 1), (2, 2)]));
+
+        // The odd rows are shifted when rendering the grid.
+        let watcher = StringWatcher::hex(5, 5);
+        gameoflife::run_simulation_steps(cells(&[(2, 2), (2, 3)]), Some(watcher.clone()), Ruleset::hex(), Topology::Hex, 2, 3);
+        let frames = watcher.frames();
+        assert!(frames.len() >= 2);
+        assert_eq!(frames[0], ". . . . .\n . . . . .\n. . # # .\n . . . . .\n. . . . .\n");
+        assert_eq!(frames[1], ". . . . .\n . . # . .\n. . . . .\n . . # . .\n. . . . .\n");
+    }
+}
@@ -2,13 +2,17 @@ extern crate reactivers;
 extern crate itertools;
 
 use reactivers::engine::process::*;
-use reactivers::engine::signal::value_signal::ValueSignal;
+use reactivers::engine::signal::counter_signal::CounterSignal;
 use reactivers::engine::signal::mpsc_signal::MPSCSignalSender;
 use reactivers::engine::signal::*;
 
 
 use self::itertools::Itertools;
 
+use std::sync::Arc;
+
+use super::ruleset::Ruleset;
+
 /// A cell in the game of life, it can be alive or dead.
 pub struct GameCell {
     /// Current status of the cell.
@@ -23,25 +27,17 @@ impl GameCell {
         }
     }
 
-    /// Update cell according to own status, alive neighbor count and the rules of the game of life.
-    pub fn update(&mut self, alive_neighbor_count: i32) -> bool {
-        if self.status_is_alive {
-            if (alive_neighbor_count <= 1) || (alive_neighbor_count >= 4) {
-                self.status_is_alive = false
-            }
-        } else {
-            if alive_neighbor_count == 3 {
-                self.status_is_alive = true
-            }
-        };
+    /// Update cell according to own status, alive neighbor count and the rules of the game.
+    pub fn update(&mut self, alive_neighbor_count: usize, ruleset: &Ruleset) -> bool {
+        self.status_is_alive = ruleset.next_status(self.status_is_alive, alive_neighbor_count);
         self.status_is_alive
     }
 
-    /// Consume self to create a reactive process that will live according to the rules of the game
-    /// of life.
+    /// Consume self to create a reactive process that will live according to the given rules.
     pub fn process(mut self,
-                   life_signal: ValueSignal<(), i32>,
-                   neighbors_signal: Vec<ValueSignal<(), i32>>,
+                   ruleset: Arc<Ruleset>,
+                   life_signal: CounterSignal,
+                   neighbors_signal: Vec<CounterSignal>,
                    (status_signal, x, y): (MPSCSignalSender<(usize, usize), Vec<(usize, usize)>>, usize, usize)) -> impl Process<Value=()> {
         // A vector of processes, each process being the transmission of the alive signal to a neighbor.
         let write_neighbors = neighbors_signal.iter().map(|signal| {
@@ -59,19 +55,16 @@ impl GameCell {
         let status_is_alive = self.status_is_alive;
 
         // A continuation that updates internal structure according to the number of alive neighbors.
-        let update_cell = move |alive_neighbor_count: i32| {
-            self.update(alive_neighbor_count)
+        let update_cell = move |alive_neighbor_count: usize| {
+            self.update(alive_neighbor_count, &ruleset)
         };
 
-        // A continuation that ignores input and returns unit (for type checker).
-        let cont_unit = |_| ();
-
         let main_loop =
             life_signal
                 .await_in() // Wait for neighbors to tell if they're alive.
                 .map(update_cell) // Update own status
                 .then_else( // If cell is alive
-                    send_status_alive2.multi_join(write_neighbors2).map(cont_unit), // Send life signal to neighbors and watcher
+                    send_status_alive2.multi_join(write_neighbors2).discard(), // Send life signal to neighbors and watcher
                     value(()) // Else do nothing
                 )
                 .loop_inf();
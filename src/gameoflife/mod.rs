@@ -2,17 +2,22 @@ extern crate reactivers;
 extern crate itertools;
 
 use reactivers::engine::signal::*;
-use reactivers::engine::signal::value_signal::ValueSignal;
+use reactivers::engine::signal::counter_signal::CounterSignal;
+use reactivers::engine::signal::mpsc_signal::MPSCSignalReceiver;
 use reactivers::engine::process::*;
 use reactivers::engine;
 
 mod cell;
+pub mod ruleset;
 pub mod watcher;
 
 use self::cell::*;
+use self::ruleset::Ruleset;
 use self::watcher::*;
 use self::itertools::Itertools;
 
+use std::sync::Arc;
+
 /// Check if coordinates (x,y) are in a n*m grid.
 pub fn is_valid(x: isize, y: isize, n: usize, m: usize) -> bool {
     return x >= 0 && y >= 0 && x < n as isize && y < m as isize;
@@ -35,17 +40,92 @@ pub fn grid_to_data (starting_grid: &Vec<Vec<bool>>) -> Vec<(usize, usize)>{
     data_vec
 }
 
-pub fn run_simulation (starting_grid: Vec<Vec<bool>>, watcher: Option<TerminalWatcher>) {
-    run_simulation_steps(starting_grid, watcher, 4, -1);
+/// Number of consecutive empty generations after which the simulation is stopped.
+pub const EXTINCTION_GENERATIONS: usize = 1;
+
+/// Layout of the grid, which defines the neighbors of each cell.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Topology {
+    /// Square cells, with up to 8 neighbors. If `wrap` is true, the edges of the grid are
+    /// connected: the left edge to the right one, and the top edge to the bottom one.
+    Square { wrap: bool },
+    /// Hexagonal cells, with up to 6 neighbors. The lines of the grid are rows of the hexagonal
+    /// grid, the odd rows being shifted by half a cell towards the greater columns.
+    Hex,
+}
+
+impl Topology {
+    /// Returns the coordinates of the neighbors of cell (x, y) in a n*m grid.
+    /// On a wrapped grid smaller than 3*3, a cell is never its own neighbor, and is a neighbor of
+    /// another cell at most once.
+    pub fn neighbors(&self, x: usize, y: usize, n: usize, m: usize) -> Vec<(usize, usize)> {
+        let mut neighbors = vec!();
+        match *self {
+            Topology::Square { wrap } => {
+                for px in -1..2 {
+                    for py in -1..2 {
+                        let (nx, ny) = (x as isize + px, y as isize + py);
+                        let neighbor = if wrap {
+                            let (n, m) = (n as isize, m as isize);
+                            (((nx + n) % n) as usize, ((ny + m) % m) as usize)
+                        } else if is_valid(nx, ny, n, m) {
+                            (nx as usize, ny as usize)
+                        } else {
+                            continue;
+                        };
+
+                        if neighbor != (x, y) && !neighbors.contains(&neighbor) {
+                            neighbors.push(neighbor);
+                        }
+                    }
+                }
+            },
+            Topology::Hex => {
+                // The rows above and below overlap the columns y - 1 and y on even rows, and the
+                // columns y and y + 1 on odd rows.
+                let shift = (x % 2) as isize;
+                let offsets = [(0, -1), (0, 1), (-1, shift - 1), (-1, shift), (1, shift - 1), (1, shift)];
+                for &(px, py) in offsets.iter() {
+                    let (nx, ny) = (x as isize + px, y as isize + py);
+                    if is_valid(nx, ny, n, m) {
+                        neighbors.push((nx as usize, ny as usize));
+                    }
+                }
+            },
+        }
+        neighbors
+    }
+
+    /// Returns the maximum number of neighbors of a cell.
+    pub fn neighbor_count(&self) -> usize {
+        match *self {
+            Topology::Square { .. } => 8,
+            Topology::Hex => 6,
+        }
+    }
+}
+
+pub fn run_simulation<W> (starting_grid: Vec<Vec<bool>>, watcher: Option<W>) where W: Watcher {
+    run_simulation_steps(starting_grid, watcher, Ruleset::conway(), Topology::Square { wrap: false }, 4, -1);
+}
+
+/// Run a simulation, with a given starting grid, the rules of the game, the topology of the grid and
+/// a watcher process that can render what is happening.
+pub fn run_simulation_steps<W> (starting_grid: Vec<Vec<bool>>, watcher: Option<W>, ruleset: Ruleset,
+                                topology: Topology, n_workers: usize, max_iters: i32) where W: Watcher
+{
+    run_simulation_until_extinction(starting_grid, watcher, ruleset, topology, n_workers, max_iters,
+                                    EXTINCTION_GENERATIONS);
 }
 
-/// Run a simulation, with a given starting grid and a watcher process that can render what is happening.
-pub fn run_simulation_steps (starting_grid: Vec<Vec<bool>>, watcher: Option<TerminalWatcher>, n_workers: usize, max_iters: i32)
+/// Creates the processes of the cells of the grid, and the signal on which the coordinates of the
+/// alive cells are emitted at each generation, in the format of `grid_to_data`.
+pub fn create_cells (starting_grid: &Vec<Vec<bool>>, ruleset: Ruleset, topology: Topology)
+    -> (Vec<impl Process<Value=()>>, MPSCSignalReceiver<(usize, usize), Vec<(usize, usize)>>)
 {
+    assert_eq!(ruleset.neighbors as usize, topology.neighbor_count(),
+               "The ruleset {} does not match the topology of the grid.", ruleset);
     let n = starting_grid.len();
-    if n == 0 {
-        return;
-    }
     let m = starting_grid[0].len();
 
     // Create the signal that the renderer will listen on.
@@ -57,32 +137,23 @@ pub fn run_simulation_steps (starting_grid: Vec<Vec<bool>>, watcher: Option<Term
     // Create cells and associated signals.
     let mut cell_signal_grid = starting_grid.iter().map(|line| {
         line.iter().map(|start_status| {
-            (GameCell::new(*start_status), value_signal::new(0, |(), y| 1 + y), multi_producer.clone())
+            (GameCell::new(*start_status), counter_signal::new(), multi_producer.clone())
         }).collect_vec()
     }).collect_vec();
 
     // Create for each cell references to neighbor signals.
     let mut neighbors_grid = starting_grid.iter().enumerate().map(|(x, line)| {
         let neighbors_line = line.iter().enumerate().map(|(y, _)| {
-            let mut ref_signals: Vec<ValueSignal<(), i32>> = vec!();
-
-            for px in -1..2 {
-                for py in -1..2 {
-                    if is_valid(x as isize + px, y as isize + py, n, m) && (px != 0 || py != 0)   {
-                        let x_as_usize = (x as isize + px) as usize;
-                        let y_as_usize = (y as isize + py) as usize;
-
-                        let (_, ref signal, _) = cell_signal_grid[x_as_usize][y_as_usize];
-                        ref_signals.push(signal.clone())
-                    }
-                }
-            };
-            ref_signals
+            topology.neighbors(x, y, n, m).into_iter().map(|(nx, ny)| {
+                let (_, ref signal, _) = cell_signal_grid[nx][ny];
+                signal.clone()
+            }).collect::<Vec<CounterSignal>>()
         }).collect_vec();
         neighbors_line
     }).collect_vec();
 
     // Create processes.
+    let ruleset = Arc::new(ruleset);
     let mut cell_processes = vec!();
     let mut i = 0;
     while let Some(mut cell_signal_line) = cell_signal_grid.pop() {
@@ -93,22 +164,68 @@ pub fn run_simulation_steps (starting_grid: Vec<Vec<bool>>, watcher: Option<Term
         while let Some((cell, signal, status_emitter)) = cell_signal_line.pop() {
 
             let mut neighbors = neighbors_line.pop().unwrap();
-            cell_processes.push(cell.process(signal, neighbors, (status_emitter, i, j)));
+            cell_processes.push(cell.process(ruleset.clone(), signal, neighbors, (status_emitter, i, j)));
             j += 1;
         }
 
         i += 1;
     };
 
+    (cell_processes, single_consumer)
+}
+
+/// Run a simulation, and stop it once no cell has been alive for `extinction_generations`
+/// consecutive generations.
+/// Returns the first generation of this extinction, or `None` if it did not happen within
+/// `max_iters` steps.
+pub fn run_simulation_until_extinction<W> (starting_grid: Vec<Vec<bool>>, watcher: Option<W>, ruleset: Ruleset,
+                                           topology: Topology, n_workers: usize, max_iters: i32,
+                                           extinction_generations: usize) -> Option<usize>
+    where W: Watcher
+{
+    let n = starting_grid.len();
+    if n == 0 {
+        return Some(0);
+    }
+
+    let (cell_processes, single_consumer) = create_cells(&starting_grid, ruleset, topology);
+
+    // Create the extinction detector. Generation `g` is emitted on the watcher signal at instant
+    // `g`, and each iteration of the detector moves to the next instant.
+    let mut generation = 0;
+    let mut empty_count = 0;
+    let check_extinction = move |alive: bool| {
+        generation += 1;
+        if alive {
+            empty_count = 0;
+        } else {
+            empty_count += 1;
+            if empty_count >= extinction_generations {
+                return LoopStatus::Exit(generation - extinction_generations);
+            }
+        }
+        LoopStatus::Continue
+    };
+    let extinct = puresignal::new();
+    let extinct_emitter = extinct.clone();
+    let detector =
+        single_consumer
+            .present(value(true).pause(), value(false))
+            .map(check_extinction)
+            .loop_while()
+            .and_then(move |generation: usize| { // Stop the simulation.
+                extinct_emitter.emit(value(())).map(move |()| generation)
+            });
+
     if let Some(watcher) = watcher {
         // Create renderer process.
-        let watcher_process = watcher.process(single_consumer);
+        let watcher_process = watcher::process(watcher, single_consumer);
         // Combine processes.
-        let simulation_process = watcher_process.multi_join(cell_processes);
+        let simulation_process = extinct.do_until(watcher_process.multi_join(cell_processes)).join(detector);
         // Run the thing
-        engine::execute_process_steps(simulation_process, n_workers, max_iters);
+        engine::execute_process_steps(simulation_process, n_workers, max_iters).map(|(_, generation)| generation)
     } else {
-        let simulation_process = multi_join(cell_processes);
-        engine::execute_process_steps(simulation_process, n_workers, max_iters);
+        let simulation_process = extinct.do_until(multi_join(cell_processes)).join(detector);
+        engine::execute_process_steps(simulation_process, n_workers, max_iters).map(|(_, generation)| generation)
     }
 }
@@ -0,0 +1,130 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::str::FromStr;
+
+/// Rules of a life-like cellular automaton, in B/S notation: a dead cell becomes alive if its
+/// number of alive neighbors is in `born`, and an alive cell stays alive if it is in `survive`.
+/// The cells of the simulation are only updated when some neighbor is alive, hence the counts 0
+/// have no effect there.
+/// The rules are meant for cells with `neighbors` neighbors: 8 on a square grid, or 6 on a
+/// hexagonal one, noted with a `H` suffix, e.g. `B2/S34H`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Ruleset {
+    /// Numbers of alive neighbors for which a dead cell becomes alive.
+    pub born: HashSet<u8>,
+    /// Numbers of alive neighbors for which an alive cell stays alive.
+    pub survive: HashSet<u8>,
+    /// Number of neighbors of a cell, which bounds the counts.
+    pub neighbors: u8,
+}
+
+/// Reasons why a ruleset could not be parsed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RulesetError {
+    /// The string does not follow the `B<digits>/S<digits>` notation.
+    Malformed,
+    /// A count is larger than the number of neighbors of a cell.
+    CountTooLarge(u8),
+}
+
+impl fmt::Display for RulesetError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            RulesetError::Malformed => write!(f, "Malformed ruleset, expected B<digits>/S<digits>."),
+            RulesetError::CountTooLarge(n) => write!(f, "Neighbor count {} is larger than the number of neighbors.", n),
+        }
+    }
+}
+
+impl Ruleset {
+    /// Returns the rules of Conway's game of life, B3/S23.
+    pub fn conway() -> Ruleset {
+        Ruleset {
+            born: [3].iter().cloned().collect(),
+            survive: [2, 3].iter().cloned().collect(),
+            neighbors: 8,
+        }
+    }
+
+    /// Returns the rules of the hexagonal game of life B2/S34H, for cells with 6 neighbors.
+    pub fn hex() -> Ruleset {
+        Ruleset {
+            born: [2].iter().cloned().collect(),
+            survive: [3, 4].iter().cloned().collect(),
+            neighbors: 6,
+        }
+    }
+
+    /// Returns the next status of a cell, given its status and its number of alive neighbors.
+    pub fn next_status(&self, is_alive: bool, alive_neighbor_count: usize) -> bool {
+        let count = alive_neighbor_count as u8;
+        if is_alive {
+            self.survive.contains(&count)
+        } else {
+            self.born.contains(&count)
+        }
+    }
+}
+
+impl Default for Ruleset {
+    fn default() -> Ruleset {
+        Ruleset::conway()
+    }
+}
+
+/// Parses the neighbor counts following `prefix` in `part`, which cannot exceed `neighbors`.
+fn parse_counts(part: &str, prefix: char, neighbors: u8) -> Result<HashSet<u8>, RulesetError> {
+    let mut chars = part.chars();
+    if chars.next() != Some(prefix) {
+        return Err(RulesetError::Malformed);
+    }
+
+    let mut counts = HashSet::new();
+    for c in chars {
+        let n = c.to_digit(10).ok_or(RulesetError::Malformed)? as u8;
+        if n > neighbors {
+            return Err(RulesetError::CountTooLarge(n));
+        }
+        counts.insert(n);
+    }
+    Ok(counts)
+}
+
+impl FromStr for Ruleset {
+    type Err = RulesetError;
+
+    /// Parses a ruleset in B/S notation, e.g. `B3/S23` for Conway's game of life or `B2/S` for
+    /// Seeds. A `H` suffix denotes the rules of a hexagonal grid, e.g. `B2/S34H`.
+    fn from_str(s: &str) -> Result<Ruleset, RulesetError> {
+        let s = s.trim();
+        let (s, neighbors) = if s.ends_with('H') {
+            (&s[..s.len() - 1], 6)
+        } else {
+            (s, 8)
+        };
+
+        let mut parts = s.split('/');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(born), Some(survive), None) => {
+                Ok(Ruleset {
+                    born: parse_counts(born, 'B', neighbors)?,
+                    survive: parse_counts(survive, 'S', neighbors)?,
+                    neighbors,
+                })
+            },
+            _ => Err(RulesetError::Malformed),
+        }
+    }
+}
+
+impl fmt::Display for Ruleset {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let digits = |counts: &HashSet<u8>| {
+            let mut counts: Vec<_> = counts.iter().cloned().collect();
+            counts.sort();
+            counts.iter().map(|n| n.to_string()).collect::<String>()
+        };
+        let suffix = if self.neighbors == 6 { "H" } else { "" };
+        write!(f, "B{}/S{}{}", digits(&self.born), digits(&self.survive), suffix)
+    }
+}
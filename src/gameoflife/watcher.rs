@@ -5,7 +5,27 @@ use reactivers::engine::process::*;
 use reactivers::engine::signal::mpsc_signal::MPSCSignalReceiver;
 use reactivers::engine::signal::*;
 
-/// Watcher structure that will listen to a signal to render game status.
+use std::sync::{Arc, Mutex};
+
+/// A renderer of the game status, called at each generation.
+pub trait Watcher: Send + 'static {
+    /// Renders the game status, data being the list of alive cells in the format of
+    /// `grid_to_data`.
+    fn render(&mut self, data: Vec<(usize, usize)>);
+}
+
+/// Consumes `watcher` to create a reactive process that will listen to `alive_signal` to render
+/// the game status.
+pub fn process<W>(mut watcher: W, alive_signal: MPSCSignalReceiver<(usize, usize), Vec<(usize, usize)>>)
+    -> impl Process<Value=()> where W: Watcher
+{
+    let render = move |data: Vec<(usize, usize)>| {
+        watcher.render(data);
+    };
+    alive_signal.await_in().map(render).loop_inf()
+}
+
+/// Watcher structure that renders the game status in the terminal.
 pub struct TerminalWatcher {
     auto: bool,
     width: i32,
@@ -40,33 +60,75 @@ impl TerminalWatcher {
         ncurses::wrefresh(win);
         (start_y, start_x, win)
     }
+}
 
-    /// Consumes self to create a reactive process that will listen to `alive_signal` to render the game status.
-    pub fn process(mut self, alive_signal: MPSCSignalReceiver<(usize, usize), Vec<(usize, usize)>>) -> impl Process<Value=()> {
+impl Watcher for TerminalWatcher {
+    /// Renders the game status, then waits for the next step. In automatic mode, the steps are
+    /// separated by 500ms, in manual mode the next step waits for an input.
+    fn render(&mut self, data: Vec<(usize, usize)>) {
         if self.auto {
-            ncurses::timeout(500); // In automatic mode, 500ms between steps.
+            ncurses::timeout(500);
         } else {
-            ncurses::timeout(-1);// In manual mode, wait for input.
+            ncurses::timeout(-1);
         }
 
-        let show_and_sleep_cont = move |data: Vec<(usize, usize)>| {
-            self.render_grid(data);
-
-            let chr = ncurses::getch();
-            if chr == 'a' as i32 || chr == ' ' as i32 {
-                self.auto = !self.auto;
-
-                if self.auto {
-                    ncurses::timeout(500);
-                } else {
-                    ncurses::timeout(-1);
-                }
-            } else if chr == 'q' as i32 {
-                ncurses::endwin();
-                panic!("Exited.");
-            }
+        self.render_grid(data);
+
+        let chr = ncurses::getch();
+        if chr == 'a' as i32 || chr == ' ' as i32 {
+            self.auto = !self.auto;
+        } else if chr == 'q' as i32 {
+            ncurses::endwin();
+            panic!("Exited.");
+        }
+    }
+}
+
+/// Watcher rendering each generation into a string, with one line per row of the grid, `#` for
+/// alive cells and `.` for dead ones. The frames are shared with the clones of the watcher, so that
+/// they can be read once the watcher has been consumed by the simulation.
+#[derive(Clone)]
+pub struct StringWatcher {
+    width: usize,
+    height: usize,
+    hex: bool,
+    frames: Arc<Mutex<Vec<String>>>,
+}
+
+impl StringWatcher {
+    /// Creates a new `StringWatcher` for a `width`*`height` grid.
+    pub fn new(width: usize, height: usize) -> StringWatcher {
+        StringWatcher { width, height, hex: false, frames: Arc::new(Mutex::new(vec!())) }
+    }
+
+    /// Creates a new `StringWatcher` for a `width`*`height` hexagonal grid. The cells are
+    /// separated by spaces, and the odd rows are shifted by one character, as in `Topology::Hex`.
+    pub fn hex(width: usize, height: usize) -> StringWatcher {
+        StringWatcher { hex: true, ..StringWatcher::new(width, height) }
+    }
+
+    /// Returns the frames rendered so far.
+    pub fn frames(&self) -> Vec<String> {
+        self.frames.lock().unwrap().clone()
+    }
+}
 
-        };
-        alive_signal.await_in().map(show_and_sleep_cont).loop_inf()
+impl Watcher for StringWatcher {
+    fn render(&mut self, data: Vec<(usize, usize)>) {
+        // The cells are indexed like the starting grid, line x being a row of the frame.
+        let mut grid = vec![vec!['.'; self.height]; self.width];
+        for (x, y) in data {
+            grid[self.width - 1 - x][self.height - 1 - y] = '#';
+        }
+        let hex = self.hex;
+        let frame = grid.into_iter().enumerate().map(|(x, line)| {
+            if hex {
+                let shift = if x % 2 == 1 { " " } else { "" };
+                shift.to_string() + &line.iter().map(|c| c.to_string()).collect::<Vec<_>>().join(" ") + "\n"
+            } else {
+                line.into_iter().collect::<String>() + "\n"
+            }
+        }).collect();
+        self.frames.lock().unwrap().push(frame);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,531 @@
+//! Directed graphs with shortest path search, parameterized over the information carried by
+//! nodes and edges.
+
+/// Node identifier
+pub type NodeId = usize;
+
+/// Edge Identifier
+pub type EdgeId = usize;
+
+/// Node of the graph, carrying some information of type `NodeInfo`.
+#[derive(Clone)]
+pub struct Node<NodeInfo> {
+    id: NodeId,         // Node identifier.
+    info: NodeInfo,     // Information attached to the node.
+    edges: Vec<EdgeId>, // Edges accesible from this node.
+}
+
+/// Edge of the graph, carrying some information of type `EdgeInfo`.
+#[derive(Copy, Clone)]
+pub struct Edge<EdgeInfo> {
+    id: EdgeId,             // Edge identifier.
+    info: EdgeInfo,         // Information attached to the edge.
+    source: NodeId,         // Source node of the edge.
+    destination: NodeId,    // Destination node of the edge.
+    removed: bool,          // Indicates if the edge has been removed from the graph.
+}
+
+/// Graph structure.
+#[derive(Clone)]
+pub struct Graph<NodeInfo, EdgeInfo> {
+    node_count: usize,
+    edge_count: usize,
+    pub nodes: Vec<Node<NodeInfo>>,
+    pub edges: Vec<Edge<EdgeInfo>>,
+}
+
+/// Weight for edges.
+pub type Weight = f32;
+
+/// Structure to save the weights of the edges.
+pub struct EdgesWeight {
+    pub weights: Vec<Weight>,
+}
+
+/// Edge information that can be used as an index in an `EdgesWeight` structure.
+pub trait EdgeIndex {
+    /// Returns the index of the weight of the edge.
+    fn edge_index(&self) -> usize;
+}
+
+macro_rules! impl_edge_index {
+    ($($t:ty),*) => {
+        $(impl EdgeIndex for $t {
+            fn edge_index(&self) -> usize {
+                *self as usize
+            }
+        })*
+    };
+}
+
+impl_edge_index!(u8, u16, u32, u64, usize);
+
+impl<NodeInfo> Node<NodeInfo> {
+    /// Creates a new node from the specified information.
+    pub fn new(id: NodeId, info: NodeInfo) -> Node<NodeInfo> {
+        Node { id, info, edges: vec!() }
+    }
+
+    /// Adds an edge to the node.
+    pub fn add_edge(&mut self, edge: EdgeId) {
+        self.edges.push(edge);
+    }
+
+    /// Returns the identifier of the node.
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// Returns the information of the node.
+    pub fn info(&self) -> &NodeInfo {
+        &self.info
+    }
+
+    /// Returns the edges that are accessible from the node.
+    pub fn edges(&self) -> &Vec<EdgeId> {
+        &self.edges
+    }
+}
+
+impl<EdgeInfo> Edge<EdgeInfo> {
+    /// Creates a new edge from the specified information.
+    pub fn new(id: EdgeId, info: EdgeInfo, source: NodeId, destination: NodeId) -> Edge<EdgeInfo> {
+        Edge { id, info, source, destination, removed: false }
+    }
+
+    /// Returns the identifier of the edge.
+    pub fn id(&self) -> EdgeId {
+        self.id
+    }
+
+    /// Returns the information of the edge.s
+    pub fn info(&self) -> &EdgeInfo {
+        &self.info
+    }
+
+    /// Returns the source node of the edge.
+    pub fn source(&self) -> NodeId {
+        self.source
+    }
+
+    /// Returns the destination node of the edge.
+    pub fn destination(&self) -> NodeId {
+        self.destination
+    }
+
+    /// Returns true if the edge has been removed from the graph.
+    pub fn is_removed(&self) -> bool {
+        self.removed
+    }
+}
+
+
+impl<NodeInfo, EdgeInfo> Graph<NodeInfo, EdgeInfo> {
+    /// Returns a new empty graph.
+    pub fn new() -> Graph<NodeInfo, EdgeInfo> {
+        Graph { node_count: 0, edge_count: 0, nodes: vec!(), edges: vec!()}
+    }
+
+    /// Adds a node with corresponding information, and returns the created Node Identifier.
+    pub fn add_node(&mut self, info: NodeInfo) -> NodeId {
+        let node = Node::new(self.node_count, info);
+        let id = node.id();
+        // We create a fresh node identifier.
+        self.node_count += 1;
+        self.nodes.push(node);
+        id
+    }
+
+    /// Adds an edge in the graph between the specified nodes, with the specified information.
+    pub fn add_edge(&mut self, source: NodeId, destination: NodeId, info: EdgeInfo) {
+        let edge_id = self.edge_count;
+        let edge = Edge::new(edge_id, info, source, destination);
+        // We create a fresh edge identifier.
+        self.edge_count += 1;
+        self.edges.push(edge);
+        self.nodes[source].add_edge(edge_id);
+    }
+
+    /// Removes the specified edge: it is no longer accessible from its source node.
+    /// The edge is kept as a tombstone, so that the identifiers of the other edges do not change,
+    /// and `get_edge` still returns it, marked as removed. Removing an edge twice has no effect.
+    pub fn remove_edge(&mut self, edge: EdgeId) {
+        if self.edges[edge].removed {
+            return;
+        }
+        self.edges[edge].removed = true;
+        let source = self.edges[edge].source;
+        self.nodes[source].edges.retain(|&e| e != edge);
+    }
+
+    /// Returns the number of edges accessible from the specified node.
+    pub fn out_degree(&self, node: NodeId) -> usize {
+        self.nodes[node].edges.len()
+    }
+
+    /// Returns the specified node.
+    pub fn get_node(&self, node: NodeId) -> &Node<NodeInfo> {
+        &self.nodes[node]
+    }
+
+    /// Returns the specified edge.
+    pub fn get_edge(&self, edge: EdgeId) -> &Edge<EdgeInfo> {
+        &self.edges[edge]
+    }
+}
+
+use std::fmt;
+impl<NodeInfo, EdgeInfo> fmt::Display for Graph<NodeInfo, EdgeInfo> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for node in &self.nodes {
+            let _ = write!(f, "Node {}:", node.id());
+            for &edge_id in node.edges() {
+                let _ = write!(f, " {}", self.get_edge(edge_id).destination());
+            }
+            let _ = write!(f, "\n");
+        }
+        write!(f, "")
+    }
+}
+
+impl EdgesWeight {
+    /// Creates a new weights structure with the specified edges.
+    pub fn new(weights: Vec<Weight>) -> EdgesWeight {
+        EdgesWeight { weights }
+    }
+
+    /// Returns the index corresponding to the specified edge.
+    /// Here, this index is given by the EdgeInfo.
+    pub fn get_index<EdgeInfo: EdgeIndex>(&self, edge: &EdgeInfo) -> usize {
+        edge.edge_index()
+    }
+
+    /// Returns the weight of the specified edge.
+    pub fn get_weight<EdgeInfo: EdgeIndex>(&self, edge: &Edge<EdgeInfo>) -> Weight {
+        self.weights[self.get_index(edge.info())]
+    }
+}
+
+
+/*
+    Dijkstra Implementation
+    (Largely inspired from the Dijkstra example in rust documentation (std::collection::binary_heap)
+*/
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::f32;
+
+/// Couple (Weight, Node) to store in the heap of the Dijkstra algorithm.
+#[derive(Copy, Clone, PartialEq)]
+struct State {
+    cost: Weight,
+    node: NodeId,
+}
+
+impl Eq for State {}
+
+impl Ord for State {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // We reverse the order to make the heap become a min heap. Equal costs are ordered by node
+        // identifier, so that the same path is found at each run among the shortest ones.
+        other.cost.partial_cmp(&self.cost).unwrap()
+            .then_with(|| other.node.cmp(&self.node))
+    }
+}
+
+// `PartialOrd` needs to be implemented as well.
+impl PartialOrd for State {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Shortest path found by `dijkstra_full`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Path {
+    pub nodes: Vec<NodeId>, // Nodes of the path, from the source to the destination.
+    pub edges: Vec<EdgeId>, // Edges of the path, from the source to the destination.
+    pub cost: Weight,       // Total weight of the edges.
+}
+
+/// Dijkstra algorithm, returning the shortest path from `source` to the nearest node satisfying
+/// `f`, in source to destination order.
+///
+/// If the source satisfies `f`, the path has no edge, its only node is the source and its cost is
+/// 0. If no node satisfying `f` can be reached, the path has neither nodes nor edges, and its cost
+/// is `f32::MAX`.
+pub fn dijkstra_full<NodeInfo, EdgeInfo, F>(source: NodeId, f: F, graph: &Graph<NodeInfo, EdgeInfo>,
+                                            weights: &EdgesWeight) -> Path
+    where EdgeInfo: EdgeIndex, F: Fn(&NodeInfo) -> bool
+{
+    let n = graph.node_count;
+    let mut distances: Vec<Weight> = (0..n).map(|_| f32::MAX).collect();
+    let mut ancestors: Vec<(usize, usize)> = (0..n).map(|_| (0, 0)).collect();
+    let mut heap = BinaryHeap::new();
+    let mut dest = None;
+
+    distances[source] = 0.;
+    heap.push(State { cost: 0., node: source });
+
+    while let Some(State { cost, node }) = heap.pop() {
+        // Alternatively we could have continued to find all shortest paths
+        if f(graph.get_node(node).info()) {
+            dest = Some(node);
+            break;
+        }
+
+        // Important as we may have already found a better way
+        if cost > distances[node] {
+            continue;
+        }
+
+        // For each node we can reach, see if we can find a way with
+        // a lower cost going through this node
+        for edge_id in graph.get_node(node).edges() {
+            let edge = graph.get_edge(*edge_id);
+            let next = State { cost: cost + weights.get_weight(edge), node: edge.destination() };
+            // If so, add it to the frontier and continue
+            if next.cost < distances[next.node] {
+                heap.push(next);
+                // Relaxation, we have now found a better way
+                distances[next.node] = next.cost;
+                ancestors[next.node] = (node, *edge_id);
+            }
+        }
+    }
+
+    let mut dest = match dest {
+        Some(dest) => dest,
+        None => return Path { nodes: vec!(), edges: vec!(), cost: f32::MAX },
+    };
+
+    // We walk back from the destination, then put the path in the right order.
+    let cost = distances[dest];
+    let mut nodes = vec!(dest);
+    let mut edges = vec!();
+    while dest != source {
+        let (new_dest, edge) = ancestors[dest];
+        dest = new_dest;
+        nodes.push(dest);
+        edges.push(edge);
+    }
+    nodes.reverse();
+    edges.reverse();
+    Path { nodes, edges, cost }
+}
+
+/// Dijkstra algorithm. Returns the edges of the shortest path from the destination back to the
+/// source, and its cost. See `dijkstra_full`.
+pub fn dijkstra<NodeInfo, EdgeInfo, F>(source: NodeId, f: F, graph: &Graph<NodeInfo, EdgeInfo>,
+                                       weights: &EdgesWeight) -> (Vec<EdgeId>, Weight)
+    where EdgeInfo: EdgeIndex, F: Fn(&NodeInfo) -> bool + Sized
+{
+    let Path { mut edges, cost, .. } = dijkstra_full(source, f, graph, weights);
+    edges.reverse();
+    (edges, cost)
+}
+
+/// A* algorithm.
+///
+/// The heuristic must be a lower bound of the distance from a node to the nearest goal. With a
+/// heuristic always equal to zero, this gives the same results as `dijkstra`.
+pub fn astar<NodeInfo, EdgeInfo, F, H>(source: NodeId, f: F, heuristic: H,
+                                       graph: &Graph<NodeInfo, EdgeInfo>, weights: &EdgesWeight)
+    -> (Vec<EdgeId>, Weight)
+    where EdgeInfo: EdgeIndex, F: Fn(&NodeInfo) -> bool, H: Fn(&NodeInfo) -> Weight
+{
+    let n = graph.node_count;
+    let mut distances: Vec<Weight> = (0..n).map(|_| f32::MAX).collect();
+    let mut ancestors: Vec<(usize, usize)> = (0..n).map(|_| (0, 0)).collect();
+    let mut heap = BinaryHeap::new();
+    let mut dest = None;
+
+    distances[source] = 0.;
+    heap.push(State { cost: heuristic(graph.get_node(source).info()), node: source });
+
+    // The heap is ordered by the estimation of the total cost, while the distances vector keeps
+    // the cost of the best known path from the source.
+    while let Some(State { cost, node }) = heap.pop() {
+        let info = graph.get_node(node).info();
+        if f(info) {
+            dest = Some(node);
+            break;
+        }
+
+        // We may have already found a better way
+        let distance = distances[node];
+        if cost > distance + heuristic(info) {
+            continue;
+        }
+
+        for edge_id in graph.get_node(node).edges() {
+            let edge = graph.get_edge(*edge_id);
+            let next = edge.destination();
+            let next_distance = distance + weights.get_weight(edge);
+            if next_distance < distances[next] {
+                distances[next] = next_distance;
+                ancestors[next] = (node, *edge_id);
+                let estimate = next_distance + heuristic(graph.get_node(next).info());
+                heap.push(State { cost: estimate, node: next });
+            }
+        }
+    }
+
+    let mut dest = match dest {
+        Some(dest) => dest,
+        None => return (vec!(), f32::MAX),
+    };
+
+    let cost = distances[dest];
+    let mut path = vec!();
+    while dest != source {
+        let (new_dest, edge) = ancestors[dest];
+        dest = new_dest;
+        path.push(edge);
+    }
+    (path, cost)
+}
+
+#[test]
+fn test_dijkstra_generic() {
+    let mut graph: Graph<&str, u32> = Graph::new();
+    let a = graph.add_node("a");
+    let b = graph.add_node("b");
+    let c = graph.add_node("c");
+    let d = graph.add_node("d");
+
+    graph.add_edge(a, b, 0);
+    graph.add_edge(a, c, 1);
+    graph.add_edge(b, d, 2);
+    graph.add_edge(c, d, 3);
+    graph.add_edge(c, b, 4);
+
+    let weights = EdgesWeight::new(vec![5., 1., 1., 7., 2.]);
+
+    let (path, cost) = dijkstra(a, |n| *n == "d", &graph, &weights);
+    assert_eq!(cost, 4.);
+    // The path is returned from the destination back to the source.
+    let infos: Vec<u32> = path.iter().map(|&e| *graph.get_edge(e).info()).collect();
+    assert_eq!(infos, vec![2, 4, 1]);
+
+    let (_, cost) = dijkstra(c, |n| *n == "d", &graph, &weights);
+    assert_eq!(cost, 3.);
+}
+
+#[test]
+fn test_dijkstra_full() {
+    let mut graph: Graph<&str, u32> = Graph::new();
+    let a = graph.add_node("a");
+    let b = graph.add_node("b");
+    let c = graph.add_node("c");
+    let d = graph.add_node("d");
+    let e = graph.add_node("e");
+
+    graph.add_edge(a, b, 0);
+    graph.add_edge(a, c, 1);
+    graph.add_edge(b, d, 2);
+    graph.add_edge(c, d, 3);
+    graph.add_edge(c, b, 4);
+
+    let weights = EdgesWeight::new(vec![5., 1., 1., 7., 2.]);
+
+    let path = dijkstra_full(a, |n| *n == "d", &graph, &weights);
+    assert_eq!(path, Path { nodes: vec![a, c, b, d], edges: vec![1, 4, 2], cost: 4. });
+
+    // The source is already a goal.
+    let path = dijkstra_full(b, |n| *n == "b", &graph, &weights);
+    assert_eq!(path, Path { nodes: vec![b], edges: vec![], cost: 0. });
+    assert_eq!(dijkstra(b, |n| *n == "b", &graph, &weights), (vec![], 0.));
+
+    // No goal can be reached.
+    let path = dijkstra_full(a, |n| *n == "e", &graph, &weights);
+    assert!(path.nodes.is_empty() && path.edges.is_empty());
+    let path = dijkstra_full(e, |n| *n == "a", &graph, &weights);
+    assert!(path.nodes.is_empty() && path.edges.is_empty());
+}
+
+#[test]
+fn test_dijkstra_ties() {
+    // A diamond with two paths of equal cost from a to d. The path through the node with the
+    // smallest identifier is chosen, whatever the order of the edges.
+    for &reversed in [false, true].iter() {
+        let mut graph: Graph<&str, u32> = Graph::new();
+        let a = graph.add_node("a");
+        let b = graph.add_node("b");
+        let c = graph.add_node("c");
+        let d = graph.add_node("d");
+
+        let mut edges = vec![(a, b), (a, c), (b, d), (c, d)];
+        if reversed {
+            edges.reverse();
+        }
+        for (i, &(x, y)) in edges.iter().enumerate() {
+            graph.add_edge(x, y, i as u32);
+        }
+
+        let weights = EdgesWeight::new(vec![1.; 4]);
+        for _ in 0..10 {
+            let path = dijkstra_full(a, |n| *n == "d", &graph, &weights);
+            assert_eq!((path.nodes, path.cost), (vec![a, b, d], 2.));
+        }
+    }
+}
+
+#[test]
+fn test_remove_edge() {
+    let mut graph: Graph<&str, u32> = Graph::new();
+    let a = graph.add_node("a");
+    let b = graph.add_node("b");
+    let c = graph.add_node("c");
+
+    graph.add_edge(a, b, 0);
+    graph.add_edge(b, c, 1);
+    graph.add_edge(a, c, 2);
+
+    let weights = EdgesWeight::new(vec![1., 1., 5.]);
+    assert_eq!(graph.out_degree(a), 2);
+    assert_eq!(dijkstra_full(a, |n| *n == "c", &graph, &weights).edges, vec![0, 1]);
+
+    // The shortest path is cut, the identifiers of the other edges are kept.
+    graph.remove_edge(1);
+    graph.remove_edge(1);
+    assert_eq!(graph.out_degree(b), 0);
+    assert!(graph.get_edge(1).is_removed());
+    assert_eq!(*graph.get_edge(2).info(), 2);
+    let path = dijkstra_full(a, |n| *n == "c", &graph, &weights);
+    assert_eq!((path.edges, path.cost), (vec![2], 5.));
+
+    graph.remove_edge(2);
+    assert!(dijkstra_full(a, |n| *n == "c", &graph, &weights).nodes.is_empty());
+}
+
+#[test]
+fn test_astar() {
+    // Nodes are placed on a line, and the heuristic is the distance to the goal along that line.
+    let mut graph: Graph<i32, u32> = Graph::new();
+    for x in 0..6 {
+        graph.add_node(x);
+    }
+
+    let links = [(0, 1, 2.), (1, 2, 1.), (0, 2, 4.), (2, 3, 1.), (1, 4, 5.), (3, 4, 1.),
+                 (4, 5, 2.), (2, 5, 4.), (5, 0, 5.), (3, 1, 2.)];
+    for (i, &(s, d, _)) in links.iter().enumerate() {
+        graph.add_edge(s, d, i as u32);
+    }
+    let weights = EdgesWeight::new(links.iter().map(|&(_, _, w)| w).collect());
+
+    for source in 0..6 {
+        for goal in 0..6 {
+            // With a zero heuristic, A* behaves exactly like Dijkstra.
+            let expected = dijkstra(source, |x| *x == goal as i32, &graph, &weights);
+            let result = astar(source, |x| *x == goal as i32, |_| 0., &graph, &weights);
+            assert_eq!(result, expected);
+
+            // With an admissible heuristic, the costs are still optimal.
+            let (_, cost) = astar(source, |x| *x == goal as i32,
+                                  |x| (goal as i32 - *x).abs() as Weight, &graph, &weights);
+            assert_eq!(cost, expected.1);
+        }
+    }
+}
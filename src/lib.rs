@@ -3,3 +3,4 @@
 #![feature(test)]
 
 pub mod engine;
+pub mod graph;
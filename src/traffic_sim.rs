@@ -29,5 +29,5 @@ pub fn main() {
     gui.run(network, cars);
 
     // Without the Gui
-//    trafficsim::run_simulation(network, cars, None);
+//    trafficsim::run_simulation(network, cars, None, reactivers::engine::CancellationToken::new());
 }
\ No newline at end of file
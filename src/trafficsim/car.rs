@@ -33,14 +33,17 @@ pub struct Car {
     path: Vec<EdgeId>,      // Path to destination crossroad.
     d: Weight,              // Estimated distance to the destination.
     graph: Arc<Graph>,      // Graph of roads and crossroad nodes.
+    free_weights: Arc<EdgesWeight>, // Weights of the roads when they are empty.
+    congestion_sensitivity: f32,    // Exponent applied to the congestion of the roads.
     speed: usize,           // Current speed.
 }
 
 
 impl Car {
 
-    /// Creates a new car.
-    pub fn new(id: CarId, source: NodeId, destination: NodeInfo, graph: Arc<Graph>) -> Car {
+    /// Creates a new car. `free_weights` are the weights of the roads when they are empty.
+    pub fn new(id: CarId, source: NodeId, destination: NodeInfo, graph: Arc<Graph>,
+               free_weights: Arc<EdgesWeight>) -> Car {
         Car { id,
             position: source,
             destination,
@@ -48,14 +51,45 @@ impl Car {
             path: vec!(),
             d: f32::MAX,
             graph,
+            free_weights,
+            congestion_sensitivity: 1.,
             speed: 0,
         }
     }
 
+    /// Returns the identifier of the car.
+    pub fn id(&self) -> CarId {
+        self.id
+    }
+
+    /// Sets how much the car avoids congested roads. The congestion of a road is the ratio between
+    /// its estimated weight and its weight when it is empty, and the car routes with the weight of
+    /// the empty road multiplied by the congestion raised to `sensitivity`.
+    /// With 1, the default, the car uses the estimated weights. With 0, it ignores the congestion,
+    /// and with more, it prefers longer but emptier paths.
+    pub fn set_congestion_sensitivity(&mut self, sensitivity: f32) {
+        self.congestion_sensitivity = sensitivity;
+    }
+
+    /// Returns the weights used by the car to route, given the estimated weights of the roads.
+    fn routing_weights(&self, weights: &EdgesWeight) -> EdgesWeight {
+        let s = self.congestion_sensitivity;
+        EdgesWeight::new(weights.weights.iter().zip(self.free_weights.weights.iter())
+            .map(|(&w, &free)| free * (w / free).powf(s))
+            .collect())
+    }
+
     /// Computes the path to the solution, using Dijkstra algorithm with specified estimations of
     /// edges lengths.
     fn compute_path(&mut self, weights: &EdgesWeight) {
         // Computes the path and updates state.
+        let scaled;
+        let weights = if self.congestion_sensitivity == 1. {
+            weights
+        } else {
+            scaled = self.routing_weights(weights);
+            &scaled
+        };
         let (path, d) =
             dijkstra(self.position,
                      |x| { *x == self.destination },
@@ -130,8 +164,8 @@ impl Car {
         // We initialize the car with a Spawn action.
         let v = (id, (Action::SPAWN, 0));
         let p =
-            value(v).emit(&pos_signal).then(
-                central_signal.await_in().map(cont).emit(&pos_signal).loop_inf()
+            value(v).emit(&pos_signal).then_loop(
+                central_signal.await_in().map(cont).emit(&pos_signal)
         );
         return p;
     }
@@ -147,4 +181,31 @@ impl fmt::Display for Car {
             self.destination
         )
     }
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_congestion_sensitivity() {
+    // Two paths lead from A to C: a direct congested road, or two empty roads through B.
+    let mut graph = Graph::new();
+    let a = graph.add_node(CrossroadId::new(0, 0));
+    let b = graph.add_node(CrossroadId::new(1, 0));
+    graph.add_node(CrossroadId::new(0, 1));
+    graph.add_edge(a, 2, 0);
+    graph.add_edge(a, b, 1);
+    graph.add_edge(b, 2, 2);
+    let graph = Arc::new(graph);
+
+    let free_weights = Arc::new(EdgesWeight::new(vec![10., 12., 12.]));
+    let weights = EdgesWeight::new(vec![20., 12., 12.]);
+
+    let next_road = |sensitivity: f32| {
+        let mut car = Car::new(0, a, CrossroadId::new(0, 1), graph.clone(), free_weights.clone());
+        car.set_congestion_sensitivity(sensitivity);
+        car.compute_path(&weights);
+        car.next_road()
+    };
+
+    assert_eq!(next_road(1.), 0);
+    assert_eq!(next_road(0.), 0);
+    assert_eq!(next_road(3.), 1);
+}
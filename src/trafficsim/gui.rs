@@ -1,5 +1,7 @@
 extern crate reactivers;
 
+use reactivers::engine::CancellationToken;
+
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
@@ -9,6 +11,7 @@ extern crate rand;
 use super::network::*;
 use super::car::*;
 use super::road::*;
+use super::playback::Playback;
 
 use super::opengl_graphics::GlGraphics;
 use super::piston::event_loop::*;
@@ -22,6 +25,7 @@ use super::graphics::*;
 pub struct Gui {
     size_cell: f64,                         // Cell size (distance between crossroads)
     size_crossroad: f64,                    // Crossroad size
+    lane_width: f64,                        // Width of a lane.
     car_place_width: f64,                   // Width occupied by a car.
                                             // Determines the space between cars.
     width: f64,                             // Width of the window.
@@ -34,6 +38,7 @@ pub struct Gui {
     car_rectangle: [f64; 4],                // Car rectangle.
     car_animations: Vec<Animation>,         // Vector of animations.
     animation_duration: f64,                // Animation duration in seconds.
+    playback: Playback,                     // Playback controls shared with the simulation.
 }
 
 /*
@@ -51,9 +56,38 @@ type AnimationStep = Box<Fn(f64, Context, &mut GlGraphics) -> () + 'static>;
 pub struct Animation {
     start: f64,             // Start time of the animation.
     duration: f64,          // Duration of the animation (in seconds).
+    easing: Easing,         // Easing applied to the progress.
     step: AnimationStep,    // Animation Step.
 }
 
+/// Easing of an animation, mapping the elapsed fraction of its duration to its progress.
+/// All the easings map 0 to 0 and 1 to 1.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Easing {
+    Linear,     // Constant speed.
+    EaseIn,     // Accelerates from zero speed.
+    EaseOut,    // Decelerates to zero speed.
+    EaseInOut,  // Accelerates until the middle of the animation, then decelerates.
+}
+
+impl Easing {
+    /// Returns the progress corresponding to the elapsed fraction `t`, in range [0., 1.].
+    pub fn apply(&self, t: f64) -> f64 {
+        match *self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t,
+            Easing::EaseOut => t * (2. - t),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    2. * t * t
+                } else {
+                    1. - 2. * (1. - t) * (1. - t)
+                }
+            },
+        }
+    }
+}
+
 /// A transformation is some object that can apply a transformation to a given Context.
 trait Transformation {
     /// Applies the transformation corresponding to the specified time `t` to the given `cont`.
@@ -148,14 +182,19 @@ impl Transformation for MultiTrajectory {
 }
 
 impl Animation {
-    /// Creates a new animation.
+    /// Creates a new animation at constant speed.
     pub fn new(f: AnimationStep, time: f64, duration: f64) -> Animation {
-        Animation { step: f,  start: time, duration }
+        Animation::with_easing(f, time, duration, Easing::Linear)
+    }
+
+    /// Creates a new animation whose progress follows `easing`.
+    pub fn with_easing(f: AnimationStep, time: f64, duration: f64, easing: Easing) -> Animation {
+        Animation { step: f,  start: time, duration, easing }
     }
 
     /// Creates an empty animation with no transformation.
     pub fn unit() -> Animation {
-        Animation { step: Box::new(| _, _, _ | {}), start: 0., duration: 1. }
+        Animation::new(Box::new(| _, _, _ | {}), 0., 1.)
     }
 
 
@@ -164,7 +203,7 @@ impl Animation {
         // We normalize the time to get the progress of the animation.
         // We crop it if it is out of range [0., 1.].
         let t = f64::max(0., f64::min(1., (time - self.start) / self.duration));
-        (self.step)(t, c, g);
+        (self.step)(self.easing.apply(t), c, g);
     }
 }
 
@@ -180,12 +219,14 @@ impl Gui {
         let height = network.height;
         let size_cell = network.cars_per_unit as f64 * car_place_width;
         let size_crossroad = network.cars_per_crossroad as f64 * car_place_width;
+        let lane_width = size_crossroad / (2 * network.lanes()) as f64;
 
         let crossroads = network.crossroads.clone();
         let roads = network.roads.iter().map(| r | { r.info() }).collect();
         Gui {
             size_cell,
             size_crossroad,
+            lane_width,
             car_place_width,
             width: (width - 1) as f64 * size_cell + size_crossroad,
             height: (height - 1) as f64 * size_cell + size_crossroad,
@@ -199,6 +240,7 @@ impl Gui {
             ],
             car_animations: (0..network.car_count).map(|_| { Animation::unit() }).collect(),
             animation_duration,
+            playback: Playback::new(),
         }
     }
 
@@ -234,8 +276,8 @@ impl Gui {
         let length = length * self.size_cell - self.size_crossroad;
 
         let (mut x, mut y) = self.pos_crossroad(r.start);
-        x += (dx / 2. - dy * r.side as f64 / 4.) * self.size_crossroad;
-        y += (dy / 2. + dx * r.side as f64 / 4.) * self.size_crossroad;
+        x += dx / 2. * self.size_crossroad - dy * r.lane as f64 * self.lane_width;
+        y += dy / 2. * self.size_crossroad + dx * r.lane as f64 * self.lane_width;
 
         // We compute the orientation.
         let rot = match (dx as i32, dy as i32) {
@@ -249,24 +291,20 @@ impl Gui {
         let line_width2 = 1.;
         let c = cont.trans(x, y).rot_deg( rot * 90.);
 
-        let t1 = Context::new().trans(0., (r.side as f64) * self.size_crossroad / 4.).prepend_transform(c.transform);
-        let t2 = Context::new().trans(0., -(r.side as f64) * self.size_crossroad / 4.).prepend_transform(c.transform);
-
         // The road.
-        rectangle([0.8, 0.8, 0.8, 1.], [0., 0., length, self.size_crossroad / 4.],
+        rectangle([0.8, 0.8, 0.8, 1.], [0., 0., length, self.lane_width],
                   c.transform, g);
 
-        // Lines separating lanes.
-        // External line (black one)
-        if r.side == 0 {
+        // Lines separating lanes, drawn on the inner edge of each lane.
+        if r.lane == 0 {
+            // External line (black one), between the two directions.
             rectangle([0., 0., 0., 1.], [0., -line_width1 / 2., length, line_width1],
-                      t1.transform, g);
+                      c.transform, g);
+        } else {
+            // Internal line (lighter one), between two lanes of the same direction.
+            rectangle([0.5, 0.5, 0.5, 1.], [0., -line_width2 / 2., length, line_width2],
+                      c.transform, g);
         }
-
-        // Internal line (lighter one).
-        rectangle([0.5, 0.5, 0.5, 1.], [0., self.size_crossroad / 4. - line_width2 / 2., length,
-            line_width2],
-                  t2.transform, g);
     }
 
     /// Draws all the roads.
@@ -285,7 +323,7 @@ impl Gui {
         // We define its trajectory.
         let r = self.car_rectangle;
         let radius = self.car_place_width;
-        let shift = self.size_crossroad / 4.;
+        let shift = self.lane_width;
 
         let mut multi_traj = MultiTrajectory::new();
         multi_traj.add(Trajectory::line(shift));
@@ -324,14 +362,14 @@ impl Gui {
     /// Returns the animation step for a car crossing a crossroad.
     pub fn cross_car(&mut self, id: CarId, info: RoadInfo) -> AnimationStep {
         // We compute the start coordinates and orientation.
-        let side1 = self.cars[id].unwrap().0.side as f64;
+        let lane1 = self.cars[id].unwrap().0.lane as f64;
         let (x1, y1, angle1) = self.car_position(id);
 
         // We update the car position.
         self.cars[id] = Some((info, info.length - 1));
 
         // We compute the end coordinates and orientation.
-        let side2 = info.side as f64;
+        let lane2 = info.lane as f64;
         let (x2, y2, angle2) = self.car_position(id);
 
         // We create an empty trajectory.
@@ -339,9 +377,9 @@ impl Gui {
 
         if angle1 == angle2 {
             // The car goes straight forwards.
-            // If the car changes its side, it uses two arcs of circle.
-            let angle = (side2 - side1) * 90.;
-            let radius = f64::abs(side1 - side2) * self.size_crossroad / 8.;
+            // If the car changes its lane, it uses two arcs of circle.
+            let angle = (lane2 - lane1).signum() * 90.;
+            let radius = f64::abs(lane1 - lane2) * self.lane_width / 2.;
             let line = (self.size_crossroad + self.car_place_width - 2.*radius) / 2.;
 
             multi_traj.add(Trajectory::line(line));
@@ -352,8 +390,8 @@ impl Gui {
         } else if f64::abs(angle1 - angle2) == 180. {
             // The car turns back.
             // Its goes a bit forwards, turns back, and goes a bit forward again.
-            // the radius changes depends on the start and end sides.
-            let radius = self.size_crossroad / 8. * (1. + side1 + side2);
+            // the radius changes depends on the start and end lanes.
+            let radius = self.lane_width / 2. * (1. + lane1 + lane2);
             let line = 1.5 * self.car_place_width;
 
             multi_traj.add(Trajectory::line(line));
@@ -424,7 +462,8 @@ impl Gui {
     }
 
     /// Updates the GUI move information from the data shared with the reactive process.
-    pub fn update(&mut self, time: f64) {
+    /// Returns true if new moves were received.
+    pub fn update(&mut self, time: f64) -> bool {
         // We try to retrieve the data.
         let moves = {
             let mut moves = self.data.lock().unwrap();
@@ -435,24 +474,30 @@ impl Gui {
 
         // If there is no change, we return.
         if moves.is_none() {
-            return;
+            return false;
         }
 
-        // Otherwise, we compute the new animations for each car.
+        // Otherwise, we compute the new animations for each car. Some cars may have been added
+        // since the last update.
         let moves = moves.unwrap();
+        while self.cars.len() < moves.len() {
+            self.cars.push(None);
+        }
         let duration = self.animation_duration;
         let animations = moves.iter().enumerate().map(|(i, m)| {
-            let animation_step = match m {
-                &Move::NONE => self.static_car(i),
-                &Move::STEP(x) => self.step_car(i, x as usize),
-                &Move::VANISH => self.vanish_car(i),
-                &Move::CROSS(r) => self.cross_car(i, r),
-                &Move::SPAWN(r, x, _) => self.spawn_car(i, r, x)
+            // Cars slow down through crossroads.
+            let (animation_step, easing) = match m {
+                &Move::NONE => (self.static_car(i), Easing::Linear),
+                &Move::STEP(x) => (self.step_car(i, x as usize), Easing::Linear),
+                &Move::VANISH => (self.vanish_car(i), Easing::Linear),
+                &Move::CROSS(r) => (self.cross_car(i, r), Easing::EaseInOut),
+                &Move::SPAWN(r, x, _) => (self.spawn_car(i, r, x), Easing::Linear)
             };
-            Animation::new(animation_step, time, duration)
+            Animation::with_easing(animation_step, time, duration, easing)
         }).collect();
 
         self.car_animations = animations;
+        true
     }
 
     /// Draws all the cars with the saved animations.
@@ -477,12 +522,12 @@ impl Gui {
 
         // Gets the start of the road.
         let (mut x, mut y) = self.pos_crossroad(r.start);
-        x += (dx * 0.5 - dy * r.side as f64 / 4.) * self.size_crossroad;
-        y += (dy * 0.5 + dx * r.side as f64 / 4.) * self.size_crossroad;
+        x += dx * 0.5 * self.size_crossroad - dy * r.lane as f64 * self.lane_width;
+        y += dy * 0.5 * self.size_crossroad + dx * r.lane as f64 * self.lane_width;
 
-        // Centers the car on the road.
-        x += -dy * self.size_crossroad / 8.;
-        y +=  dx * self.size_crossroad / 8.;
+        // Centers the car on the lane.
+        x += -dy * self.lane_width / 2.;
+        y +=  dx * self.lane_width / 2.;
 
         // Uses the index of the car on this road.
         x += dx * dist;
@@ -506,15 +551,36 @@ impl Gui {
         self.data.clone()
     }
 
-    /// Launches the GUI and the simulation on the specified network and cars.
+    /// Handles the keyboard controls of the playback: space pauses or resumes the simulation, `.`
+    /// runs a single instant while paused, and `+` and `-` change its speed.
+    fn handle_key(&self, key: Key) {
+        match key {
+            Key::Space => self.playback.toggle_pause(),
+            Key::Period => self.playback.step(),
+            Key::Plus | Key::Equals | Key::NumPadPlus => self.playback.speed_up(),
+            Key::Minus | Key::NumPadMinus => self.playback.slow_down(),
+            _ => {},
+        }
+    }
+
+    /// Launches the GUI and the simulation on the specified network and cars. When the window is
+    /// closed, the simulation is stopped and its thread joined before returning.
+    ///
+    /// The simulation is stopped through a `CancellationToken`, a shared flag whose clones are
+    /// held by every worker of the simulation runtime. Each worker checks it at the end of each
+    /// instant: the first one to see it cancelled tells the others, and they all stop at this
+    /// instant boundary, so `run_simulation` returns.
     pub fn run(&mut self, network: Network, cars: Vec<Car>)
     {
-        // We initialize the simulation with the shared data and animation duration.
+        // We initialize the simulation with the shared data, animation duration and playback.
         let data = self.transfer_data();
         let duration = self.animation_duration;
-        thread::spawn(move |  | {
+        let playback = self.playback.clone();
+        let cancellation = CancellationToken::new();
+        let simulation_cancellation = cancellation.clone();
+        let simulation = thread::spawn(move |  | {
             thread::sleep(Duration::from_millis(1000));
-            super::run_simulation(network, cars, Some((duration, data)));
+            let _ = super::run_simulation(network, cars, Some((duration, data, playback)), simulation_cancellation);
         });
 
         // We create a window.
@@ -532,7 +598,13 @@ impl Gui {
         let mut time = 0.;
         self.update(time);
 
+        // End of the animation of the last received instant.
+        let mut frame_end = time + self.animation_duration;
+
         while let Some(e) = events.next(&mut window) {
+            if let Some(Button::Keyboard(key)) = e.press_args() {
+                self.handle_key(key);
+            }
             if let Some(args) = e.render_args() {
                 gl.draw(args.viewport(), |cont, g| {
                     clear([1., 1., 1., 1.0], g);
@@ -544,10 +616,41 @@ impl Gui {
                 });
             }
             if let Some(args) = e.update_args() {
-                // We update the time and try to retrieve the data.
-                time += args.dt;
-                self.update(time);
+                // We update the time and try to retrieve the data. The time runs faster or slower
+                // with the speed, as the simulation does. While paused, it only runs until the end
+                // of the animation of the last received instant: the simulation waits for a step
+                // before sending the next one, whose animation then starts from a still frame.
+                let dt = args.dt * self.playback.speed();
+                if !self.playback.is_paused() {
+                    time += dt;
+                } else if time < frame_end {
+                    time = f64::min(frame_end, time + dt);
+                }
+                if self.update(time) {
+                    frame_end = time + self.animation_duration;
+                }
             }
         }
+
+        // The window was closed, we stop the simulation, which may be waiting for a step, and
+        // wait for its thread to end.
+        self.playback.resume();
+        cancellation.cancel();
+        simulation.join().expect("The simulation thread panicked.");
+    }
+}
+
+#[test]
+fn test_easing() {
+    for easing in [Easing::Linear, Easing::EaseIn, Easing::EaseOut, Easing::EaseInOut].iter() {
+        assert_eq!(easing.apply(0.), 0.);
+        assert_eq!(easing.apply(1.), 1.);
     }
+
+    // Ease in-out is symmetric, but slower than linear at the ends.
+    let ease = Easing::EaseInOut;
+    assert_eq!(ease.apply(0.5), 0.5);
+    assert!(ease.apply(0.25) < 0.25);
+    assert!(ease.apply(0.75) > 0.75);
+    assert!((ease.apply(0.25) + ease.apply(0.75) - 1.).abs() < 1e-12);
 }
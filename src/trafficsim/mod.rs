@@ -8,10 +8,14 @@ pub mod car;
 pub mod graph;
 pub mod road;
 pub mod network;
+pub mod pedestrian;
+pub mod playback;
 pub mod gui;
 
 use self::network::*;
 use self::car::*;
+use self::pedestrian::*;
+use self::playback::Playback;
 
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -20,10 +24,14 @@ use std::time::Duration;
 use reactivers::engine::signal::*;
 use reactivers::engine::process::*;
 use reactivers::engine;
+use reactivers::engine::{CancellationToken, ExecError, Runtime, RuntimeConfig};
 
 
-/// Launches a simulation
-pub fn run_simulation(network: Network, cars: Vec<Car>, data: Option<(f64,Arc<Mutex<Option<Vec<Move>>>>)>)
+/// Builds the processes of a simulation: the network, its cars and the pedestrians, which all
+/// stop once the network reaches a stationary state. Also returns the signal on which the network
+/// emits the information of each step, and the signal emitted when it stops.
+fn simulation(network: Network, cars: Vec<Car>, pedestrians: Vec<Pedestrian>, spawner: CarSpawner)
+              -> (impl Process<Value=()>, spmc_signal::SPMCSignalReceiver<Arc<GlobalInfo>>, puresignal::PureSignal)
 {
     // We first define the signals.
     // A first SPMC signal to send information to the cars.
@@ -44,40 +52,80 @@ pub fn run_simulation(network: Network, cars: Vec<Car>, data: Option<(f64,Arc<Mu
                 (v, s)
             });
 
-    // We get the network and the car processes.
-    let network_process = network.process(central_sender, pos_signal_receiver);
+    // And a signal emitted when the network stops, which stops all the other processes.
+    let stop = puresignal::new();
+
+    // And a signal per crossroad, on which the pedestrians emit their presence.
+    let crossings = crossing_signals(&network);
+    let pedestrian_processes = pedestrians.into_iter().map(|p| p.process(&crossings)).collect();
+
+    // We get the car processes, in a join which accepts the cars spawned during the simulation.
     let car_processes = cars.into_iter().map(|c| {
         c.process(
             central_receiver.clone(),
             pos_signal_sender.clone()
-        )
+        ).boxed()
     }).collect();
+    let (car_join, car_handle) = dynamic_multi_join(car_processes);
+    let (car_receiver, car_sender) = (central_receiver.clone(), pos_signal_sender.clone());
+    let launch_car = move |runtime: &mut Runtime, c: Car| {
+        car_handle.add(runtime, c.process(car_receiver.clone(), car_sender.clone()).boxed());
+    };
 
-    let process = network_process.multi_join(car_processes);
+    // We get the network process.
+    let network_process =
+        network.process(central_sender, pos_signal_receiver, crossings, spawner, launch_car)
+            .then(stop.emit(value(())));
+
+    let others = stop.do_until(car_join.multi_join(pedestrian_processes));
+    (network_process.join(others).map(|_| ()), central_receiver, stop)
+}
+
+/// Launches a simulation, which runs until `cancellation` is cancelled or the network reaches a
+/// stationary state, in which case it returns `Ok`.
+/// If there is a GUI, `data` holds the duration of an instant in seconds at normal speed, the data
+/// shared with the GUI and the playback controls.
+pub fn run_simulation(network: Network, cars: Vec<Car>, data: Option<(f64, Arc<Mutex<Option<Vec<Move>>>>, Playback)>,
+                      cancellation: CancellationToken) -> Result<(), ExecError>
+{
+    run_simulation_with_pedestrians(network, cars, vec!(), CarSpawner::new(), data, cancellation)
+}
+
+/// Launches a simulation with some pedestrians, which runs until `cancellation` is cancelled or
+/// the network reaches a stationary state, in which case it returns `Ok`.
+/// More cars can be added while it runs through `spawner`.
+pub fn run_simulation_with_pedestrians(network: Network, cars: Vec<Car>, pedestrians: Vec<Pedestrian>,
+                                       spawner: CarSpawner,
+                                       data: Option<(f64, Arc<Mutex<Option<Vec<Move>>>>, Playback)>,
+                                       cancellation: CancellationToken) -> Result<(), ExecError>
+{
+    let (process, central_receiver, stop) = simulation(network, cars, pedestrians, spawner);
 
     // We build the process that transfers the data to the GUI, if there is one.
 
     // First the process that returns true or false if there is some GUI.
     let gui_bool = data.is_some();
-    let (duration, data) = {
+    let (duration, data, playback) = {
         if gui_bool {
             data.unwrap()
         }
         else {
-            (1., Arc::new(Mutex::new(None)))
+            (1., Arc::new(Mutex::new(None)), Playback::new())
         }
     };
 
     // Second the main loop that transfers the data.
     let mut step = 0;
     let gui_c = move | infos: Arc<GlobalInfo> | {
+        // While paused, we wait for the GUI to request the next instant.
+        let wait = playback.wait_turn(Duration::from_millis((duration * 1000.) as u64));
         {
             let mut data = data.lock().unwrap();
             *data = Some(infos.moves.clone());
         }
         step += 1;
         // This process synchronizes with the GUI.
-        thread::sleep(Duration::from_millis((duration * 1000.) as u64));
+        thread::sleep(wait);
     };
 
 
@@ -86,7 +134,118 @@ pub fn run_simulation(network: Network, cars: Vec<Car>, data: Option<(f64,Arc<Mu
 
     // The final transfer process void or the transfer loop.
     let transfer_process =
-        value(gui_bool).then_else(transfer_loop, void);
+        stop.do_until(value(gui_bool).then_else(transfer_loop, void));
+
+    let config = RuntimeConfig::new().workers(8).cancellation(cancellation);
+    engine::execute_with_config(transfer_process.join(process), config).map(|_| ())
+}
+
+/// Seed of the networks built by `compare_policies`.
+const COMPARISON_SEED: u64 = 42;
+
+/// Metrics of a simulation run, comparable between runs of the same scenario.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimMetrics {
+    pub steps: usize,               // Number of steps performed by the network.
+    pub trips: usize,               // Number of trips completed, from spawn to vanish.
+    pub throughput: f32,            // Average number of trips completed per step.
+    pub average_trip_time: f32,     // Average number of steps of the completed trips.
+}
+
+/// Runs the same scenario under each of the `policies`, and returns the metrics of each run, in
+/// the same order. The scenario is the network loaded from `map` with a fixed seed, with `cars`
+/// cars, and each run lasts at most `instants` instants.
+pub fn compare_policies(map: &str, cars: usize, policies: Vec<Box<CrossroadPolicy>>, instants: usize)
+                        -> Result<Vec<SimMetrics>, ExecError>
+{
+    policies.into_iter().map(|policy| {
+        let mut network = Network::with_seed(0, 0, COMPARISON_SEED);
+        network.load_string(map);
+        network.set_policy(policy);
+        let cars = (0..cars).map(|_| network.create_car()).collect();
+
+        let (process, central_receiver, stop) =
+            simulation(network, cars, vec!(), CarSpawner::new());
+
+        // We follow the moves of the cars to measure their trips, and keep the last metrics.
+        let metrics = Arc::new(Mutex::new(None));
+        let metrics2 = metrics.clone();
+        let mut step = 0;
+        let mut spawned_at = vec!();
+        let mut trip_times = vec!();
+        let record = move |infos: Arc<GlobalInfo>| {
+            step += 1;
+            for (id, m) in infos.moves.iter().enumerate() {
+                if spawned_at.len() <= id {
+                    spawned_at.resize(id + 1, None);
+                }
+                match *m {
+                    Move::SPAWN(..) => spawned_at[id] = Some(step),
+                    Move::VANISH => {
+                        if let Some(start) = spawned_at[id].take() {
+                            trip_times.push(step - start);
+                        }
+                    },
+                    _ => (),
+                }
+            }
+
+            let trips = trip_times.len();
+            let average_trip_time = if trips == 0 {
+                0.
+            } else {
+                trip_times.iter().sum::<usize>() as f32 / trips as f32
+            };
+            *metrics2.lock().unwrap() = Some(SimMetrics {
+                steps: step,
+                trips,
+                throughput: trips as f32 / step as f32,
+                average_trip_time,
+            });
+        };
+        let record_loop = stop.do_until(central_receiver.await_in().map(record).loop_inf());
+
+        // The run either stops in a stationary state, or reaches the maximum number of instants.
+        let config = RuntimeConfig::new().workers(8).max_iters(instants as i32);
+        match engine::execute_with_config(record_loop.join(process), config) {
+            Ok(_) | Err(ExecError::IterationLimit) => (),
+            Err(e) => return Err(e),
+        }
 
-    engine::execute_process_steps(transfer_process.join(process), 8, -1);
-}
\ No newline at end of file
+        let metrics = metrics.lock().unwrap().take();
+        Ok(metrics.expect("The network performed no step."))
+    }).collect()
+}
+
+#[test]
+fn test_compare_policies() {
+    // Most of the traffic goes through the central crossroad along the horizontal axis, which is
+    // longer than the vertical one. Enabling two compatible roads at once lets more cars cross
+    // than enabling a single one, so the trips are shorter.
+    let map = "  C\n  |\nC-C-C-C\n  |\n  C";
+    let policies: Vec<Box<CrossroadPolicy>> = vec!(Box::new(MaxPairLoad), Box::new(MostLoadedSingle));
+    let metrics = compare_policies(map, 20, policies, 300).unwrap();
+
+    assert_eq!(metrics.len(), 2);
+    let (pair, single) = (&metrics[0], &metrics[1]);
+    assert_eq!(pair.steps, single.steps);
+    assert!(pair.throughput > single.throughput);
+    assert!(pair.average_trip_time < single.average_trip_time);
+
+    // The scenario is seeded, so it gives the same metrics again.
+    let again = compare_policies(map, 20, vec!(Box::new(MaxPairLoad)), 300).unwrap();
+    assert_eq!(again[0], *pair);
+}
+
+#[test]
+fn test_stationary_state() {
+    // Pedestrians never leave the two crossroads, so the cars are stuck at the end of their road.
+    let mut network = Network::new(0, 0);
+    network.load_string("C-C");
+    let cars = (0..2).map(|_| network.create_car()).collect();
+    let pedestrians = network.crossroads.iter().map(|&c| Pedestrian::new(c, 1, 0)).collect();
+
+    let result = run_simulation_with_pedestrians(network, cars, pedestrians, CarSpawner::new(),
+                                                 None, CancellationToken::new());
+    assert_eq!(result, Ok(()));
+}
@@ -5,16 +5,24 @@ use reactivers::engine::process::*;
 use reactivers::engine::signal::*;
 use reactivers::engine::signal::spmc_signal::*;
 use reactivers::engine::signal::mpsc_signal::*;
+#[cfg(test)]
+use reactivers::engine;
+use reactivers::engine::Runtime;
+#[cfg(test)]
+use std::sync::Mutex;
 
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::prelude::*;
 
-use self::rand::Rng;
+use self::rand::{Rng, SeedableRng, StdRng};
 
 use super::graph::*;
 use super::car::*;
 use super::road::*;
+use super::pedestrian::*;
 
 // These constant directions are used to index roads and nodes at a crossroad.
 const NORTH:    usize = 0;
@@ -30,6 +38,42 @@ const RIGHT:    usize = 1;
 pub struct GlobalInfo {
     pub weights: EdgesWeight,   // Last estimation of the edges weights.
     pub moves: Vec<Move>,       // Moves of all the cars.
+    pub stats: NetworkStats,    // Statistics of the roads after this step.
+}
+
+/// Statistics of the roads of a network, accumulated since its creation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NetworkStats {
+    pub steps: usize,           // Number of steps performed by the network.
+    pub roads: Vec<RoadStats>,  // Statistics of each road, indexed by road identifier.
+}
+
+impl NetworkStats {
+    /// Returns the average number of cars delivered per step by road `r`.
+    pub fn throughput(&self, r: RoadId) -> f32 {
+        if self.steps == 0 {
+            0.
+        } else {
+            self.roads[r].delivered as f32 / self.steps as f32
+        }
+    }
+
+    /// Returns the total number of cars delivered by the roads.
+    pub fn delivered(&self) -> usize {
+        self.roads.iter().map(|r| r.delivered).sum()
+    }
+}
+
+/// Prints a table of the statistics of each road.
+impl fmt::Display for NetworkStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:>6} {:>10} {:>10} {:>10}", "road", "delivered", "throughput", "occupancy")?;
+        for (r, stats) in self.roads.iter().enumerate() {
+            writeln!(f, "{:>6} {:>10} {:>10.3} {:>10.3}", r, stats.delivered, self.throughput(r),
+                     stats.occupancy)?;
+        }
+        Ok(())
+    }
 }
 
 /// Move of a car.
@@ -43,6 +87,31 @@ pub enum Move {
     CROSS(RoadInfo),                        // The car crossed and is now on specified road.
 }
 
+/// Handle through which control processes add cars to a running simulation. It can be cloned and
+/// used from any process or thread.
+#[derive(Clone)]
+pub struct CarSpawner {
+    requested: Arc<AtomicUsize>,    // Number of cars requested since the last network step.
+}
+
+impl CarSpawner {
+    /// Creates a new spawner, with no car requested.
+    pub fn new() -> CarSpawner {
+        CarSpawner { requested: Arc::new(AtomicUsize::new(0)) }
+    }
+
+    /// Requests a new car. The network creates it at its next step, and the car spawns at the
+    /// following instant.
+    pub fn spawn_new_car(&self) {
+        self.requested.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Returns the number of cars requested since the last call, and resets it.
+    fn take_requests(&self) -> usize {
+        self.requested.swap(0, Ordering::SeqCst)
+    }
+}
+
 /// Network structure containing all the information relative to crossroads and roads.
 #[derive(Clone)]
 pub struct Network {
@@ -55,12 +124,19 @@ pub struct Network {
     pub roads: Vec<Road>,               // Vector containing the roads.
     graph: Graph,                       // Corresponding abstract graph.
     car_graph: Option<Arc<Graph>>,      // Shared reference to the same graph.
+    free_weights: Option<Arc<EdgesWeight>>, // Weights of the empty roads, shared with the cars.
     pub crossroads: Vec<CrossroadId>,   // Vector containing all the coordinates of existing
                                         // crossroads.
+    reaction_factor: f32,               // Free places kept by a car per unit of speed.
+    policy: Arc<CrossroadPolicy>,       // Traffic light policy of the crossroads.
+    lanes: usize,                       // Number of lanes per road direction.
+    speed_limit: SpeedLimit,            // Speed limits of the roads added by `add_road`.
+    rng: StdRng,                        // Random generator for spawns and destinations.
+    steps: usize,                       // Number of steps performed by the roads.
 }
 
 /// Crossroad Coordinates.
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
 pub struct CrossroadId {
     pub x: usize,   // Abscissa
     pub y: usize,   // Ordinate
@@ -136,10 +212,10 @@ pub struct Crossroad {
     id: CrossroadId,                            // Coordinates
     pub nodes: Vec<NodeId>,                     // Vector of its 4 quarter nodes.
                                                 // They are indexed by direction.
-    roads: Vec<Vec<Option<RoadId>>>,            // Roads leaving this crossroad.
-                                                // They are indexed by direction and side.
-    roads_arriving: Vec<Vec<Option<RoadId>>>,   // Roads arriving at this crossroad.
-                                                // They are indexed by direction and side.
+    roads: Vec<Vec<Vec<Option<RoadId>>>>,           // Roads leaving this crossroad. They are
+                                                    // indexed by direction, side and lane.
+    roads_arriving: Vec<Vec<Vec<Option<RoadId>>>>,  // Roads arriving at this crossroad. They are
+                                                    // indexed by direction, side and lane.
 }
 
 impl Crossroad {
@@ -148,8 +224,8 @@ impl Crossroad {
         let mut c = Crossroad {
             id,
             nodes: vec!(),
-            roads: none_array(4, 2),
-            roads_arriving: none_array(4, 2),
+            roads: empty_lanes(),
+            roads_arriving: empty_lanes(),
         };
 
         for _ in 0..4 {
@@ -158,21 +234,76 @@ impl Crossroad {
         c
     }
 
-    /// Enables some roads. Only the cars from enabled roads are able to cross a crossroad.
-    fn enable_path(&self, roads: &mut Vec<Road>) {
-        // First policy: we enable the most loaded road with some guy waiting.
-//        let mut max = -1;
-//        let mut r_max = 0;
-//        for r in self.existing_roads_arriving() {
-//            if roads[r].is_waiting() && roads[r].get_car_count() > max {
-//                r_max = r;
-//                max = roads[r].get_car_count();
-//            }
-//        }
-//        roads[r_max].enable();
-
-        // Second policy: we enable the most loaded roads with guys waiting, but in pairs.
+    /// Computes the load of a lane, i.e. the numbers of cars on this lane.
+    /// If there is no car ready to cross, returns 0.
+    fn compute_lane_load(&self, direction: usize, side: usize, lane: usize, roads: &mut Vec<Road>) -> i32 {
+        let r = self.roads_arriving[direction][side][lane];
+        if r.is_none() || !roads[r.unwrap()].is_waiting() {
+            return 0;
+        }
+        return roads[r.unwrap()].get_car_count();
+    }
+
+    /// Computes the load of a side of a road, i.e. the cumulated load of its lanes.
+    fn compute_load(&self, direction: usize, side: usize, roads: &mut Vec<Road>) -> i32 {
+        (0..self.roads_arriving[direction][side].len())
+            .map(|lane| { self.compute_lane_load(direction, side, lane, roads) })
+            .sum()
+    }
+
+    /// Enables all the lanes of a side of a road arriving at this crossroad.
+    fn enable_side(&self, direction: usize, side: usize, roads: &mut Vec<Road>) {
+        for r in self.roads_arriving[direction][side].iter().filter_map(|&r| r) {
+            roads[r].enable();
+        }
+    }
+}
+
+/// Traffic light policy: chooses the roads enabled at a crossroad for a step. Only the cars from
+/// enabled roads are able to cross a crossroad.
+pub trait CrossroadPolicy: Send + Sync {
+    /// Enables some of the roads arriving at `crossroad`.
+    fn enable(&self, crossroad: &Crossroad, roads: &mut Vec<Road>);
+}
+
+/// Enables the most loaded road with some car waiting.
+pub struct MostLoadedSingle;
+
+impl CrossroadPolicy for MostLoadedSingle {
+    fn enable(&self, crossroad: &Crossroad, roads: &mut Vec<Road>) {
+        // Ties are broken by keeping the first road, in the order of the directions, sides and
+        // lanes.
+        let mut max_road = None;
+        let mut max_load = 0;
+
+        for d in 0..4 {
+            for s in 0..2 {
+                for l in 0..crossroad.roads_arriving[d][s].len() {
+                    let load = crossroad.compute_lane_load(d, s, l, roads);
+                    if load > max_load {
+                        max_load = load;
+                        max_road = crossroad.roads_arriving[d][s][l];
+                    }
+                }
+            }
+        }
+
+        if let Some(r) = max_road {
+            roads[r].enable();
+        }
+    }
+}
+
+/// Enables the pair of compatible roads with the maximum cumulated load, counting all the lanes
+/// of each road side. All the lanes of the chosen sides are enabled. This is the default policy.
+pub struct MaxPairLoad;
+
+impl CrossroadPolicy for MaxPairLoad {
+    fn enable(&self, crossroad: &Crossroad, roads: &mut Vec<Road>) {
         // We compute the pair of compatible roads with the maximum cumulated load.
+        // Ties are broken deterministically: the pair ((d1, s1), (d2, s2)) which is the smallest
+        // in lexicographic order (direction first, then side) is chosen. Hence the same network
+        // state always enables the same roads.
         let mut max_pair = ((NORTH, LEFT), (NORTH, LEFT));
         let mut max_load = 0;
 
@@ -187,34 +318,21 @@ impl Crossroad {
                             ((d + 2) % 4, s)
                         }
                     };
-                    let load = self.compute_load(d, s, roads) +
-                        self.compute_load(d2, s2, roads);
+                    let load = crossroad.compute_load(d, s, roads) +
+                        crossroad.compute_load(d2, s2, roads);
 
-                    if load > max_load {
+                    let pair = ((d, s), (d2, s2));
+                    if load > max_load || (load == max_load && pair < max_pair) {
                         max_load = load;
-                        max_pair = ((d, s), (d2, s2));
+                        max_pair = pair;
                     }
                 }
             }
         }
 
         let ((d1, s1), (d2, s2)) = max_pair;
-        if self.roads_arriving[d1][s1].is_some() {
-            roads[self.roads_arriving[d1][s1].unwrap()].enable();
-        }
-        if self.roads_arriving[d2][s2].is_some() {
-            roads[self.roads_arriving[d2][s2].unwrap()].enable();
-        }
-    }
-
-    /// Computes the load of a road, i.e. the numbers of cars on this road.
-    /// If there is no car ready to cross, returns 0.
-    fn compute_load(&self, direction: usize, side: usize, roads: &mut Vec<Road>) -> i32 {
-        let r = self.roads_arriving[direction][side];
-        if r.is_none() || !roads[r.unwrap()].is_waiting() {
-            return 0;
-        }
-        return roads[r.unwrap()].get_car_count();
+        crossroad.enable_side(d1, s1, roads);
+        crossroad.enable_side(d2, s2, roads);
     }
 }
 
@@ -222,6 +340,12 @@ impl Crossroad {
 impl Network {
     /// Creates a new empty Network, with specified width and heights.
     pub fn new(width: usize, height: usize) -> Network {
+        Network::with_seed(width, height, rand::thread_rng().gen())
+    }
+
+    /// Creates a new empty Network, with specified width and heights, whose spawns and
+    /// destinations are drawn from a random generator initialized with `seed`.
+    pub fn with_seed(width: usize, height: usize, seed: u64) -> Network {
         Network {
             width,
             height,
@@ -232,10 +356,57 @@ impl Network {
             roads: vec!(),
             graph: Graph::new(),
             car_graph: None,
+            free_weights: None,
             crossroads: vec!(),
+            reaction_factor: 2.,
+            policy: Arc::new(MaxPairLoad),
+            lanes: 2,
+            speed_limit: SpeedLimit::default(),
+            rng: StdRng::from_seed(&[seed as usize]),
+            steps: 0,
         }
     }
 
+    /// Sets the traffic light policy of the crossroads. The default policy is `MaxPairLoad`.
+    pub fn set_policy(&mut self, policy: Box<CrossroadPolicy>) {
+        self.policy = Arc::from(policy);
+    }
+
+    /// Sets the number of lanes per road direction of the roads added afterwards. The default is
+    /// two lanes. Panics if `lanes` is smaller than two, as a road needs a left and a right lane.
+    pub fn set_lanes(&mut self, lanes: usize) {
+        assert!(lanes >= 2, "A road needs at least two lanes.");
+        self.lanes = lanes;
+    }
+
+    /// Returns the number of lanes per road direction.
+    pub fn lanes(&self) -> usize {
+        self.lanes
+    }
+
+    /// Sets the speed limits of the roads added afterwards with `add_road`, e.g. by the loaders.
+    /// The default is `SpeedLimit::default()`. Panics if the maximum speed is 0.
+    pub fn set_speed_limit(&mut self, speed_limit: SpeedLimit) {
+        assert!(speed_limit.max_speed > 0, "A road needs a positive maximum speed.");
+        self.speed_limit = speed_limit;
+    }
+
+    /// Sets the number of cars fitting between the centers of two neighbor crossroads, and the
+    /// number of cars fitting in a crossroad, for the roads added afterwards. The defaults are 10
+    /// and 4. Panics if a road between neighbor crossroads would have no place.
+    pub fn set_road_scale(&mut self, cars_per_unit: i32, cars_per_crossroad: i32) {
+        assert!(cars_per_crossroad >= 0 && cars_per_unit > cars_per_crossroad,
+                "A road needs at least one place.");
+        self.cars_per_unit = cars_per_unit;
+        self.cars_per_crossroad = cars_per_crossroad;
+    }
+
+    /// Sets the reaction factor of the cars: a car at speed `s` keeps at least
+    /// `s * reaction_factor` free places in front of it.
+    pub fn set_reaction_factor(&mut self, reaction_factor: f32) {
+        self.reaction_factor = reaction_factor;
+    }
+
     /// Adds a crossroad to specified location.
     pub fn add_crossroad(&mut self, x: usize, y: usize) {
         let c = CrossroadId::new(x, y);
@@ -247,11 +418,16 @@ impl Network {
         self.crossroads.push(c);
     }
 
-    /// Adds a new specific road.
-    pub fn new_road(&mut self, src: CrossroadId, dest: CrossroadId, side: Side){
+    /// Adds a new specific road, on the specified lane, with the specified speed limits. Lanes
+    /// are numbered from the innermost one, the only left lane, to the outermost one. Panics if
+    /// the maximum speed is 0.
+    pub fn new_road(&mut self, src: CrossroadId, dest: CrossroadId, lane: usize,
+                    speed_limit: SpeedLimit) {
+        assert!(speed_limit.max_speed > 0, "A road needs a positive maximum speed.");
         // We get the parameters of the road.
         let (dx, dy, length) = src.join(dest);
         let length = length * self.cars_per_unit - self.cars_per_crossroad;
+        let (side, side_lane) = lane_side(lane);
         let (d1, d2) = compute_directions(dx, dy, side);
         let id = self.roads.len();
 
@@ -261,15 +437,17 @@ impl Network {
             start: src,
             end: dest,
             side,
+            lane,
             destination: self.crossroad(dest).nodes[d2],
             length: length as usize,
+            speed_limit,
         };
 
         // Then, we add it to the crossroads and the roads.
         let road = Road::new(road_info);
         self.roads.push(road);
-        self.crossroad_mut(src).roads[d1][side] = Some(id);
-        self.crossroad_mut(dest).roads_arriving[d1][side] = Some(id);
+        set_lane(&mut self.crossroad_mut(src).roads[d1][side], side_lane, id);
+        set_lane(&mut self.crossroad_mut(dest).roads_arriving[d1][side], side_lane, id);
 
         // Then, it builds the two corresponding edges in the graph.
         let (n1, n2) = {
@@ -282,7 +460,7 @@ impl Network {
         self.graph.add_edge(n2, n3, id);
     }
 
-    /// Add the two road linking the first crossroad to the second one.
+    /// Add the roads, one per lane, linking the first crossroad to the second one.
     pub fn add_road(&mut self, (src_x, src_y): (usize, usize), (dest_x, dest_y): (usize, usize)) {
         let (src, dest) =
             (CrossroadId::new(src_x, src_y), CrossroadId::new(dest_x, dest_y));
@@ -300,9 +478,11 @@ impl Network {
             self.assert_crossroad_not_exists(src + (k*dx, k*dy));
         }
 
-        // Creates both roads.
-        self.new_road(src, dest, LEFT);
-        self.new_road(src, dest, RIGHT);
+        // Creates all the lanes.
+        for lane in 0..self.lanes {
+            let speed_limit = self.speed_limit;
+            self.new_road(src, dest, lane, speed_limit);
+        }
     }
 
     /// Adds all roads between the crossroads `c1` and `c2`.
@@ -311,6 +491,42 @@ impl Network {
         self.add_road(c2, c1);
     }
 
+    /// Inserts the crossroads and roads of `other` into the network, shifted by `offset`.
+    /// The roads and graph nodes of `other` get fresh identifiers, and the road lengths follow the
+    /// parameters of the network. Cars are not transferred.
+    /// Panics if a crossroad of `other` overlaps an existing one. The two parts are not linked:
+    /// bridging roads have to be added afterwards, e.g. with `add_all_roads`.
+    pub fn merge(&mut self, other: Network, (offset_x, offset_y): (usize, usize)) {
+        // We first grow the grid if needed.
+        let width = self.width.max(other.width + offset_x);
+        let height = self.height.max(other.height + offset_y);
+        for line in self.grid.iter_mut() {
+            while line.len() < width {
+                line.push(None);
+            }
+        }
+        while self.grid.len() < height {
+            self.grid.push((0..width).map(|_| { None }).collect());
+        }
+        self.width = width;
+        self.height = height;
+
+        // Then, we add all the crossroads and the roads between them.
+        let shift = |c: CrossroadId| { CrossroadId::new(c.x + offset_x, c.y + offset_y) };
+        for &c in &other.crossroads {
+            let c = shift(c);
+            self.add_crossroad(c.x, c.y);
+        }
+        for r in &other.roads {
+            let r = r.info();
+            self.new_road(shift(r.start), shift(r.end), r.lane, r.speed_limit);
+        }
+
+        // The graph and weights shared with the cars are outdated.
+        self.car_graph = None;
+        self.free_weights = None;
+    }
+
     /// Panics if the crossroad exists.
     pub fn assert_crossroad_exists(&self, c: CrossroadId) {
         if self.grid[c].is_none() {
@@ -335,27 +551,30 @@ impl Network {
         self.grid[c].as_mut().unwrap()
     }
 
-    /// Creates a new car. It transfers the current graph to the car, with a fresh identifier.
+    /// Creates a new car. It transfers the current graph and the weights of the empty roads to
+    /// the car, with a fresh identifier.
     pub fn create_car(&mut self) -> Car {
         if self.car_graph.is_none() {
-            // If needed, we generate this shared reference.
+            // If needed, we generate these shared references.
             self.car_graph = Some(Arc::new(self.clone_graph()));
+            let free_weights = self.roads.iter().map(|r| r.free_weight()).collect();
+            self.free_weights = Some(Arc::new(EdgesWeight::new(free_weights)));
         }
         let id = self.car_count;
         self.car_count += 1;
 
-        Car::new(id, 0, CrossroadId::new(0, 0), self.car_graph.clone().unwrap())
+        Car::new(id, 0, CrossroadId::new(0, 0), self.car_graph.clone().unwrap(),
+                 self.free_weights.clone().unwrap())
     }
 
     /// Spawns a car on a random road, and finds a random destination.
     pub fn generate_request(&mut self, id: CarId) -> (RoadInfo, usize, CrossroadId) {
         // First, it finds a road to spawn the car.
-        let mut rng = rand::thread_rng();
-        let mut road_id = rng.gen_range(0, self.roads.len());
+        let mut road_id = self.rng.gen_range(0, self.roads.len());
 
         let mut pos = self.roads[road_id].spawn_car(id);
         while pos == -1 {
-            road_id = rng.gen_range(0, self.roads.len());
+            road_id = self.rng.gen_range(0, self.roads.len());
             pos = self.roads[road_id].spawn_car(id);
         }
 
@@ -385,8 +604,16 @@ impl Network {
 
     /// Makes the crossroads enable some roads.
     pub fn enable_paths(&mut self) {
+        self.enable_paths_blocked(&HashSet::new());
+    }
+
+    /// Makes the crossroads enable some roads, except the `blocked` crossroads, where some
+    /// pedestrian is crossing: all their roads conflict with the pedestrian.
+    pub fn enable_paths_blocked(&mut self, blocked: &HashSet<CrossroadId>) {
         for &c in &self.crossroads {
-            self.grid[c].as_ref().unwrap().enable_path(&mut self.roads);
+            if !blocked.contains(&c) {
+                self.policy.enable(self.grid[c].as_ref().unwrap(), &mut self.roads);
+            }
         }
     }
 
@@ -395,6 +622,7 @@ impl Network {
     pub fn roads_step(&mut self, actions: &mut Vec<Action>, moves: &mut Vec<Move>, speeds: &Vec<Speed>)
                    -> EdgesWeight
     {
+        let reaction_factor = self.reaction_factor;
         let roads = &mut self.roads;
 
         // All the possibles enabled paths are tried.
@@ -406,17 +634,29 @@ impl Network {
         // We make a step for all remaining cars, and get the weights estimations.
         let mut weights = vec!();
         for i in 0..roads.len() {
-            weights.push(roads[i].step_forward(moves, speeds));
+            weights.push(roads[i].step_forward(moves, speeds, reaction_factor));
         }
         let edges_weight = EdgesWeight::new(weights);
+        self.steps += 1;
 
         return edges_weight
     }
 
-    /// Returns the central reactive process of the network.
-    pub fn process(mut self, central_signal: SPMCSignalSender<Arc<GlobalInfo>>,
-                   pos_signal: MPSCSignalReceiver<(CarId, (Action, Speed)), (Vec<Action>, Vec<Speed>)>)
-                   -> impl Process<Value=()> {
+    /// Returns the statistics of the roads, accumulated since the creation of the network.
+    pub fn stats(&self) -> NetworkStats {
+        NetworkStats { steps: self.steps, roads: self.roads.iter().map(|r| r.stats()).collect() }
+    }
+
+    /// Returns the central reactive process of the network. The crossroads whose signal in
+    /// `crossings` is present during an instant are blocked for the step computed from the car
+    /// actions of this instant. At each step, the cars requested through `spawner` are created
+    /// and passed to `launch_car`, which is responsible for running their processes.
+    pub fn process<F>(mut self, central_signal: SPMCSignalSender<Arc<GlobalInfo>>,
+                      pos_signal: MPSCSignalReceiver<(CarId, (Action, Speed)), (Vec<Action>, Vec<Speed>)>,
+                      crossings: CrossingSignals, spawner: CarSpawner, mut launch_car: F)
+                      -> impl Process<Value=()>
+        where F: FnMut(&mut Runtime, Car) + Send + 'static
+    {
 
         let mut weights = vec!();
         for r in &self.roads {
@@ -426,13 +666,16 @@ impl Network {
         let mut step = 0;
         let mut mean_moves = self.car_count as f32;
         let beta = 0.99;
+        let stationary = Arc::new(AtomicBool::new(false));
+        let stationary2 = stationary.clone();
 
-        let cont = move | (mut actions, speeds): (Vec<Action>, Vec<Speed>) | {
+        let cont = move |runtime: &mut Runtime, ((mut actions, speeds), present): ((Vec<Action>, Vec<Speed>), Vec<(CrossroadId, bool)>)| {
             // We count the steps.
             step += 1;
 
-            // We enable some path.
-            self.enable_paths();
+            // We enable some path, where no pedestrian is crossing.
+            let blocked = present.into_iter().filter(|&(_, p)| p).map(|(c, _)| c).collect();
+            self.enable_paths_blocked(&blocked);
 
             // We compute the road step and get back some weights.
             let mut moves = (0..actions.len()).map(|_| { Move::NONE }).collect();
@@ -441,28 +684,46 @@ impl Network {
             // We spawn the cars that requested to be.
             self.spawn_cars(actions, &mut moves);
 
+            // We create the cars requested by the control processes, which act from the next step.
+            for _ in 0..spawner.take_requests() {
+                launch_car(runtime, self.create_car());
+            }
+
             // We count the number of cars that did something.
             let nb_moves: i32 = moves.iter().map(| m | { match m {
                 &Move::NONE => 0,
                 _ => 1,
             }}).sum();
 
-            // We keep some moving mean of this number. If it is too low, nothing is happening, so
-            // it panics.
+            // We keep some moving mean of this number. If it is too low, nothing is happening
+            // anymore, so the network stops once this step is emitted.
             mean_moves = beta * mean_moves + (1. - beta) * (nb_moves as f32);
             if mean_moves < 1e-3 {
-                panic!("It looks like a stationary state: not enough moves.");
+                stationary2.store(true, Ordering::SeqCst);
             }
 
             // Returns the updated information about the step.
-            Arc::new(GlobalInfo { weights, moves })
+            Arc::new(GlobalInfo { weights, moves, stats: self.stats() })
+        };
+
+        // Tests the presence of pedestrians on each crossroad.
+        let presence = multi_join(crossings.into_iter().map(|(c, signal)| {
+            let with_crossroad = move |p: bool| (c, p);
+            signal.is_present().map(with_crossroad)
+        }).collect());
+
+        // Stops the loop in a stationary state.
+        let exit = move |()| {
+            if stationary.load(Ordering::SeqCst) { LoopStatus::Exit(()) } else { LoopStatus::Continue }
         };
 
         let p =
             pos_signal.await_in()                   // Awaits the car actions
-                .map(cont)                          // Computes the resulting moves and weights.
+                .join(presence)                     // and the pedestrians of the same instant.
+                .map_rt(cont)                       // Computes the resulting moves and weights.
                 .emit_consume(central_signal)       // Emits this information.
-                .loop_inf();                        // Loops.
+                .map(exit)
+                .loop_while();                      // Loops until a stationary state.
         return p;
     }
 
@@ -495,6 +756,23 @@ impl Network {
         char_map.into_iter().map(|line| { line.into_iter().collect::<String>().add("\n") }).collect()
     }
 
+    /// Returns the network in the ASCII format read by `load_string`. Loading the result gives
+    /// back the same crossroads and roads, except that adjacent crossroads joined in a single
+    /// direction are joined in both directions once reloaded.
+    pub fn save_string(&self) -> String {
+        self.to_string()
+    }
+
+    /// Saves the network to a file located in trafficsim/maps/, in the format read by
+    /// `load_file`.
+    pub fn save_file(&self, filename: &str) {
+        let mut f = File::create(format!("./src/trafficsim/maps/{}", filename))
+            .expect("Could not create the file");
+
+        f.write_all(self.save_string().as_bytes())
+            .expect("Something went wrong writing the file");
+    }
+
     /// Loads a network from a file located in trafficsim/maps/.
     pub fn load_file(&mut self, filename: &str) {
         let mut f = File::open(format!("./src/trafficsim/maps/{}", filename)).expect("File not found");
@@ -528,8 +806,8 @@ impl Network {
             }
         }
 
-        // We change the network size.
-        *self = Network::new((width + 1) / 2, (height + 1) / 2);
+        // We empty the network and change its size, keeping its configuration.
+        self.clear((width + 1) / 2, (height + 1) / 2);
 
         // Then, we add all the crossroads.
         for (j, line) in char_map.iter().enumerate() {
@@ -600,14 +878,30 @@ impl Network {
         }
     }
 
+    /// Removes all the crossroads, roads and cars, and resizes the network to `width` and
+    /// `height`. The configuration of the network is kept, e.g. its random generator, policy,
+    /// reaction factor, lanes, speed limits and road scale.
+    fn clear(&mut self, width: usize, height: usize) {
+        self.width = width;
+        self.height = height;
+        self.grid = none_array(height, width);
+        self.roads = vec!();
+        self.graph = Graph::new();
+        self.car_graph = None;
+        self.free_weights = None;
+        self.crossroads = vec!();
+        self.car_count = 0;
+        self.steps = 0;
+    }
+
     /// Returns the cloned graph.
     pub fn clone_graph(&self) -> Graph {
         self.graph.clone()
     }
 
     /// Returns random crossroad coordinates (of an existing crossroad).
-    pub fn random_crossroad(&self) -> CrossroadId {
-        let i = rand::thread_rng().gen_range(0, self.crossroads.len());
+    pub fn random_crossroad(&mut self) -> CrossroadId {
+        let i = self.rng.gen_range(0, self.crossroads.len());
         self.crossroads[i]
     }
 
@@ -621,34 +915,26 @@ impl Network {
             n.edges().is_empty()
         }).collect();
 
-        // Then, we mark all the roads that do not end in a dead end.
-        let used_roads: Vec<bool> = self.roads.iter().map(|r| {
-            !dead_ends[r.info().destination]
+        // Then, we keep all the interesting crossroads, i.e. that don't have 4 dead end nodes.
+        let crossroads: Vec<CrossroadId> = self.crossroads.iter().cloned().filter(|&c| {
+            self.crossroad(c).nodes.iter().any(|id| !dead_ends[*id])
         }).collect();
 
-        // We create a fresh network.
-        let mut network = Network::new(self.width, self.height);
+        // And all the roads that do not end in a dead end.
+        let roads: Vec<RoadInfo> = self.roads.iter().map(|r| r.info())
+            .filter(|r| !dead_ends[r.destination])
+            .collect();
 
-        // Then, we add all the interesting crossroads, i.e. that don't have 4 dead end nodes.
-        for &c in &self.crossroads {
-            let c = self.crossroad(c);
-            if c.nodes.iter()
-                .map(|id| { !dead_ends[*id] })
-                .fold(false, |x, y| { x || y }) {
-                network.add_crossroad(c.id.x, c.id.y);
-            }
+        // Finally, we rebuild the network from them, keeping the configuration.
+        let (width, height) = (self.width, self.height);
+        self.clear(width, height);
+        for c in crossroads {
+            self.add_crossroad(c.x, c.y);
         }
-
-        // Finally, we add only the used edges.
-        for r in &self.roads {
-            let r = r.info();
-            if used_roads[r.id] {
-                network.new_road(r.start, r.end, r.side);
-            }
+        for r in roads {
+            self.new_road(r.start, r.end, r.lane, r.speed_limit);
         }
 
-        // We change the initial network.
-        *self = network;
         println!("After simplification, it only has {} crossroads and {} roads.",
                  self.crossroads.len(), self.roads.len());
     }
@@ -665,6 +951,29 @@ pub fn none_array<T>(height: usize, width: usize) -> Vec<Vec<Option<T>>> {
     (0..height).map(|_| { (0..width).map(|_| { None }).collect()}).collect()
 }
 
+/// Returns the roads of a crossroad without any lane, indexed by direction and side.
+fn empty_lanes() -> Vec<Vec<Vec<Option<RoadId>>>> {
+    (0..4).map(|_| { (0..2).map(|_| { vec!() }).collect() }).collect()
+}
+
+/// Stores the road `id` at index `lane` of the lanes of a road side.
+fn set_lane(lanes: &mut Vec<Option<RoadId>>, lane: usize, id: RoadId) {
+    while lanes.len() <= lane {
+        lanes.push(None);
+    }
+    lanes[lane] = Some(id);
+}
+
+/// Returns the side of a lane, and its index among the lanes of this side.
+/// The innermost lane is the only left lane, the other ones are right lanes.
+pub fn lane_side(lane: usize) -> (Side, usize) {
+    if lane == 0 {
+        (LEFT, 0)
+    } else {
+        (RIGHT, lane - 1)
+    }
+}
+
 /// Computes the road direction and its node direction.
 pub fn compute_directions(dx: i32, dy: i32, side: Side) -> (usize, usize) {
     let d1 = match (dx, dy) {
@@ -682,4 +991,314 @@ pub fn compute_directions(dx: i32, dy: i32, side: Side) -> (usize, usize) {
 /// Returns the previous (clockwise) direction.
 pub fn previous_direction(d: usize) -> usize {
     (d + 3) % 4
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_enable_path_tie_breaking() {
+    let mut network = Network::new(0, 0);
+    network.load_string("  C\n  |\nC-C-C\n  |\n  C");
+    let center = CrossroadId::new(1, 1);
+
+    // We put one waiting car on each road arriving at the center: all the pairs have the same load.
+    let arriving: Vec<RoadId> = network.roads.iter()
+        .map(|r| r.info())
+        .filter(|r| r.end == center)
+        .map(|r| r.id)
+        .collect();
+    for (car, &r) in arriving.iter().enumerate() {
+        network.roads[r].spawn_car(car);
+    }
+
+    // The pair of roads going north (from the southern crossroad) is the smallest one.
+    let mut expected: Vec<RoadId> = arriving.iter().cloned()
+        .filter(|&r| network.roads[r].info().start == CrossroadId::new(1, 2))
+        .collect();
+    expected.sort();
+
+    for _ in 0..10 {
+        network.enable_paths();
+        let mut enabled: Vec<RoadId> = arriving.iter().cloned()
+            .filter(|&r| network.roads[r].is_enabled())
+            .collect();
+        enabled.sort();
+        assert_eq!(enabled, expected);
+
+        for r in network.roads.iter_mut() {
+            r.update_status();
+        }
+    }
+}
+
+#[test]
+fn test_merge() {
+    let mut network = Network::new(0, 0);
+    network.load_string("C-C");
+    let mut other = Network::new(0, 0);
+    other.load_string("C-C\n|\nC");
+    network.merge(other, (2, 0));
+
+    assert_eq!(network.crossroads.len(), 5);
+    assert_eq!(network.roads.len(), 12);
+    assert_eq!((network.width, network.height), (4, 2));
+
+    // Returns the cost and the last crossroad of the shortest path between two crossroads.
+    let route = |network: &Network, src: CrossroadId, dest: CrossroadId| {
+        let graph = network.clone_graph();
+        let weights = EdgesWeight::new(network.roads.iter().map(|r| r.weight()).collect());
+        let (path, cost) = dijkstra(network.crossroad(src).nodes[EAST], |c| *c == dest, &graph, &weights);
+        let last = path.first().map(|&e| *graph.get_node(graph.get_edge(e).destination()).info());
+        (cost, last)
+    };
+
+    let (src, dest) = (CrossroadId::new(0, 0), CrossroadId::new(2, 1));
+    assert!(route(&network, src, dest).1.is_none());
+
+    // Once a bridging road is added, the cars can go across the junction.
+    network.add_all_roads((1, 0), (2, 0));
+    let (cost, last) = route(&network, src, dest);
+    assert!(cost < ::std::f32::MAX);
+    assert!(last == Some(dest));
+}
+
+#[test]
+#[should_panic(expected = "already exists")]
+fn test_merge_overlapping() {
+    let mut network = Network::new(0, 0);
+    network.load_string("C-C");
+    let mut other = Network::new(0, 0);
+    other.load_string("C-C");
+    // The first crossroad of `other` lands on the second one of the network.
+    network.merge(other, (1, 0));
+}
+
+#[test]
+fn test_most_loaded_single() {
+    let mut network = Network::new(0, 0);
+    network.load_string("  C\n  |\nC-C-C\n  |\n  C");
+    let center = CrossroadId::new(1, 1);
+    network.set_policy(Box::new(MostLoadedSingle));
+
+    // A road with three cars waiting, and two roads with a single car waiting.
+    let arriving: Vec<RoadId> = network.crossroad(center).roads_arriving.iter()
+        .flat_map(|sides| sides.iter().flat_map(|lanes| lanes.iter().filter_map(|&r| r)))
+        .collect();
+    let (heavy, others) = (arriving[0], &arriving[1..3]);
+    for id in 0..3 {
+        network.roads[heavy].spawn_car(id);
+    }
+    for (id, &r) in others.iter().enumerate() {
+        network.roads[r].spawn_car(3 + id);
+    }
+
+    network.enable_paths();
+    assert!(network.roads[heavy].is_enabled());
+    assert!(others.iter().all(|&r| !network.roads[r].is_enabled()));
+}
+
+#[test]
+fn test_seeded_requests() {
+    // Returns the spawn roads, positions and destinations of a few cars.
+    let requests = |seed: u64| {
+        let mut network = Network::with_seed(0, 0, seed);
+        network.load_string("C-C-C\n| | |\nC-C-C\n| |\nC-C");
+        (0..8).map(|id| {
+            let (road_info, pos, destination) = network.generate_request(id);
+            (road_info.id, pos, destination)
+        }).collect::<Vec<_>>()
+    };
+
+    assert!(requests(42) == requests(42));
+    assert!(requests(42) != requests(43));
+}
+
+#[test]
+fn test_three_lanes() {
+    let mut network = Network::new(0, 0);
+    network.set_lanes(3);
+    network.load_string("C-C");
+    assert_eq!(network.roads.len(), 6);
+
+    // Each direction has a left lane and two right lanes, numbered from the innermost one.
+    let (west, east) = (CrossroadId::new(0, 0), CrossroadId::new(1, 0));
+    let c = network.crossroad(east);
+    assert_eq!(c.roads_arriving[EAST][LEFT].len(), 1);
+    assert_eq!(c.roads_arriving[EAST][RIGHT].len(), 2);
+    let lanes: Vec<usize> = c.roads_arriving[EAST].iter()
+        .flat_map(|lanes| lanes.iter().map(|r| network.roads[r.unwrap()].info().lane))
+        .collect();
+    assert_eq!(lanes, vec!(0, 1, 2));
+
+    // The two right lanes lead to the same node, and are enabled together.
+    let right: Vec<RoadId> = c.roads_arriving[EAST][RIGHT].iter().map(|r| r.unwrap()).collect();
+    assert_eq!(network.roads[right[0]].info().destination, network.roads[right[1]].info().destination);
+    network.roads[right[1]].spawn_car(0);
+    network.enable_paths();
+    assert!(right.iter().all(|&r| network.roads[r].is_enabled()));
+    assert_eq!(network.crossroad(west).roads[EAST][RIGHT].len(), 2);
+}
+
+#[test]
+fn test_save_load() {
+    let mut network = Network::new(0, 0);
+    network.load_file("map1");
+    let saved = network.save_string();
+
+    let mut reloaded = Network::new(0, 0);
+    reloaded.load_string(&saved);
+    assert_eq!(reloaded.crossroads.len(), network.crossroads.len());
+    assert_eq!(reloaded.roads.len(), network.roads.len());
+    assert_eq!(reloaded.save_string(), saved);
+}
+
+#[test]
+fn test_load_road_scale() {
+    let mut network = Network::new(0, 0);
+    network.set_road_scale(20, 6);
+    network.load_file("map1");
+    assert_eq!((network.cars_per_unit, network.cars_per_crossroad), (20, 6));
+    assert!(!network.roads.is_empty());
+    for road in &network.roads {
+        let (_, _, units) = road.info().start.join(road.info().end);
+        assert_eq!(road.info().length as i32, units * 20 - 6);
+    }
+}
+
+#[test]
+fn test_pedestrian_blocks_crossing() {
+    let mut network = Network::new(0, 0);
+    network.load_string("C-C-C");
+    let center = CrossroadId::new(1, 0);
+    // The moving mean of the moves starts at the number of cars.
+    network.create_car();
+
+    // A car waits at the center, and wants to vanish there.
+    let road = network.roads.iter().map(|r| r.info()).find(|r| r.end == center).unwrap().id;
+    network.roads[road].spawn_car(0);
+
+    let (central_sender, central_receiver) = spmc_signal::new();
+    let (pos_sender, pos_receiver) = mpsc_signal::new(
+        |(_, (action, speed)): (CarId, (Action, Speed)), _: (Vec<Action>, Vec<Speed>)| {
+            (vec!(action), vec!(speed))
+        });
+    let car = value((0, (Action::VANISH, 0))).emit(&pos_sender).pause().loop_inf();
+
+    // A pedestrian crosses the center during the first three instants.
+    let crossings = crossing_signals(&network);
+    let pedestrian = Pedestrian::new(center, 3, 100).process(&crossings);
+
+    // Records whether the car vanished, at each step.
+    let vanished = Arc::new(Mutex::new(vec!()));
+    let vanished2 = vanished.clone();
+    let record = move |info: Arc<GlobalInfo>| {
+        vanished2.lock().unwrap().push(match info.moves[0] { Move::VANISH => true, _ => false });
+    };
+    let observer = central_receiver.await_in().map(record).loop_inf();
+
+    let process = network.process(central_sender, pos_receiver, crossings, CarSpawner::new(), |_: &mut Runtime, _| ())
+        .join(car).join(pedestrian).join(observer);
+    engine::execute_process_steps(process, 2, 8);
+
+    assert_eq!(&vanished.lock().unwrap()[..5], &[false, false, false, true, false]);
+}
+
+#[test]
+fn test_spawn_new_car() {
+    let mut network = Network::new(0, 0);
+    network.load_string("C-C-C");
+    network.create_car();
+    network.roads[0].spawn_car(0);
+
+    let (central_sender, _) = spmc_signal::new();
+    let (pos_sender, pos_receiver) = mpsc_signal::new(
+        |(_, (action, speed)): (CarId, (Action, Speed)), _: (Vec<Action>, Vec<Speed>)| {
+            (vec!(action), vec!(speed))
+        });
+    let car = value((0, (Action::VANISH, 0))).emit(&pos_sender).pause().loop_inf();
+    let crossings = crossing_signals(&network);
+
+    // A control process requests two cars at instant 2, which get the next identifiers.
+    let spawner = CarSpawner::new();
+    let spawner2 = spawner.clone();
+    let control = value(()).pause().pause().map(move |()| {
+        spawner2.spawn_new_car();
+        spawner2.spawn_new_car();
+    });
+    let launched = Arc::new(Mutex::new(vec!()));
+    let launched2 = launched.clone();
+    let launch_car = move |_: &mut Runtime, car: Car| launched2.lock().unwrap().push(car.id());
+
+    let process = network.process(central_sender, pos_receiver, crossings, spawner, launch_car)
+        .join(car).join(control);
+    engine::execute_process_steps(process, 2, 5);
+
+    assert_eq!(*launched.lock().unwrap(), vec![1, 2]);
+}
+
+#[test]
+fn test_road_stats() {
+    let mut network = Network::new(0, 0);
+    network.load_string("C-C");
+    for _ in 0..3 {
+        network.create_car();
+    }
+
+    // Three cars on the road ending at the right crossroad, two of them vanish there and the last
+    // one crosses to the opposite road, and vanishes at its end.
+    let right = CrossroadId::new(1, 0);
+    let road = network.roads.iter().find(|r| r.info().end == right).unwrap().info().id;
+    let back = network.roads.iter().find(|r| r.info().start == right).unwrap().info().id;
+    for id in 0..3 {
+        network.roads[road].spawn_car(id);
+    }
+    let mut actions = vec![Action::VANISH, Action::VANISH, Action::CROSS(back)];
+    let speeds = vec![0; 3];
+
+    let mut crossed = 0;
+    for _ in 0..20 {
+        network.enable_paths();
+        let mut moves = vec![Move::NONE; 3];
+        network.roads_step(&mut actions, &mut moves, &speeds);
+        crossed += moves.iter().filter(|m| match **m { Move::VANISH | Move::CROSS(_) => true, _ => false }).count();
+        if let Move::CROSS(_) = moves[2] {
+            actions[2] = Action::VANISH;
+        }
+    }
+
+    let stats = network.stats();
+    assert_eq!(stats.steps, 20);
+    assert_eq!(crossed, 4);
+    assert_eq!(stats.roads[road].delivered, 3);
+    assert_eq!(stats.roads[back].delivered, 1);
+    assert_eq!(stats.delivered(), crossed);
+    assert!(stats.throughput(road) > 0.);
+    assert!(stats.roads[back].occupancy > 0.);
+    assert_eq!(stats.to_string().lines().count(), stats.roads.len() + 1);
+}
+
+#[test]
+fn test_load_keeps_configuration() {
+    let mut network = Network::new(0, 0);
+    network.set_policy(Box::new(MostLoadedSingle));
+    network.set_reaction_factor(3.);
+    network.set_lanes(3);
+    let policy = network.policy.clone();
+
+    network.load_string("C-C-C\n|\nC");
+    assert!(Arc::ptr_eq(&policy, &network.policy));
+    assert_eq!(network.reaction_factor, 3.);
+    assert_eq!(network.lanes(), 3);
+    assert_eq!(network.crossroads.len(), 4);
+
+    // Removing the dead ends keeps it too.
+    network.simplify();
+    assert!(Arc::ptr_eq(&policy, &network.policy));
+    assert_eq!(network.reaction_factor, 3.);
+    assert_eq!(network.lanes(), 3);
+}
+
+#[test]
+#[should_panic(expected = "positive maximum speed")]
+fn test_zero_max_speed() {
+    let mut network = Network::new(0, 0);
+    network.set_speed_limit(SpeedLimit { max_speed: 0, acceleration: 3 });
+}
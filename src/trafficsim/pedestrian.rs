@@ -0,0 +1,58 @@
+extern crate reactivers;
+use reactivers::engine::process::*;
+use reactivers::engine::signal::*;
+use reactivers::engine::signal::puresignal::PureSignal;
+
+use super::network::*;
+
+use std::collections::HashMap;
+
+/// Signals on which the pedestrians emit their presence, indexed by crossroad.
+pub type CrossingSignals = HashMap<CrossroadId, PureSignal>;
+
+/// Creates a presence signal for each crossroad of the network.
+pub fn crossing_signals(network: &Network) -> CrossingSignals {
+    network.crossroads.iter().map(|&c| (c, puresignal::new())).collect()
+}
+
+/// A Pedestrian, crossing a crossroad periodically. While a pedestrian is on a crossroad, no car
+/// can cross it.
+#[derive(Copy, Clone)]
+pub struct Pedestrian {
+    crossroad: CrossroadId, // Crossroad crossed by the pedestrian.
+    crossing_time: usize,   // Number of instants spent on the crossroad at each crossing.
+    interval: usize,        // Number of instants between two crossings.
+}
+
+impl Pedestrian {
+    /// Creates a new pedestrian, which starts crossing `crossroad` at the first instant. Panics if
+    /// both `crossing_time` and `interval` are 0.
+    pub fn new(crossroad: CrossroadId, crossing_time: usize, interval: usize) -> Pedestrian {
+        assert!(crossing_time + interval > 0, "A pedestrian needs a positive crossing period.");
+        Pedestrian { crossroad, crossing_time, interval }
+    }
+
+    /// Returns true if the pedestrian is on its crossroad at the specified step.
+    fn is_crossing(&self, step: usize) -> bool {
+        step % (self.crossing_time + self.interval) < self.crossing_time
+    }
+
+    /// Returns the reactive process corresponding to the pedestrian. It emits the signal of its
+    /// crossroad at each instant it spends on it.
+    pub fn process(self, crossings: &CrossingSignals) -> impl Process<Value=()> {
+        let signal = crossings[&self.crossroad].clone();
+
+        let mut step = 0;
+        let crossing = move |()| {
+            let crossing = self.is_crossing(step);
+            step += 1;
+            crossing
+        };
+
+        value(())
+            .map(crossing)
+            .then_else(value(()).emit(&signal), value(()))
+            .pause()
+            .loop_inf()
+    }
+}
@@ -0,0 +1,136 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+/// Minimal playback speed multiplier.
+const MIN_SPEED: f64 = 0.125;
+
+/// Maximal playback speed multiplier.
+const MAX_SPEED: f64 = 8.;
+
+/// State of the playback, shared between the GUI and the simulation.
+struct PlaybackState {
+    paused: bool,   // True if the simulation is paused.
+    steps: usize,   // Number of instants the simulation may run while paused.
+    speed: f64,     // Speed multiplier of the simulation.
+}
+
+/// Playback controls of a simulation, shared between the GUI and the simulation.
+///
+/// The simulation calls `wait_turn` before sending the moves of each instant to the GUI: while
+/// paused, it blocks until a step is requested or the playback resumes. It never gets more than
+/// one instant ahead of the GUI, so no frame is skipped or piled up while paused.
+#[derive(Clone)]
+pub struct Playback {
+    state: Arc<(Mutex<PlaybackState>, Condvar)>,
+}
+
+impl Playback {
+    /// Creates a new playback, running at normal speed.
+    pub fn new() -> Playback {
+        let state = PlaybackState { paused: false, steps: 0, speed: 1. };
+        Playback { state: Arc::new((Mutex::new(state), Condvar::new())) }
+    }
+
+    /// Returns true if the playback is paused.
+    pub fn is_paused(&self) -> bool {
+        self.state.0.lock().unwrap().paused
+    }
+
+    /// Returns the speed multiplier of the playback.
+    pub fn speed(&self) -> f64 {
+        self.state.0.lock().unwrap().speed
+    }
+
+    /// Pauses the playback if it is running, or resumes it otherwise.
+    pub fn toggle_pause(&self) {
+        let &(ref state, ref cvar) = &*self.state;
+        let mut state = state.lock().unwrap();
+        state.paused = !state.paused;
+        state.steps = 0;
+        cvar.notify_all();
+    }
+
+    /// Resumes the playback.
+    pub fn resume(&self) {
+        let &(ref state, ref cvar) = &*self.state;
+        state.lock().unwrap().paused = false;
+        cvar.notify_all();
+    }
+
+    /// Lets the simulation run one more instant while paused. Has no effect if running.
+    pub fn step(&self) {
+        let &(ref state, ref cvar) = &*self.state;
+        let mut state = state.lock().unwrap();
+        if state.paused {
+            state.steps += 1;
+            cvar.notify_all();
+        }
+    }
+
+    /// Doubles the speed of the playback, up to `MAX_SPEED`.
+    pub fn speed_up(&self) {
+        let mut state = self.state.0.lock().unwrap();
+        state.speed = f64::min(MAX_SPEED, state.speed * 2.);
+    }
+
+    /// Halves the speed of the playback, down to `MIN_SPEED`.
+    pub fn slow_down(&self) {
+        let mut state = self.state.0.lock().unwrap();
+        state.speed = f64::max(MIN_SPEED, state.speed / 2.);
+    }
+
+    /// Blocks while the playback is paused and no step is requested, then consumes a step if
+    /// paused. Returns the time the simulation should wait for an instant lasting `duration` at
+    /// normal speed.
+    pub fn wait_turn(&self, duration: Duration) -> Duration {
+        let &(ref state, ref cvar) = &*self.state;
+        let mut state = state.lock().unwrap();
+        while state.paused && state.steps == 0 {
+            state = cvar.wait(state).unwrap();
+        }
+        if state.paused {
+            state.steps -= 1;
+        }
+
+        let nanos = (duration.as_secs() as f64 * 1e9 + duration.subsec_nanos() as f64) / state.speed;
+        Duration::new((nanos / 1e9) as u64, (nanos % 1e9) as u32)
+    }
+}
+
+#[test]
+fn test_playback() {
+    use std::thread;
+
+    let playback = Playback::new();
+    let second = Duration::from_secs(1);
+    assert_eq!(playback.wait_turn(second), second);
+
+    playback.speed_up();
+    assert_eq!(playback.wait_turn(second), Duration::from_millis(500));
+    for _ in 0..10 {
+        playback.slow_down();
+    }
+    assert_eq!(playback.speed(), MIN_SPEED);
+    assert_eq!(playback.wait_turn(second), Duration::from_secs(8));
+
+    // While paused, the simulation runs only the requested steps.
+    playback.toggle_pause();
+    playback.step();
+    playback.step();
+    let (turns, playback2) = (Arc::new(Mutex::new(0)), playback.clone());
+    let turns2 = turns.clone();
+    let simulation = thread::spawn(move || {
+        for _ in 0..3 {
+            playback2.wait_turn(second);
+            *turns2.lock().unwrap() += 1;
+        }
+    });
+    thread::sleep(Duration::from_millis(50));
+    assert_eq!(*turns.lock().unwrap(), 2);
+
+    // The simulation is released when resuming.
+    playback.resume();
+    simulation.join().unwrap();
+    assert_eq!(*turns.lock().unwrap(), 3);
+    assert!(!playback.is_paused());
+}
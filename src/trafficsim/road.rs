@@ -5,6 +5,25 @@ use super::network::*;
 /// Road identifier.
 pub type RoadId = usize;
 
+/// Maximum speed reached without any car ahead, in places per step. Without speed limits, the
+/// speed was only bounded by the acceleration, the traffic and the end of the road: this default
+/// slows the cars down on the roads longer than it.
+pub const DEFAULT_MAX_SPEED: usize = 10;
+
+/// Speed limits of a road, which depend on its class (residential, highway...). The maximum speed
+/// must be positive.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SpeedLimit {
+    pub max_speed: usize,       // Maximum length of a step on the road.
+    pub acceleration: usize,    // Maximum speed increase per step.
+}
+
+impl Default for SpeedLimit {
+    fn default() -> SpeedLimit {
+        SpeedLimit { max_speed: DEFAULT_MAX_SPEED, acceleration: 3 }
+    }
+}
+
 /// Road information.
 #[derive(Copy, Clone)]
 pub struct RoadInfo {
@@ -12,8 +31,18 @@ pub struct RoadInfo {
     pub start: CrossroadId,     // Starting crossroad coordinates.
     pub end: CrossroadId,       // Ending crossroad coordinates.
     pub side: Side,             // Side of the road (RIGHT is the outermost road.
+    pub lane: usize,            // Lane of the road, from the innermost (0) to the outermost one.
     pub destination: NodeId,    // Destination crossroad node.
     pub length: usize,          // Length of the road, i.e. number of cars fitting in the road.
+    pub speed_limit: SpeedLimit,// Speed limits of the cars on the road.
+}
+
+/// Statistics of a road, accumulated since its creation.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RoadStats {
+    pub delivered: usize,       // Number of cars which left the road at a crossroad.
+    pub average_flow: f32,      // Average number of cars leaving the road per instant.
+    pub occupancy: f32,         // Moving average of the fraction of the places that are taken.
 }
 
 /// A simple road.
@@ -25,6 +54,8 @@ pub struct Road {
     last_index: usize,          // Last place on this road.
     average_flow: f32,          // Average number of cars leaving the road per instant.
     car_count: i32,             // Number of cars on the road.
+    delivered: usize,           // Number of cars which left the road at a crossroad.
+    occupancy: f32,             // Moving average of the fraction of the places that are taken.
 
     new_guy: bool,              // Indicates if a new car arrived on the road at the current step.
     has_moved: bool,            // Indicates if a car left the road at the current step.
@@ -40,6 +71,8 @@ impl Road {
             queue: (0..info.length).map(|_| { None }).collect(),
             average_flow: 1.,
             car_count: 0,
+            delivered: 0,
+            occupancy: 0.,
             last_index: (info.length - 1) as usize,
 
             new_guy: false,
@@ -58,6 +91,11 @@ impl Road {
         self.enabled = true;
     }
 
+    /// Returns true if the road is enabled for this step.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
     /// Returns the number of cars on this road.
     pub fn get_car_count(&self) -> i32 {
         self.car_count
@@ -68,6 +106,11 @@ impl Road {
         self.info
     }
 
+    /// Returns the statistics of the road.
+    pub fn stats(&self) -> RoadStats {
+        RoadStats { delivered: self.delivered, average_flow: self.average_flow, occupancy: self.occupancy }
+    }
+
     /// Tries to add car `car` at the end of the road.
     /// Returns `true` if it succeeded, `false` otherwise.
     pub fn add(&mut self, car: CarId) -> bool {
@@ -87,6 +130,7 @@ impl Road {
     pub fn pop(&mut self) {
         self.queue[0] = None;
         self.car_count -= 1;
+        self.delivered += 1;
         self.has_moved = true;
     }
 
@@ -106,6 +150,7 @@ impl Road {
     /// This has to be done after each end of step.
     pub fn update_status(&mut self) {
         self.average_flow = update_flow(self.average_flow, self.has_moved, self.queue[0].is_none());
+        self.occupancy = update_occupancy(self.occupancy, self.car_count, self.info.length);
         self.new_guy = false;
         self.has_moved = false;
         self.enabled = false;
@@ -113,40 +158,52 @@ impl Road {
 
     /// Returns the estimated weight of the road.
     pub fn weight(&self) -> Weight {
-        compute_weight(self.average_flow, self.info.length as f32, self.car_count)
+        compute_weight(self.average_flow, self.info.length as f32, self.car_count,
+                       self.info.speed_limit.max_speed)
+    }
+
+    /// Returns the weight of the road when it is empty, i.e. its length scaled by its speed limit.
+    pub fn free_weight(&self) -> Weight {
+        compute_weight(1., self.info.length as f32, 0, self.info.speed_limit.max_speed)
     }
 
     /// Performs a step on all possible cars on the road, returns the updated weight estimation,
     /// resets the status of the road.
-    pub fn step_forward(&mut self, moves: &mut Vec<Move>, speeds: &Vec<Speed>) -> Weight {
-        // The speed can increase at most by speed_increase per cycle.
-        let speed_increase = 3;
-
-        // The speed cannot decrease more than speed_decrease per cycle.
-        let speed_decrease = 2;
-
-        // The two following variables are a bit technical.
-        // They allow to compute the maximum allowed speed of the next car to ensure that the car
-        // never decreases its speed more than the threshold in a row.
-        let mut free_space= 0;
-        let mut last_speed = 0;
-        if self.queue[0].is_none() {
-            free_space += 1;
-            last_speed = 1;
-        }
-        for i in 1..(self.last_index+1) {
-            if self.queue[i].is_some() {
-                if last_speed > 0 {
-                    if (i == self.last_index) && self.new_guy {
-                        // The car was just added to the end of the queue.
-                        break;
-                    }
-                    let id = self.queue[i].unwrap();
-
-                    // We compute the length of the step, based on maximal allowed speed and based
-                    // on the previous speed of the car.
-                    let step = last_speed.min(speeds[id] + speed_increase);
+    ///
+    /// Cars move towards the crossroad (index 0), starting with the first one. The speed of a car
+    /// increases by at most the acceleration of the road per step, never exceeds its maximum
+    /// speed, and is bounded by a safe following distance: after its step, a car at speed `s` has
+    /// at least `s * reaction_factor` free places between it and the car ahead (which has already
+    /// moved). The first car is also bounded by the end of the road.
+    pub fn step_forward(&mut self, moves: &mut Vec<Move>, speeds: &Vec<Speed>, reaction_factor: f32)
+                        -> Weight {
+        let SpeedLimit { max_speed, acceleration } = self.info.speed_limit;
+
+        // Position of the car ahead, after its step.
+        let mut ahead: Option<usize> = None;
+        for i in 0..(self.last_index+1) {
+            if let Some(id) = self.queue[i] {
+                if (i == self.last_index) && self.new_guy {
+                    // The car was just added to the end of the queue.
+                    break;
+                }
 
+                // We compute the length of the step, based on the free places in front of the car
+                // and on the previous speed of the car.
+                let step = match ahead {
+                    Some(j) => {
+                        let gap = i - j - 1;
+                        gap.min((gap as f32 / reaction_factor) as usize)
+                    },
+                    None => i,
+                }.min(speeds[id] + acceleration).min(max_speed);
+
+                // The bounds above never let a car step past the crossroad, the clamp only makes
+                // sure a fast car cannot underflow the index.
+                debug_assert!(step <= i, "Car {} would step past the crossroad.", id);
+                let step = step.min(i);
+
+                if step > 0 {
                     // If there was some error, panics.
                     if self.queue[i - step].is_some() {
                         panic!("Just overwrote some car!");
@@ -158,18 +215,8 @@ impl Road {
 
                     // Adds the move.
                     moves[id] = Move::STEP(step as i32);
-                    free_space = 0;
-                }
-                else {
-                    free_space = 0;
-                }
-            }
-            else {
-                free_space += 1;
-                if free_space >= last_speed / speed_decrease + 1 {
-                    free_space = 0;
-                    last_speed += 1;
                 }
+                ahead = Some(i - step);
             }
         }
 
@@ -229,7 +276,93 @@ pub fn update_flow(average_flow: f32, has_moved: bool, is_no_one: bool) -> f32 {
     f32::max(new_value, 1e-12)
 }
 
-/// Returns the estimation of the real length of the road.
-pub fn compute_weight(average_flow: f32, length: f32, car_count: i32) -> Weight {
+/// Returns the updated moving average of the occupancy of a road of `length` places, on which
+/// there are `car_count` cars.
+pub fn update_occupancy(occupancy: f32, car_count: i32, length: usize) -> f32 {
+    let alpha = 0.95;
+    alpha * occupancy + (1. - alpha) * car_count as f32 / length as f32
+}
+
+/// Returns the estimation of the real length of the road. The length is scaled by the speed
+/// limit, relatively to `DEFAULT_MAX_SPEED`, so that faster roads look shorter.
+pub fn compute_weight(average_flow: f32, length: f32, car_count: i32, max_speed: usize) -> Weight {
+    let length = length * DEFAULT_MAX_SPEED as f32 / max_speed as f32;
     length.max(car_count as f32 / average_flow)
-}
\ No newline at end of file
+}
+
+#[test]
+fn test_reaction_factor_gap() {
+    // Returns the gap between two cars following each other, after a few steps.
+    let gap_after_steps = |reaction_factor: f32| {
+        let info = RoadInfo { id: 0, start: CrossroadId::new(0, 0), end: CrossroadId::new(0, 1),
+                              side: 0, lane: 0, destination: 0, length: 40,
+                              speed_limit: SpeedLimit::default() };
+        let mut road = Road::new(info);
+        road.queue[30] = Some(0);
+        road.queue[36] = Some(1);
+
+        let mut speeds = vec![2, 2];
+        for _ in 0..3 {
+            let mut moves = vec![Move::NONE, Move::NONE];
+            road.step_forward(&mut moves, &speeds, reaction_factor);
+            for (id, m) in moves.iter().enumerate() {
+                speeds[id] = match *m {
+                    Move::STEP(s) => s as usize,
+                    _ => 0,
+                };
+            }
+        }
+        let positions: Vec<usize> = (0..road.queue.len()).filter(|&i| road.queue[i].is_some()).collect();
+        positions[1] - positions[0] - 1
+    };
+
+    assert!(gap_after_steps(1.) < gap_after_steps(2.));
+    assert!(gap_after_steps(2.) < gap_after_steps(4.));
+}
+
+#[test]
+fn test_fast_car_near_crossroad() {
+    // Cars much faster than their distance to the crossroad stop right at it.
+    let speed_limit = SpeedLimit { max_speed: 1000, acceleration: 1000 };
+    let info = RoadInfo { id: 0, start: CrossroadId::new(0, 0), end: CrossroadId::new(0, 1),
+                          side: 0, lane: 0, destination: 0, length: 10, speed_limit };
+    let mut road = Road::new(info);
+    road.queue[1] = Some(0);
+    road.queue[3] = Some(1);
+
+    let speeds = vec![500, 500];
+    let mut moves = vec![Move::NONE, Move::NONE];
+    road.step_forward(&mut moves, &speeds, 0.);
+    assert_eq!(road.queue[0], Some(0));
+    assert_eq!(road.queue[1], Some(1));
+    let steps: Vec<i32> = moves.iter().map(|m| match *m { Move::STEP(s) => s, _ => 0 }).collect();
+    assert_eq!(steps, vec![1, 2]);
+}
+
+#[test]
+fn test_max_speed() {
+    let speed_limit = SpeedLimit { max_speed: 2, acceleration: 3 };
+    let info = RoadInfo { id: 0, start: CrossroadId::new(0, 0), end: CrossroadId::new(0, 1),
+                          side: 0, lane: 0, destination: 0, length: 40, speed_limit };
+    let mut road = Road::new(info);
+    road.queue[39] = Some(0);
+    road.queue[30] = Some(1);
+
+    let mut speeds = vec![0, 0];
+    for _ in 0..10 {
+        let mut moves = vec![Move::NONE, Move::NONE];
+        road.step_forward(&mut moves, &speeds, 1.);
+        for (id, m) in moves.iter().enumerate() {
+            speeds[id] = match *m {
+                Move::STEP(s) => s as usize,
+                _ => 0,
+            };
+            assert!(speeds[id] <= 2);
+        }
+    }
+    // The leading car is not slowed down by anything else.
+    assert_eq!(speeds[1], 2);
+
+    // Faster roads are preferred when they are empty.
+    assert!(compute_weight(1., 40., 0, 20) < compute_weight(1., 40., 0, DEFAULT_MAX_SPEED));
+}
@@ -0,0 +1,113 @@
+extern crate reactivers;
+
+use reactivers::engine::{Continuation, Runtime, SequentialRuntime};
+use reactivers::engine::process::*;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::{Arc, Mutex};
+
+thread_local!(static ALLOCATIONS: Cell<usize> = const { Cell::new(0) });
+
+/// Allocator counting the allocations made by each thread.
+struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|a| a.set(a.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Returns the number of allocations made by the calling thread while running `f`. Only
+/// sequential executions are measured, since the workers of a parallel runtime allocate on their
+/// own threads.
+fn count_allocations<F>(f: F) -> usize where F: FnOnce() {
+    let before = ALLOCATIONS.with(|a| a.get());
+    f();
+    ALLOCATIONS.with(|a| a.get()) - before
+}
+
+/// Calls `n` chains of three steps composed with `seq`.
+fn run_continuation_seq(n: usize) -> i32 {
+    let total = Arc::new(Mutex::new(0));
+    let total2 = total.clone();
+    let job = move |runtime: &mut Runtime, ()| {
+        for i in 0..n {
+            let total = total2.clone();
+            let store = move |_: &mut Runtime, v: i32| *total.lock().unwrap() += v;
+            let c = store.seq(|_: &mut Runtime, v: i32| v * 2)
+                .seq(|_: &mut Runtime, v: i32| v + 1);
+            c.call(runtime, i as i32);
+        }
+    };
+    SequentialRuntime::new().execute(Box::new(job), -1);
+    let total = *total.lock().unwrap();
+    total
+}
+
+/// Calls `n` chains of the same three steps, each one boxed.
+fn run_continuation_boxed(n: usize) -> i32 {
+    let total = Arc::new(Mutex::new(0));
+    let total2 = total.clone();
+    let job = move |runtime: &mut Runtime, ()| {
+        for i in 0..n {
+            let total = total2.clone();
+            let store: Box<Continuation<i32>> = Box::new(move |_: &mut Runtime, v: i32| {
+                *total.lock().unwrap() += v;
+            });
+            let double: Box<Continuation<i32>> = Box::new(move |r: &mut Runtime, v: i32| {
+                store.call_box(r, v * 2);
+            });
+            let c: Box<Continuation<i32>> = Box::new(move |r: &mut Runtime, v: i32| {
+                double.call_box(r, v + 1);
+            });
+            c.call_box(runtime, i as i32);
+        }
+    };
+    SequentialRuntime::new().execute(Box::new(job), -1);
+    let total = *total.lock().unwrap();
+    total
+}
+
+/// Runs the workload of `test_multijoin` with `n` processes in a sequential runtime.
+fn run_multijoin_sequential(n: usize) -> usize {
+    let counter = Arc::new(Mutex::new(0));
+    let processes = (0..n).map(|_| {
+        let counter = counter.clone();
+        value(()).map(move |_| { *counter.lock().unwrap() += 1; })
+    }).collect();
+
+    let p = multi_join(processes);
+    let todo = Box::new(move |runtime: &mut Runtime, ()| {
+        p.call(runtime, |_: &mut Runtime, _| {});
+    });
+    SequentialRuntime::new().execute(todo, -1);
+    let count = *counter.lock().unwrap();
+    count
+}
+
+#[test]
+fn test_continuation_seq_allocations() {
+    // Each boxed chain allocates its three steps, while the chains composed with `seq` do not
+    // allocate. The remaining allocations are shared by both runs.
+    assert_eq!(run_continuation_seq(1000), run_continuation_boxed(1000));
+    let seq = count_allocations(|| { run_continuation_seq(1000); });
+    let boxed = count_allocations(|| { run_continuation_boxed(1000); });
+    assert_eq!(boxed - seq, 3 * 1000);
+}
+
+#[test]
+fn test_multijoin_allocations() {
+    // The join continuation of each process is not boxed, so the only allocation made per process
+    // by the join is the job launching it.
+    let allocations = |n| count_allocations(|| { assert_eq!(run_multijoin_sequential(n), n); });
+    let per_process = (allocations(2000) - allocations(1000)) / 1000;
+    assert_eq!(per_process, 1);
+}
@@ -0,0 +1,64 @@
+extern crate reactivers;
+
+use reactivers::engine::{self, Continuation, Runtime};
+use reactivers::engine::process::*;
+
+/// Process waiting `instants` instants, then returning the number of the current instant.
+struct Wait {
+    instants: usize,
+}
+
+impl Process for Wait {
+    type Value = u64;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<u64> {
+        if self.instants == 0 {
+            let instant = runtime.current_instant();
+            next.call(runtime, instant);
+        } else {
+            let wait = Wait { instants: self.instants - 1 };
+            runtime.schedule_next(Box::new(move |r: &mut Runtime, ()| wait.call(r, next)));
+        }
+    }
+}
+
+/// Process returning the number of the current instant, seen at the end of the instant.
+struct AtEnd;
+
+impl Process for AtEnd {
+    type Value = u64;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<u64> {
+        runtime.schedule_end(Box::new(move |r: &mut Runtime, ()| {
+            let instant = r.current_instant();
+            next.call(r, instant);
+        }));
+    }
+}
+
+/// Process returning the number of the instant in which its continuation is scheduled.
+struct Now;
+
+impl Process for Now {
+    type Value = u64;
+
+    fn call<C>(self, runtime: &mut Runtime, next: C) where C: Continuation<u64> {
+        runtime.schedule_current(Box::new(move |r: &mut Runtime, ()| {
+            let instant = r.current_instant();
+            next.call(r, instant);
+        }));
+    }
+}
+
+#[test]
+fn test_custom_wait() {
+    assert_eq!(engine::execute_process(Wait { instants: 3 }), 3);
+}
+
+#[test]
+fn test_custom_end_of_instant() {
+    // The end of instant still sees instant 0, but continuations it schedules on the current
+    // instant run during instant 1.
+    let p = AtEnd.and_then(|end| Now.map(move |now| (end, now)));
+    assert_eq!(engine::execute_process(p), (0, 1));
+}